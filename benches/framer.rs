@@ -0,0 +1,101 @@
+#![cfg_attr(all(feature = "bench", test), feature(test))]
+
+// Run with: cargo +nightly bench --features bench --bench framer
+//
+// Each iteration reads SENTENCES sentences from a file, so sentences per second is
+// SENTENCES / (ns/iter) * 1_000_000_000.
+
+#[cfg(all(feature = "bench", test))]
+mod bench {
+    extern crate test;
+    use test::Bencher;
+
+    use std::fs::{remove_file, File};
+    use std::io::{ErrorKind, Read, Write};
+    use std::str;
+    use std::time::{Duration, Instant};
+
+    use adafruit_gps::{Gps, GpsError};
+
+    const CAPTURE: &str = "$GNGGA,131613.000,5132.7314,N,00005.9099,W,1,9,1.17,42.4,M,47.0,M,,*60\r\n\
+$GPGSA,A,3,29,02,26,25,31,14,,,,,,,1.42,1.17,0.80*07\r\n\
+$GPGSV,4,3,14,12,12,100,,04,11,331,,16,06,282,,05,05,074,22*75\r\n\
+$GNRMC,131613.000,A,5132.7314,N,00005.9099,W,0.11,0.00,250520,,,A*6D\r\n\
+$GNVTG,0.00,T,,M,0.11,N,0.20,K,A*21\r\n";
+
+    const SENTENCES: usize = 1_000;
+
+    fn write_capture(path: &str) -> u64 {
+        let mut file = File::create(path).unwrap();
+        for _ in 0..SENTENCES / 5 {
+            file.write_all(CAPTURE.as_bytes()).unwrap();
+        }
+        file.metadata().unwrap().len()
+    }
+
+    /// The reader before the Framer: one byte per read call.
+    fn read_line_byte_at_a_time<R: Read>(port: &mut R) -> Result<String, GpsError> {
+        let mut buffer: Vec<u8> = vec![0; 1];
+        let mut output: Vec<u8> = Vec::new();
+        let start = Instant::now();
+        loop {
+            if start.elapsed() > Duration::from_secs(1) {
+                return Err(GpsError::Timeout);
+            }
+            match port.read(buffer.as_mut_slice()) {
+                Ok(0) => {
+                    if output.is_empty() {
+                        return Err(GpsError::Timeout);
+                    }
+                    break;
+                }
+                Ok(buffer_size) => {
+                    output.extend_from_slice(&buffer[..buffer_size]);
+                    if output.last() == Some(&10u8) || output.len() > 255 {
+                        break;
+                    }
+                }
+                Err(e) => match e.kind() {
+                    ErrorKind::TimedOut | ErrorKind::WouldBlock | ErrorKind::Interrupted => (),
+                    _ => return Err(GpsError::Io(e)),
+                },
+            }
+        }
+        match str::from_utf8(&output) {
+            Ok(string) => Ok(string.to_string()),
+            Err(_e) => Err(GpsError::InvalidBytes(output)),
+        }
+    }
+
+    #[bench]
+    fn bench_byte_at_a_time(b: &mut Bencher) {
+        b.bytes = write_capture("bench_framer_old");
+        b.iter(|| {
+            let mut file = File::open("bench_framer_old").unwrap();
+            let mut count = 0;
+            while read_line_byte_at_a_time(&mut file).is_ok() {
+                count += 1;
+            }
+            assert_eq!(count, SENTENCES);
+        });
+        let _ = remove_file("bench_framer_old");
+    }
+
+    #[bench]
+    fn bench_framer(b: &mut Bencher) {
+        b.bytes = write_capture("bench_framer_new");
+        b.iter(|| {
+            let mut gps = Gps::from_file("bench_framer_new").unwrap();
+            let mut count = 0;
+            while gps.read_line().is_ok() {
+                count += 1;
+            }
+            assert_eq!(count, SENTENCES);
+        });
+        let _ = remove_file("bench_framer_new");
+    }
+}
+
+// 1_000 sentences, read from a file
+// test bench::bench_byte_at_a_time ... bench:  37,185,721 ns/iter (+/- 7,240,900) = 1 MB/s    ~27,000 sentences/s
+// test bench::bench_framer         ... bench:     507,400 ns/iter (+/- 37,176) = 117 MB/s   ~1,970,000 sentences/s
@@ -9,9 +9,10 @@ mod bench {
 
     use adafruit_gps::GpsSentence;
     use adafruit_gps::gga::{GgaData, SatFix};
+    use adafruit_gps::{Talker, UtcTime};
 
     const SENTENCE: GpsSentence = GpsSentence::GGA(GgaData {
-        utc: 100.0,
+        utc: Some(UtcTime { hour: 0, minute: 1, second: 0, millisecond: 0 }),
         lat: Some(51.55465),
         long: Some(-0.05632),
         sat_fix: SatFix::DgpsFix,
@@ -20,6 +21,7 @@ mod bench {
         msl_alt: Some(42.53),
         geoidal_sep: Some(47.0),
         age_diff_corr: None,
+        talker: Talker::Gps,
     });
 
     const VECTOR_SIZE: i32 = 1_000;
@@ -33,7 +35,7 @@ mod bench {
 
         b.iter(|| {
             for s in v.iter() {
-                s.clone().append_to("bench_test1")
+                s.append_to("bench_test1").unwrap()
             }
             let _ = remove_file("bench_test1");
         });
@@ -43,7 +45,7 @@ mod bench {
     fn bench_append(b: &mut Bencher) {
         b.iter(|| {
             for _ in 0..VECTOR_SIZE {
-                SENTENCE.append_to("bench_test2")
+                SENTENCE.append_to("bench_test2").unwrap()
             }
             let _ = remove_file("bench_test2");
         });
@@ -53,7 +55,7 @@ mod bench {
     fn bench_append_single_struct(b: &mut Bencher) {
         b.iter(|| {
             for _ in 0..1 {
-                SENTENCE.append_to("bench_test2")
+                SENTENCE.append_to("bench_test2").unwrap()
             }
             let _ = remove_file("bench_test2");
         });
@@ -70,11 +72,11 @@ mod bench {
     #[bench]
     fn bench_read(b: &mut Bencher) {
         for _ in 0..VECTOR_SIZE {
-            SENTENCE.append_to("bench_read")
+            SENTENCE.append_to("bench_read").unwrap()
         }
 
         b.iter(|| {
-            GpsSentence::read_from("bench_read")
+            GpsSentence::read_from("bench_read").unwrap()
         });
         let _ = remove_file("bench_read");
     }
@@ -1,26 +1,35 @@
-use adafruit_gps::{Gps, GpsSentence};
+use adafruit_gps::{read_log, Gps, GpsSentence, LogWriter, SentenceIter};
 use adafruit_gps::NmeaOutput;
 
 fn main() {
-    let mut gps = Gps::new("/dev/serial0", "9600");
+    let mut gps = Gps::new("/dev/serial0", "9600").expect("Could not open the gps port");
     gps.pmtk_220_set_nmea_updaterate("1000");
-    gps.pmtk_314_api_set_nmea_output(NmeaOutput{ gll: 1, rmc: 0, vtg: 0, gga: 0, gsa: 1, gsv: 0, pmtkchn_interval: 0 });
+    gps.pmtk_314_api_set_nmea_output(NmeaOutput{ gll: 1, rmc: 0, vtg: 0, gga: 0, gsa: 1, gsv: 0, zda: 0, pmtkchn_interval: 0 });
 
-    for _ in 0..100 {
-        let values = gps.update();
-        values.append_to("main_test");
+    // Save the first 100 sentences with data.
+    for values in gps.sentences().valid().take(100) {
+        values.append_to("main_test").expect("Could not write to main_test");
     }
 
-    // Read a file of structs. Always gives it as a vector.
-    let gps: Vec<GpsSentence> = GpsSentence::read_from("main_test");
+    // Read a file of structs. Always gives it as a vector, skipping any corrupt records.
+    let gps: Vec<GpsSentence> = GpsSentence::read_from("main_test").expect("Could not read main_test");
     println!("{:?}", gps);
 
-    // If you have a Vec<GpsSentence> and you wish to save it, do the following:
-    // v is the Vec<GpsSentence>. The reason you have to do this is I don't want to implement a
-    // a trait on Vec<GpsSentence>.
+    // The same adapters work on any iterator of GpsSentence, such as a file read back.
+    let gsa_count = gps.into_iter().gsa().count();
+    println!("{} GSA sentences", gsa_count);
+
+    // The receive time of each sentence, and any corrupt records that were skipped.
+    let log = read_log("main_test").expect("Could not read main_test");
+    for error in log.errors.iter() {
+        println!("{}", error);
+    }
+
+    // If you have a Vec<GpsSentence> and you wish to save it, keep the file open with a LogWriter:
     let v: Vec<GpsSentence> = Vec::new();
+    let mut writer = LogWriter::open("bench_test1").expect("Could not open bench_test1");
     for s in v.iter() {
-        s.clone().append_to("bench_test1")
+        writer.write(s).expect("Could not write to bench_test1");
     }
 
 }
\ No newline at end of file
@@ -12,7 +12,8 @@ fn main() {
     let port = args.get(2).unwrap();
 
     // Open the port that is connected to the GPS module.
-    let mut gps = Gps::new(port, baud_rate);
+    // Gives a GpsError if the port is not found or the baud rate is not a number.
+    let mut gps = Gps::new(port, baud_rate).expect("Could not open the gps port");
 
     // gps.init() requires the update rate for the gps (1000 miliseconds (1Hz) is default)
     // It returns a hash map to tell you if setting the update rate was successful and if the
@@ -23,15 +24,14 @@ fn main() {
     // If setting the update_rate consistently fails for faster updates, see exmaples/increase_frequency.rs
 
     // Give settings here.
-    gps.pmtk_314_api_set_nmea_output(NmeaOutput{gga: 1, gsa: 1, gsv: 1,  gll: 1, rmc: 1, vtg: 1, pmtkchn_interval: 1 });
+    gps.pmtk_314_api_set_nmea_output(NmeaOutput{gga: 1, gsa: 1, gsv: 1,  gll: 1, rmc: 1, vtg: 1, zda: 0, pmtkchn_interval: 1 });
     let r = gps.pmtk_220_set_nmea_updaterate("1000");
     println!("{:?}", r);
 
-    // In a loop, constantly update the gps. The update trait will give you all the data you
-    // want from the gps module.
-    loop {
-        let values = gps.update();
-
+    // gps.sentences() is an iterator of gps.update(), which gives you all the data you want from
+    // the gps module. Use .valid() to skip NoConnection, InvalidBytes and InvalidSentence, or
+    // .gga(), .rmc() etc. to only get one sentence type.
+    for values in gps.sentences() {
         // Depending on what values you are interested in you can adjust what sentences you
         // wish to get and ignore all other sentences.
         match values {
@@ -40,16 +40,14 @@ fn main() {
             GpsSentence::NoConnection => println!("No connection with gps"),
             GpsSentence::GGA(sentence) => {
                 println!("UTC: {}\nLat:{}, Long:{}, Sats:{}, MSL Alt:{}",
-                         sentence.utc, sentence.lat.unwrap_or(0.0), sentence.long.unwrap_or(0.0), sentence.satellites_used,
+                         sentence.utc.unwrap_or_default(), sentence.lat.unwrap_or(0.0), sentence.long.unwrap_or(0.0), sentence.satellites_used,
                 sentence.msl_alt.unwrap_or(0.0));
             }
             GpsSentence::GSA(sentence) => {
                 println!("PDOP:{}, VDOP:{}, HDOP:{}",
                          sentence.pdop.unwrap_or(0.0), sentence.vdop.unwrap_or(0.0), sentence.hdop.unwrap_or(0.0))
             }
-            _ => {
-                ()
-            }
+            _ => {}
         }
     }
 }
\ No newline at end of file
@@ -59,16 +59,13 @@ pub fn inverse_vincenty(start: &Coordinate, end: &Coordinate) -> f64 {
         let C = f / 16_f64 * cos_sq_alpha * (4_f64 + f * (4_f64 - 3.0 * cos_sq_alpha));
 
         let prev_Y = Y;
-        Y = &L + (1_f64 - C) * f * sin_alpha * (sigma + C * sin_sigma *
+        Y = L + (1_f64 - C) * f * sin_alpha * (sigma + C * sin_sigma *
             (cos_2_sigma_m + C * cos_sigma * (-1_f64 + 2_f64 * cos_2_sigma_m.powi(2))));
 
-        if Y - prev_Y < 1e-12 {
+        if Y - prev_Y < 1e-12 || counter > max_iter {
             break (cos_sq_alpha, sin_sigma, cos_2_sigma_m, cos_sigma, sigma);
-        } else if counter > max_iter {
-            break (cos_sq_alpha, sin_sigma, cos_2_sigma_m, cos_sigma, sigma);
-        } else {
-            counter += 1;
         }
+        counter += 1;
     };
 
     let uSq = cos_sq_alpha * ((a.powi(2) - b.powi(2)) / b.powi(2));
@@ -78,9 +75,7 @@ pub fn inverse_vincenty(start: &Coordinate, end: &Coordinate) -> f64 {
         (-1_f64 + 2_f64 * cos_2_sigma_m.powi(2)) - B / 6_f64 * cos_2_sigma_m *
         (-3_f64 + 4_f64 * sin_sigma.powi(2)) * (-3_f64 + 4_f64 * cos_2_sigma_m.powi(2))));
 
-    let s = b * A * (sigma - delta_sigma);
-
-    return s;
+    b * A * (sigma - delta_sigma)
 }
 
 /// # Haversine
@@ -89,15 +84,14 @@ pub fn inverse_vincenty(start: &Coordinate, end: &Coordinate) -> f64 {
 ///
 /// (Haversine wiki) [https://en.wikipedia.org/wiki/Haversine_formula]
 pub fn haversine(start: &Coordinate, end: &Coordinate) -> f64 {
-    let lat1 = start.latitude.unwrap().to_radians() as f64;
-    let lat2 = end.latitude.unwrap().to_radians() as f64;
-    let long1 = start.longitude.unwrap().to_radians() as f64;
-    let long2 = end.longitude.unwrap().to_radians() as f64;
+    let lat1 = (start.latitude.unwrap() as f64).to_radians();
+    let lat2 = (end.latitude.unwrap() as f64).to_radians();
+    let long1 = (start.longitude.unwrap() as f64).to_radians();
+    let long2 = (end.longitude.unwrap() as f64).to_radians();
     let mean_earth_radius = 6371008.8; // https://en.wikipedia.org/wiki/Earth_radius#Global_average_radii
     let havlat = ((lat2 - lat1) / 2_f64).sin().powi(2);
     let havlong = ((long2 - long1) / 2_f64).sin().powi(2);
-    let distance = ((havlat + lat1.cos() * lat2.cos() * havlong).sqrt()).asin() * 2_f64 * mean_earth_radius;
-    return distance;
+    ((havlat + lat1.cos() * lat2.cos() * havlong).sqrt()).asin() * 2_f64 * mean_earth_radius
 }
 
 pub trait DeltaCoordinates {
@@ -109,22 +103,22 @@ impl DeltaCoordinates for Vec<Coordinate> {
     fn vincenty(&self) -> Vec<(f64, f64)> {
         let mut return_vec: Vec<(f64, f64)> = Vec::new();
         for t in 0..self.len() - 1 {
-            let time_diff = self.get(t+1).unwrap().utc - self.get(t).unwrap().utc;
+            let time_diff = self[t + 1].utc.seconds_since(&self[t].utc);
             let mut d = inverse_vincenty(self.get(t).unwrap(), self.get(t + 1).unwrap());
             d = (d.powi(2) + (self.get(t).unwrap().altitude.unwrap() - self.get(t + 1).unwrap().altitude.unwrap()).powi(2) as f64).sqrt();
             return_vec.push((time_diff, d))
         }
-        return return_vec;
+        return_vec
     }
     fn haversine(&self) -> Vec<(f64, f64)> {
         let mut return_vec: Vec<(f64, f64)> = Vec::new();
         for t in 0..self.len() - 1 {
-            let time_diff = self.get(t+1).unwrap().utc - self.get(t).unwrap().utc;
+            let time_diff = self[t + 1].utc.seconds_since(&self[t].utc);
             let mut d = haversine(self.get(t).unwrap(), self.get(t + 1).unwrap());
             d = (d.powi(2) + (self.get(t).unwrap().altitude.unwrap() - self.get(t + 1).unwrap().altitude.unwrap()).powi(2) as f64).sqrt();
             return_vec.push((time_diff, d))
         }
-        return return_vec;
+        return_vec
     }
 }
 
@@ -139,7 +133,7 @@ impl Kinematics for Vec<(f64, f64)> {
         for (_time_diff, distance) in self.iter() {
             return_vec.push(*distance)
         }
-        return return_vec;
+        return_vec
     }
 
     fn speed(&self) -> Vec<f64> {
@@ -147,22 +141,29 @@ impl Kinematics for Vec<(f64, f64)> {
         for (time_diff, distance) in self.iter() {
             return_vec.push(distance/time_diff)
         }
-        return return_vec;
+        return_vec
     }
 }
 
 
 #[cfg(test)]
 mod test_distances {
+    use adafruit_gps::UtcTime;
+
     use super::{haversine, inverse_vincenty, Coordinate, DeltaCoordinates, Kinematics};
 
-    const SMALL1: Coordinate = Coordinate { utc: (0.0), latitude: Some(51.55814), longitude: Some(0.02955), altitude: Some(0.0) };
-    const SMALL2: Coordinate = Coordinate { utc: (0.0), latitude: Some(51.55795), longitude: Some(0.03014), altitude: Some(100.0) };
-    const SMALL3: Coordinate = Coordinate { utc: (0.0), latitude: Some(51.55795), longitude: Some(0.03014), altitude: Some(0.0) };
+    const MIDNIGHT: UtcTime = UtcTime { hour: 0, minute: 0, second: 0, millisecond: 0 };
 
-    const LONDON: Coordinate = Coordinate { utc: (0.0), latitude: Some(51.500821), longitude: Some(-0.126670), altitude: Some(0.0) };
-    const PARIS: Coordinate = Coordinate { utc: (0.0), latitude: Some(48.858788), longitude: Some(2.293746), altitude: Some(0.0) };
-    const SYDNEY: Coordinate = Coordinate { utc: (0.0), latitude: Some(-33.852239), longitude: Some(151.210675), altitude: Some(0.0) };
+    const SMALL1: Coordinate = Coordinate { utc: MIDNIGHT, latitude: Some(51.55814), longitude: Some(0.02955), altitude: Some(0.0) };
+    const SMALL2: Coordinate = Coordinate { utc: MIDNIGHT, latitude: Some(51.55795), longitude: Some(0.03014), altitude: Some(100.0) };
+    const SMALL3: Coordinate = Coordinate { utc: MIDNIGHT, latitude: Some(51.55795), longitude: Some(0.03014), altitude: Some(0.0) };
+
+    // Coordinates as given by the map, more precise than the f32 they are stored in.
+    #[allow(clippy::excessive_precision)]
+    const LONDON: Coordinate = Coordinate { utc: MIDNIGHT, latitude: Some(51.500821), longitude: Some(-0.126670), altitude: Some(0.0) };
+    const PARIS: Coordinate = Coordinate { utc: MIDNIGHT, latitude: Some(48.858788), longitude: Some(2.293746), altitude: Some(0.0) };
+    #[allow(clippy::excessive_precision)]
+    const SYDNEY: Coordinate = Coordinate { utc: MIDNIGHT, latitude: Some(-33.852239), longitude: Some(151.210675), altitude: Some(0.0) };
 
     #[test]
     fn vincenty_same_point() {
@@ -173,14 +174,14 @@ mod test_distances {
     #[test]
     fn vincenty_small_no_alt() {
         let locations: Vec<Coordinate> = vec![SMALL1, SMALL3];
-        let cal = locations.vincenty().distance().get(0).unwrap().round();
+        let cal = locations.vincenty().distance().first().unwrap().round();
         assert_eq!(cal, 46.0)
     }
 
     #[test]
     fn vincenty_small() {
         let locations: Vec<Coordinate> = vec![SMALL1, SMALL2];
-        let cal = locations.vincenty().distance().get(0).unwrap().round();
+        let cal = locations.vincenty().distance().first().unwrap().round();
         assert_eq!(cal, 110.0)
     }
 
@@ -205,14 +206,14 @@ mod test_distances {
     #[test]
     fn haversine_small() {
         let locations: Vec<Coordinate> = vec![SMALL1, SMALL2];
-        let cal = locations.haversine().distance().get(0).unwrap().round();
+        let cal = locations.haversine().distance().first().unwrap().round();
         assert_eq!(cal, 110.0)
     }
 
     #[test]
     fn haversine_small_no_alt() {
         let locations: Vec<Coordinate> = vec![SMALL1, SMALL3];
-        let cal = locations.haversine().distance().get(0).unwrap().round();
+        let cal = locations.haversine().distance().first().unwrap().round();
         assert_eq!(cal, 46.0)
     }
 
@@ -228,4 +229,14 @@ mod test_distances {
         assert_eq!(cal, 16992936.0)
     }
 
+    #[test]
+    fn speed_over_midnight() {
+        let mut before = SMALL1;
+        before.utc = UtcTime { hour: 23, minute: 59, second: 59, millisecond: 0 };
+        let mut after = SMALL3;
+        after.utc = UtcTime { hour: 0, minute: 0, second: 1, millisecond: 0 };
+        let delta = vec![before, after].haversine();
+        assert_eq!(delta[0].0, 2.0);
+        assert_eq!(delta.speed()[0].round(), 23.0);
+    }
 }
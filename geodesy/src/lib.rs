@@ -1,26 +1,40 @@
-/// # Geodesy library for all that extra fun post data gathering analysis.
-///
-/// One to work out the distance between two points.
-/// Make a structure of a series of points and implement.
-/// At any point you are doing stuff to a series of points.
+//! # Geodesy library for all that extra fun post data gathering analysis.
+//!
+//! One to work out the distance between two points.
+//! Make a structure of a series of points and implement.
+//! At any point you are doing stuff to a series of points.
 
 //todo - size of error for a long lat: 51.0, 1.0 is x m^2 area.
 // todo - expected distance error for a given pdop.
 
+use adafruit_gps::{LocusFix, UtcTime};
+
 pub mod kinematics;
 pub mod position;
 
 /// This is the basic coordinate data for a single point in space.
 ///
-/// - UTC is used when calculating speed (relative UTC is needed)
+/// - UTC is used when calculating speed (relative UTC is needed). Times after midnight are taken as
+///   the next day.
 /// - altitude is used when measuring distance and actually calculates euclidian distance between
-/// points. If not required just put altitude to 0 and it will not affect calculations.
+///   points. If not required just put altitude to 0 and it will not affect calculations.
 #[derive(Default, PartialEq, Debug)]
 pub struct Coordinate {
-    pub utc: f64,
+    pub utc: UtcTime,
     pub latitude: Option<f32>,
     pub longitude: Option<f32>,
     pub altitude: Option<f32>,
 }
 
-
+impl From<&LocusFix> for Coordinate {
+    /// A fix logged by LOCUS, with its height as the altitude. Fixes with no time, logged without the
+    /// UTC content bit, are given 00:00:00. LocusConverter::to_coords() skips them instead.
+    fn from(fix: &LocusFix) -> Coordinate {
+        Coordinate {
+            utc: fix.utc.map(|utc| utc.time).unwrap_or_default(),
+            latitude: fix.latitude,
+            longitude: fix.longitude,
+            altitude: fix.height,
+        }
+    }
+}
@@ -1,11 +1,9 @@
-use plotters::prelude::*;
-
-use adafruit_gps::GpsSentence;
+//! # Position Accuracy
+//! Given a set of coordinates, produce the average longitude and latitude,
 
-use crate::kinematics::{inverse_vincenty};
+use plotters::prelude::*;
 
-/// # Position Accuracy
-/// Given a set of coordinates, produce the average longitude and latitude,
+use adafruit_gps::{GpsSentence, LocusFix, UtcTime};
 
 use super::Coordinate;
 use std::fs::File;
@@ -29,19 +27,19 @@ impl GpsSentenceConverter for Vec<GpsSentence> {
             match s {
                 GpsSentence::GGA(sentence) => {
                     let mut gga = Coordinate {
-                        utc: sentence.utc,
+                        utc: sentence.utc.unwrap_or_default(),
                         latitude: sentence.lat,
                         longitude: sentence.long,
                         altitude: sentence.msl_alt,
                     };
-                    if include_geoidal_separation && gga.altitude.is_some() && sentence.geoidal_sep.is_some(){
-                        gga.altitude = Some(gga.altitude.unwrap() + sentence.geoidal_sep.unwrap());
+                    if let (true, Some(altitude), Some(sep)) = (include_geoidal_separation, gga.altitude, sentence.geoidal_sep) {
+                        gga.altitude = Some(altitude + sep);
                     }
                     vec_coord.push(gga);
                 }
                 GpsSentence::GLL(sentence) => {
                     vec_coord.push(Coordinate {
-                        utc: sentence.utc.unwrap_or(0.0),
+                        utc: sentence.utc.unwrap_or_default(),
                         latitude: sentence.latitude,
                         longitude: sentence.longitude,
                         altitude: None,
@@ -49,7 +47,7 @@ impl GpsSentenceConverter for Vec<GpsSentence> {
                 }
                 GpsSentence::RMC(sentence) => {
                     vec_coord.push(Coordinate {
-                        utc: sentence.utc,
+                        utc: sentence.utc.unwrap_or_default(),
                         latitude: sentence.latitude,
                         longitude: sentence.longitude,
                         altitude: None,
@@ -62,6 +60,22 @@ impl GpsSentenceConverter for Vec<GpsSentence> {
     }
 }
 
+pub trait LocusConverter {
+    fn to_coords(&self) -> Vec<Coordinate>;
+}
+
+impl LocusConverter for Vec<LocusFix> {
+    /// Converts the fixes decoded from a LOCUS dump to `Vec<Coordinate>`, with the height as the
+    /// altitude. Fixes with no time, position or height are skipped, as they can not be used for
+    /// speed or plotted.
+    fn to_coords(&self) -> Vec<Coordinate> {
+        self.iter()
+            .filter(|fix| fix.utc.is_some() && fix.latitude.is_some() && fix.longitude.is_some() && fix.height.is_some())
+            .map(Coordinate::from)
+            .collect()
+    }
+}
+
 pub trait Position {
     fn average_long_lat(&self) -> Coordinate;
     fn plot_positions(&self, name: &str);
@@ -84,14 +98,16 @@ impl Position for Vec<Coordinate> {
         let mut altitude: f64 = 0.0;
 
         for location in self.iter() {
-            x += (location.latitude.unwrap().cos() * location.longitude.unwrap().cos()) as f64;
-            y += (location.latitude.unwrap().cos() * location.longitude.unwrap().sin()) as f64;
-            z += location.latitude.unwrap().sin() as f64;
+            let latitude = (location.latitude.unwrap() as f64).to_radians();
+            let longitude = (location.longitude.unwrap() as f64).to_radians();
+            x += latitude.cos() * longitude.cos();
+            y += latitude.cos() * longitude.sin();
+            z += latitude.sin();
             altitude += location.altitude.unwrap() as f64;
         }
-        x = x / self.len() as f64;
-        y = y / self.len() as f64;
-        z = z / self.len() as f64;
+        x /= self.len() as f64;
+        y /= self.len() as f64;
+        z /= self.len() as f64;
 
         let central_long = y.atan2(x);
         let central_sq_rt = (x * x + y * y).sqrt();
@@ -101,27 +117,27 @@ impl Position for Vec<Coordinate> {
         let average_lat = central_lat.to_degrees();
         let average_alt: f64 = altitude / self.len() as f64;
 
-        return Coordinate {
+        Coordinate {
             latitude: Some(average_lat as f32),
             longitude: Some(average_long as f32),
             altitude: Some(average_alt as f32),
-            utc: 0.0,
-        };
+            utc: UtcTime::default(),
+        }
     }
 
 
     /// Plots the longitude and latitiude coordinates on a 2d graph using plotters.
     fn plot_positions(&self, name: &str) {
-        let mut positions: Vec<(f32, f32)> = self.into_iter()
+        let mut positions: Vec<(f32, f32)> = self.iter()
             .map(|x| (x.longitude.unwrap(), x.latitude.unwrap())).collect();
         positions.retain(|x| *x != (0.0, 0.0));  // Remove all (0,0) coords.
 
         let latitudes: Vec<f32> = positions.clone().into_iter().map(|x| x.0).collect();
         let longitudes: Vec<f32> = positions.clone().into_iter().map(|x| x.1).collect();
-        let min_long = longitudes.iter().cloned().fold(0. / 0., f32::min);
-        let max_long = longitudes.iter().cloned().fold(0. / 0., f32::max);
-        let min_lat = latitudes.iter().cloned().fold(0. / 0., f32::min);
-        let max_lat = latitudes.iter().cloned().fold(0. / 0., f32::max);
+        let min_long = longitudes.iter().cloned().fold(f32::NAN, f32::min);
+        let max_long = longitudes.iter().cloned().fold(f32::NAN, f32::max);
+        let min_lat = latitudes.iter().cloned().fold(f32::NAN, f32::min);
+        let max_lat = latitudes.iter().cloned().fold(f32::NAN, f32::max);
 
         // // x axis is
         // let _x_axis = inverse_vincenty(
@@ -156,10 +172,10 @@ impl Position for Vec<Coordinate> {
     fn to_klm(&self, name: &str, description: &str) -> std::io::Result<()>{
         let mut coordinates = String::new();
         for c in self.iter() {
-            if c.latitude.is_some() && c.longitude.is_some() && c.altitude.is_some() {
+            if let (Some(latitude), Some(longitude), Some(altitude)) = (c.latitude, c.longitude, c.altitude) {
                 coordinates.push_str(format!(
                     "{},{},{}
-                    ", c.longitude.unwrap(), c.latitude.unwrap(), c.altitude.unwrap()).as_str());
+                    ", longitude, latitude, altitude).as_str());
             }
         }
 
@@ -200,3 +216,66 @@ impl Position for Vec<Coordinate> {
     }
 }
 
+
+#[cfg(test)]
+mod test_position {
+    use adafruit_gps::UtcTime;
+
+    use super::{Coordinate, Position};
+
+    fn coordinate(latitude: f32, longitude: f32, altitude: f32) -> Coordinate {
+        Coordinate { utc: UtcTime::default(), latitude: Some(latitude), longitude: Some(longitude), altitude: Some(altitude) }
+    }
+
+    #[test]
+    fn average_long_lat() {
+        let average = vec![coordinate(51.5, -0.1, 0.0), coordinate(51.6, -0.1, 10.0)].average_long_lat();
+        assert!((average.latitude.unwrap() - 51.55).abs() < 0.0001);
+        assert!((average.longitude.unwrap() - -0.1).abs() < 0.0001);
+        assert_eq!(average.altitude, Some(5.0));
+    }
+
+    #[test]
+    fn average_across_date_line() {
+        let average = vec![coordinate(0.0, 179.0, 0.0), coordinate(0.0, -179.0, 0.0)].average_long_lat();
+        assert!(average.latitude.unwrap().abs() < 0.0001);
+        assert!((average.longitude.unwrap().abs() - 180.0).abs() < 0.0001);
+    }
+}
+
+#[cfg(test)]
+mod test_locus {
+    use std::fs::{read_to_string, remove_file};
+
+    use adafruit_gps::{Gps, LocusFix};
+
+    use super::{LocusConverter, Position};
+
+    /// Fixes decoded from a LOCUS dump of the basic content: UTC, fix, latitude, longitude, height.
+    /// The dump is the hand-assembled one used by the locus tests, not a module capture.
+    fn fixes() -> Vec<LocusFix> {
+        let mut gps = Gps::from_bytes(include_bytes!("../../tests/data/locus-basic.nmea"));
+        gps.pmtk_622_locus_dump().unwrap().decode().fixes
+    }
+
+    #[test]
+    fn locus_to_coords() {
+        let mut fixes = fixes();
+        fixes.push(LocusFix { utc: None, ..fixes[0].clone() });
+        let coords = fixes.to_coords();
+        assert_eq!(coords.len(), 5);
+        assert_eq!(coords[4].utc.seconds_since(&coords[0].utc), 75.0);
+
+        let average = coords.average_long_lat();
+        assert!((average.latitude.unwrap() - 51.5457).abs() < 0.0001);
+        assert!((average.longitude.unwrap() - -0.0990).abs() < 0.0001);
+        assert!((average.altitude.unwrap() - 44.4).abs() < 0.01);
+
+        coords.to_klm("locus_test", "LOCUS dump").unwrap();
+        let klm = read_to_string("locus_test.kml");
+        let _ = remove_file("locus_test.kml");
+        let klm = klm.unwrap();
+        assert!(klm.contains("<coordinates> -0.0985,51.5455,42\n"));
+        assert!(klm.contains("-0.0995,51.546,47\n"));
+    }
+}
@@ -0,0 +1,371 @@
+//! # Async Gps
+//! Reads the gps over any tokio AsyncRead + AsyncWrite port, so a receiver does not need its own
+//! thread. Needs the `async` feature.
+//!
+//! Parsing is shared with the blocking Gps, so the sentences given are the same.
+
+pub mod gps {
+    //! AsyncGps: sentences as a Stream, and async versions of the basic PMTK commands.
+    use std::time::Duration;
+
+    use futures_core::Stream;
+    use futures_util::stream;
+    use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+    use crate::error::GpsError;
+    use crate::framing::check::{check_sentence, Verdict};
+    use crate::framing::framer::Framer;
+    use crate::nmea::gsv::GsvAssembler;
+    use crate::open_gps::gps::{parse_line, sky_view, GpsSentence, Parsed};
+    use crate::parser::registry::{Registry, SentenceParser};
+    use crate::pmtk::send_pmtk::{add_checksum, parse_pmtk_001, parse_pmtk_500, Pmtk001Ack};
+
+    /// Most bytes asked of the port per read call.
+    const READ_SIZE: usize = 1024;
+
+    /// # AsyncGps
+    /// The async version of [Gps](../../struct.Gps.html), over a tokio port such as a serial port
+    /// stream or a duplex stream for testing.
+    ///
+    /// - sentences() -> Stream of Result<GpsSentence, GpsError>.
+    /// - update() -> The next GpsSentence, the same as gps.update().
+    /// - send_command(), pmtk_001() and pmtk_500() -> The same as the blocking Gps.
+    pub struct AsyncGps<T: AsyncRead + AsyncWrite + Unpin> {
+        pub port: T,
+        framer: Framer,
+        timeout: Duration,
+        parsers: Registry,
+        gsv: GsvAssembler,
+    }
+
+    impl<T: AsyncRead + AsyncWrite + Unpin> AsyncGps<T> {
+        /// Use any tokio AsyncRead + AsyncWrite port to talk to the gps.
+        pub fn from_port(port: T) -> AsyncGps<T> {
+            AsyncGps {
+                port,
+                framer: Framer::new(),
+                timeout: Duration::from_secs(1),
+                parsers: Registry::new(),
+                gsv: GsvAssembler::new(),
+            }
+        }
+
+        /// Parse sentences with the given address with parser. The same as gps.register().
+        pub fn register<P: SentenceParser + 'static>(&mut self, address: &str, parser: P) {
+            self.parsers.register(address, parser);
+        }
+
+        /// The parsers used by update(), to remove or replace them.
+        pub fn parsers_mut(&mut self) -> &mut Registry {
+            &mut self.parsers
+        }
+
+        /// How long read_line waits for a full sentence before giving GpsError::Timeout.
+        /// Default is 1 second.
+        pub fn set_timeout(&mut self, timeout: Duration) {
+            self.timeout = timeout;
+        }
+
+        /// Reads the next line. None when the port is closed and there is nothing left to read.
+        async fn read_frame(&mut self) -> Result<Option<Vec<u8>>, GpsError> {
+            self.read_frame_until(tokio::time::Instant::now() + self.timeout).await
+        }
+
+        /// read_frame, but waiting until the deadline instead of the timeout.
+        async fn read_frame_until(&mut self, deadline: tokio::time::Instant) -> Result<Option<Vec<u8>>, GpsError> {
+            let mut chunk = [0u8; READ_SIZE];
+            loop {
+                if let Some(line) = self.framer.next_line() {
+                    return Ok(Some(line));
+                }
+                let read = tokio::time::timeout_at(deadline, self.port.read(&mut chunk));
+                match read.await {
+                    Err(_elapsed) => return Err(GpsError::Timeout),
+                    // Port closed: nothing more will be given.
+                    Ok(Ok(0)) => return Ok(self.framer.take_remaining()),
+                    Ok(Ok(size)) => self.framer.push(&chunk[..size]),
+                    Ok(Err(e)) => return Err(GpsError::Io(e)),
+                }
+            }
+        }
+
+        /// Reads a full sentence from the port, returns a String.
+        ///
+        /// Errors are the same as gps.read_line(): Timeout, InvalidBytes or Io.
+        pub async fn read_line(&mut self) -> Result<String, GpsError> {
+            match self.read_frame().await? {
+                Some(line) => String::from_utf8(line).map_err(|e| GpsError::InvalidBytes(e.into_bytes())),
+                None => Err(GpsError::Timeout),
+            }
+        }
+
+        /// Reads the next sentence. The same as gps.update().
+        pub async fn update(&mut self) -> GpsSentence {
+            loop {
+                if let Some(view) = self.gsv.pop() {
+                    return sky_view(view);
+                }
+                match self.read_line().await {
+                    Err(GpsError::InvalidBytes(_vector)) => return GpsSentence::InvalidBytes,
+                    Err(_e) => return GpsSentence::NoConnection,
+                    Ok(line) => match parse_line(&line, &self.parsers) {
+                        Parsed::Sentence(sentence) => return sentence,
+                        Parsed::Gsv(message) => self.gsv.push(message),
+                    },
+                }
+            }
+        }
+
+        /// Reads the next sentence, or None when the port is closed.
+        ///
+        /// Sentences that could not be read are given as errors:
+        /// - InvalidSentence -> GpsError::Checksum when the checksum does not match, otherwise
+        ///   GpsError::InvalidSentence.
+        /// - ParseError -> GpsError::MalformedField, including dropped GSV groups.
+        async fn next_sentence(&mut self) -> Option<Result<GpsSentence, GpsError>> {
+            loop {
+                if let Some(view) = self.gsv.pop() {
+                    return Some(match sky_view(view) {
+                        GpsSentence::ParseError(e) => Err(GpsError::MalformedField(e)),
+                        sentence => Ok(sentence),
+                    });
+                }
+                let line = match self.read_frame().await {
+                    Ok(Some(line)) => line,
+                    Ok(None) => return None,
+                    Err(e) => return Some(Err(e)),
+                };
+                let line = match String::from_utf8(line) {
+                    Ok(line) => line,
+                    Err(e) => return Some(Err(GpsError::InvalidBytes(e.into_bytes()))),
+                };
+                return Some(match parse_line(&line, &self.parsers) {
+                    Parsed::Gsv(message) => {
+                        self.gsv.push(message);
+                        continue;
+                    }
+                    Parsed::Sentence(GpsSentence::InvalidSentence) => Err(invalid_sentence(line.trim())),
+                    Parsed::Sentence(GpsSentence::ParseError(e)) => Err(GpsError::MalformedField(e)),
+                    Parsed::Sentence(sentence) => Ok(sentence),
+                });
+            }
+        }
+
+        /// Stream of sentences from the gps.
+        ///
+        /// GpsError::Timeout is given when no sentence is read before the timeout and the stream
+        /// carries on. The stream ends when the port is closed, or after a GpsError::Io.
+        ///
+        /// ```no_run
+        /// # async fn run(port: tokio::io::DuplexStream) {
+        /// use adafruit_gps::AsyncGps;
+        /// use futures_util::StreamExt;
+        ///
+        /// let mut gps = AsyncGps::from_port(port);
+        /// let mut sentences = Box::pin(gps.sentences());
+        /// while let Some(sentence) = sentences.next().await {
+        ///     println!("{:?}", sentence);
+        /// }
+        /// # }
+        /// ```
+        pub fn sentences(&mut self) -> impl Stream<Item = Result<GpsSentence, GpsError>> + '_ {
+            stream::unfold(Some(self), |gps| async move {
+                let gps = gps?;
+                match gps.next_sentence().await {
+                    Some(Err(GpsError::Io(e))) => Some((Err(GpsError::Io(e)), None)),
+                    Some(item) => Some((item, Some(gps))),
+                    None => None,
+                }
+            })
+        }
+
+        /// Send the PMTK command.
+        pub async fn send_command(&mut self, cmd: &str) -> Result<(), GpsError> {
+            //! Input: no $ and no *checksum.
+            let cmd = add_checksum(cmd.to_string());
+            self.port.write_all(cmd.as_bytes()).await?;
+            self.port.flush().await?;
+            Ok(())
+        }
+
+        /// Check for the PMTK001 return for the command number, passing over acks for other commands.
+        ///
+        /// Waits for the whole timeout, even when it is longer than the read_line timeout. NoPacket
+        /// if there is no ack before then, or the port is closed.
+        pub async fn pmtk_001(&mut self, command: u16, timeout: Duration) -> Pmtk001Ack {
+            let deadline = tokio::time::Instant::now() + timeout;
+            while tokio::time::Instant::now() < deadline {
+                match self.read_frame_until(deadline).await {
+                    Ok(Some(line)) => match String::from_utf8(line).ok().and_then(|line| parse_pmtk_001(&line)) {
+                        Some((number, ack)) if number == command => return ack,
+                        _ => {}
+                    },
+                    Err(GpsError::Timeout) => {}
+                    Ok(None) | Err(_) => return Pmtk001Ack::NoPacket,
+                }
+            }
+            Pmtk001Ack::NoPacket
+        }
+
+        /// Check for PMTK500 style return.
+        pub async fn pmtk_500(&mut self) -> Option<String> {
+            //! Return the string without checksum.
+            for _i in 0..10 {
+                match self.read_line().await {
+                    Ok(line) => {
+                        if let Some(line) = parse_pmtk_500(&line) {
+                            return Some(line);
+                        }
+                    }
+                    Err(_e) => return None,
+                }
+            }
+            None
+        }
+    }
+
+    /// The error for a line parsed as GpsSentence::InvalidSentence. Only a checksum that does not
+    /// match is a GpsError::Checksum.
+    fn invalid_sentence(line: &str) -> GpsError {
+        match check_sentence(line.as_bytes()) {
+            Verdict::BadChecksum { .. } => GpsError::Checksum(line.to_string()),
+            _ => GpsError::InvalidSentence(line.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod async_gps_test {
+    use std::time::Duration;
+
+    use futures_util::StreamExt;
+    use tokio::io::{duplex, AsyncReadExt, AsyncWriteExt};
+
+    use crate::error::GpsError;
+    use crate::nmea::parse_nmea::ParseErrorReason;
+    use crate::open_gps::gps::GpsSentence;
+    use crate::pmtk::send_pmtk::Pmtk001Ack;
+
+    use super::gps::AsyncGps;
+
+    const GGA: &str = "$GNGGA,131613.000,5132.7314,N,00005.9099,W,1,9,1.17,42.4,M,47.0,M,,*60\r\n";
+    const GSV_1: &str = "$GPGSV,2,1,08,12,12,100,30,04,11,331,,16,06,282,,05,05,074,22*7F\r\n";
+    const GSV_2: &str = "$GPGSV,2,2,08,32,01,215,,41,,,,26,40,050,35,29,70,120,40*4A\r\n";
+
+    #[tokio::test]
+    async fn stream_of_sentences() {
+        let (mut receiver, port) = duplex(64);
+        tokio::spawn(async move {
+            // Split over writes smaller than a sentence.
+            let bytes = [GGA, "$GPGGA,bad*00\r\n", GSV_1, GSV_2, GGA].concat();
+            for chunk in bytes.as_bytes().chunks(20) {
+                receiver.write_all(chunk).await.unwrap();
+            }
+        });
+        let mut gps = AsyncGps::from_port(port);
+        let sentences: Vec<Result<GpsSentence, GpsError>> = gps.sentences().collect().await;
+
+        assert_eq!(sentences.len(), 4);
+        match &sentences[0] {
+            Ok(GpsSentence::GGA(gga)) => assert_eq!(gga.satellites_used, 9),
+            other => panic!("Expected GGA, got {:?}", other),
+        }
+        match &sentences[1] {
+            Err(GpsError::Checksum(line)) => assert_eq!(line, "$GPGGA,bad*00"),
+            other => panic!("Expected Checksum, got {:?}", other),
+        }
+        match &sentences[2] {
+            Ok(GpsSentence::GSV(view)) => assert_eq!(view.satellites.len(), 8),
+            other => panic!("Expected GSV, got {:?}", other),
+        }
+        assert!(matches!(sentences[3], Ok(GpsSentence::GGA(_))));
+    }
+
+    #[tokio::test]
+    async fn stream_parse_error() {
+        let (mut receiver, port) = duplex(256);
+        receiver.write_all(b"$GPGGA,131613.000,5132.7314,N,00005.9099,W,1*5F\r\n").await.unwrap();
+        drop(receiver);
+        let mut gps = AsyncGps::from_port(port);
+        let sentences: Vec<Result<GpsSentence, GpsError>> = gps.sentences().collect().await;
+        match &sentences[..] {
+            [Err(GpsError::MalformedField(e))] => assert_eq!(e.reason, ParseErrorReason::Missing),
+            other => panic!("Expected MalformedField, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn stream_invalid_sentence() {
+        let (mut receiver, port) = duplex(256);
+        // Valid checksum but not a valid address, then no $.
+        receiver.write_all(b"$GP-GA,1*21\r\nGPGGA,1*4B\r\n").await.unwrap();
+        drop(receiver);
+        let mut gps = AsyncGps::from_port(port);
+        let sentences: Vec<Result<GpsSentence, GpsError>> = gps.sentences().collect().await;
+        match &sentences[..] {
+            [Err(GpsError::InvalidSentence(address)), Err(GpsError::InvalidSentence(no_start))] => {
+                assert_eq!(address, "$GP-GA,1*21");
+                assert_eq!(no_start, "GPGGA,1*4B");
+            }
+            other => panic!("Expected InvalidSentence, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn stream_timeout() {
+        let (_receiver, port) = duplex(64);
+        let mut gps = AsyncGps::from_port(port);
+        gps.set_timeout(Duration::from_millis(10));
+        let mut sentences = Box::pin(gps.sentences());
+        assert!(matches!(sentences.next().await, Some(Err(GpsError::Timeout))));
+        assert!(matches!(sentences.next().await, Some(Err(GpsError::Timeout))));
+    }
+
+    #[tokio::test]
+    async fn send_command_and_ack() {
+        let (mut receiver, port) = duplex(256);
+        let mut gps = AsyncGps::from_port(port);
+        gps.send_command("PMTK220,1000").await.unwrap();
+
+        let mut command = [0u8; 18];
+        receiver.read_exact(&mut command).await.unwrap();
+        assert_eq!(&command, b"$PMTK220,1000*1F\r\n");
+
+        receiver.write_all(GGA.as_bytes()).await.unwrap();
+        receiver.write_all(b"$PMTK001,313,3*31\r\n$PMTK001,220,3*30\r\n").await.unwrap();
+        assert_eq!(gps.pmtk_001(220, Duration::from_secs(1)).await, Pmtk001Ack::Success);
+        assert_eq!(gps.pmtk_001(220, Duration::from_millis(50)).await, Pmtk001Ack::NoPacket);
+    }
+
+    #[tokio::test]
+    async fn ack_past_read_timeout() {
+        // The read_line timeout is 1 second, the ack is waited for as long as pmtk_001 is told.
+        let (mut receiver, port) = duplex(256);
+        let mut gps = AsyncGps::from_port(port);
+        let late = tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(1200)).await;
+            receiver.write_all(b"$PMTK001,220,3*30\r\n").await.unwrap();
+            receiver
+        });
+        assert_eq!(gps.pmtk_001(220, Duration::from_millis(1500)).await, Pmtk001Ack::Success);
+        let _receiver = late.await.unwrap();
+
+        let start = std::time::Instant::now();
+        assert_eq!(gps.pmtk_001(220, Duration::from_millis(1500)).await, Pmtk001Ack::NoPacket);
+        assert!(start.elapsed() >= Duration::from_millis(1500));
+    }
+
+    #[tokio::test]
+    async fn pmtk_500_response() {
+        let (mut receiver, port) = duplex(256);
+        let mut gps = AsyncGps::from_port(port);
+        receiver.write_all(GGA.as_bytes()).await.unwrap();
+        receiver.write_all(b"$PMTK705,AXN_5.1.7_3333_19020118,0027,PA1010D,1.0*76\r\n").await.unwrap();
+        assert_eq!(
+            gps.pmtk_500().await,
+            Some("$PMTK705,AXN_5.1.7_3333_19020118,0027,PA1010D,1.0".to_string())
+        );
+        drop(receiver);
+        assert_eq!(gps.pmtk_500().await, None);
+    }
+}
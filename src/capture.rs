@@ -0,0 +1,282 @@
+//! # Raw capture
+//! Every line read from the gps can be teed, as received, to a .nmea log, so it can be re-run through
+//! the parsers later. Each line of the log is the Unix time it was received and the raw line:
+//!
+//! ```text
+//! 1591884463.123 $GNGGA,131613.000,5132.7314,N,00005.9099,W,1,9,1.17,42.4,M,47.0,M,,*60
+//! ```
+//!
+//! A log is read back with Gps::replay(), as fast as possible or at the pace it was recorded.
+
+pub mod raw {
+    //! RawLog writes captured lines, ReplayPort reads them back as a port for the Gps struct.
+    use std::fs::{File, OpenOptions};
+    use std::io::{BufRead, BufReader, Read, Result, Write};
+    use std::thread::sleep;
+    use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+    use crate::error::GpsError;
+    use crate::open_gps::gps::Gps;
+
+    /// # Replay pace
+    /// - Fast -> Give each line as soon as it is asked for. Default.
+    /// - RealTime -> Give each line at the same time after the first line as when it was recorded.
+    #[derive(Debug, PartialEq, Clone, Copy, Default)]
+    pub enum Pace {
+        #[default]
+        Fast,
+        RealTime,
+    }
+
+    /// Writes each line with the time it was received.
+    pub struct RawLog {
+        writer: Box<dyn Write + Send>,
+    }
+
+    impl RawLog {
+        /// Log to any writer, such as a file or a `Vec<u8>`.
+        pub fn new<W: Write + Send + 'static>(writer: W) -> RawLog {
+            RawLog { writer: Box::new(writer) }
+        }
+
+        /// Appends to the log file, making it if it does not exist.
+        pub fn open(path: &str) -> Result<RawLog> {
+            Ok(RawLog::new(OpenOptions::new().append(true).create(true).open(path)?))
+        }
+
+        /// Writes the line, without its \r\n, after the current time.
+        pub fn write_line(&mut self, line: &[u8]) -> Result<()> {
+            let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+            self.write_line_at(line, now)
+        }
+
+        /// Writes the line, without its \r\n, after the given time since the Unix epoch.
+        pub fn write_line_at(&mut self, line: &[u8], received: Duration) -> Result<()> {
+            let line = line.strip_suffix(b"\n").unwrap_or(line);
+            let line = line.strip_suffix(b"\r").unwrap_or(line);
+            write!(self.writer, "{}.{:03} ", received.as_secs(), received.subsec_millis())?;
+            self.writer.write_all(line)?;
+            self.writer.write_all(b"\n")?;
+            self.writer.flush()
+        }
+    }
+
+    /// Splits a log line into its time and the line. Lines without a time, such as from a plain
+    /// `cat /dev/serial0 > file` capture, are given with None.
+    pub fn split_log_line(line: &[u8]) -> (Option<Duration>, &[u8]) {
+        if let Some(space) = line.iter().position(|b| *b == b' ') {
+            if let Some(time) = std::str::from_utf8(&line[..space]).ok().and_then(parse_time) {
+                return (Some(time), &line[space + 1..]);
+            }
+        }
+        (None, line)
+    }
+
+    /// Parses seconds.milliseconds, such as 1591884463.123
+    fn parse_time(time: &str) -> Option<Duration> {
+        let (seconds, millis) = time.split_once('.')?;
+        if millis.len() != 3 || !seconds.bytes().chain(millis.bytes()).all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        Some(Duration::from_secs(seconds.parse().ok()?) + Duration::from_millis(millis.parse().ok()?))
+    }
+
+    /// # ReplayPort
+    /// Port that gives the lines of a .nmea log, without their times, as if they came from the gps.
+    ///
+    /// Anything written to it, such as PMTK commands, is discarded.
+    pub struct ReplayPort<R: BufRead> {
+        reader: R,
+        pace: Pace,
+        pending: Vec<u8>,
+        /// When the first line was given, and the time it was recorded.
+        start: Option<(Instant, Duration)>,
+    }
+
+    impl<R: BufRead> ReplayPort<R> {
+        pub fn new(reader: R, pace: Pace) -> ReplayPort<R> {
+            ReplayPort { reader, pace, pending: Vec::new(), start: None }
+        }
+
+        /// Reads the next line of the log into pending, waiting until it is due for Pace::RealTime.
+        /// Gives false at the end of the log.
+        fn next_line(&mut self) -> Result<bool> {
+            let mut line = Vec::new();
+            if self.reader.read_until(b'\n', &mut line)? == 0 {
+                return Ok(false);
+            }
+            let (received, sentence) = split_log_line(&line);
+            if let (Pace::RealTime, Some(received)) = (self.pace, received) {
+                match self.start {
+                    None => self.start = Some((Instant::now(), received)),
+                    Some((start, first)) => {
+                        let due = start + received.checked_sub(first).unwrap_or_default();
+                        if let Some(wait) = due.checked_duration_since(Instant::now()) {
+                            sleep(wait);
+                        }
+                    }
+                }
+            }
+            let sentence = sentence.strip_suffix(b"\n").unwrap_or(sentence);
+            let sentence = sentence.strip_suffix(b"\r").unwrap_or(sentence);
+            self.pending.extend_from_slice(sentence);
+            self.pending.extend_from_slice(b"\r\n");
+            Ok(true)
+        }
+    }
+
+    impl<R: BufRead> Read for ReplayPort<R> {
+        fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+            if self.pending.is_empty() && !self.next_line()? {
+                return Ok(0);
+            }
+            let size = buf.len().min(self.pending.len());
+            buf[..size].copy_from_slice(&self.pending[..size]);
+            self.pending.drain(..size);
+            Ok(size)
+        }
+    }
+
+    impl<R: BufRead> Write for ReplayPort<R> {
+        fn write(&mut self, buf: &[u8]) -> Result<usize> {
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    impl Gps<ReplayPort<BufReader<File>>> {
+        /// Reads the sentences of a .nmea log made by gps.capture(), at the given pace.
+        ///
+        /// gps.update() gives NoConnection at the end of the log.
+        pub fn replay(path: &str, pace: Pace) -> std::result::Result<Gps<ReplayPort<BufReader<File>>>, GpsError> {
+            let file = File::open(path)?;
+            Ok(Gps::from_port(ReplayPort::new(BufReader::new(file), pace)))
+        }
+    }
+}
+
+#[cfg(test)]
+mod raw_test {
+    use std::env::temp_dir;
+    use std::fs::{read, remove_file};
+    use std::io::{Cursor, Write};
+    use std::sync::{Arc, Mutex};
+    use std::time::{Duration, Instant};
+
+    use crate::open_gps::gps::{Gps, GpsSentence};
+    use crate::open_gps::sentences::SentenceIter;
+
+    use super::raw::{split_log_line, Pace, RawLog, ReplayPort};
+
+    const GGA: &str = "$GNGGA,131613.000,5132.7314,N,00005.9099,W,1,9,1.17,42.4,M,47.0,M,,*60\r\n";
+    const GSA: &str = "$GPGSA,A,3,29,02,26,25,31,14,,,,,,,1.42,1.17,0.80*07\r\n";
+
+    /// Writer that can be looked at after it is given to the gps.
+    #[derive(Clone, Default)]
+    struct Shared(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Shared {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn log_lines() {
+        let shared = Shared::default();
+        let mut log = RawLog::new(shared.clone());
+        log.write_line_at(GGA.as_bytes(), Duration::from_millis(1_591_884_463_123)).unwrap();
+        let logged = String::from_utf8(shared.0.lock().unwrap().clone()).unwrap();
+        assert_eq!(logged, format!("1591884463.123 {}\n", GGA.trim_end()));
+
+        let (received, line) = split_log_line(logged.as_bytes());
+        assert_eq!(received, Some(Duration::from_millis(1_591_884_463_123)));
+        assert_eq!(line, format!("{}\n", GGA.trim_end()).as_bytes());
+        assert_eq!(split_log_line(GSA.as_bytes()), (None, GSA.as_bytes()));
+    }
+
+    #[test]
+    fn capture_every_line() {
+        let shared = Shared::default();
+        let mut gps = Gps::from_bytes([GGA, "$GPGGA,bad*00\r\n", GSA].concat().as_bytes());
+        gps.capture_to(shared.clone());
+        assert!(matches!(gps.update(), GpsSentence::GGA(_)));
+        assert_eq!(gps.update(), GpsSentence::InvalidSentence);
+        gps.stop_capture();
+        assert!(matches!(gps.update(), GpsSentence::GSA(_)));
+
+        let logged = String::from_utf8(shared.0.lock().unwrap().clone()).unwrap();
+        let lines: Vec<&str> = logged.lines().map(|line| split_log_line(line.as_bytes()).1)
+            .map(|line| std::str::from_utf8(line).unwrap()).collect();
+        assert_eq!(lines, vec![GGA.trim_end(), "$GPGGA,bad*00"]);
+    }
+
+    /// Writer whose disk is full.
+    struct Full;
+
+    impl Write for Full {
+        fn write(&mut self, _buf: &[u8]) -> std::io::Result<usize> {
+            Err(std::io::Error::other("disk full"))
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn capture_error_keeps_line() {
+        let mut gps = Gps::from_bytes([GGA, GSA].concat().as_bytes());
+        gps.capture_to(Full);
+        assert!(matches!(gps.update(), GpsSentence::GGA(_)));
+        assert_eq!(gps.capture_error().map(|e| e.to_string()), Some("disk full".to_string()));
+        // Stopped, so the next line is read without trying the log again.
+        assert!(matches!(gps.update(), GpsSentence::GSA(_)));
+        gps.capture_to(Shared::default());
+        assert!(gps.capture_error().is_none());
+    }
+
+    #[test]
+    fn capture_and_replay_file() {
+        let path = temp_dir().join(format!("raw_test_{}.nmea", std::process::id()));
+        let path = path.to_str().unwrap();
+        let mut gps = Gps::from_bytes([GGA, GSA].concat().as_bytes());
+        gps.capture(path).unwrap();
+        while gps.update() != GpsSentence::NoConnection {}
+        gps.stop_capture();
+
+        let replayed = Gps::replay(path, Pace::Fast).map(|mut gps| {
+            gps.sentences().stop_after(1).collect::<Vec<GpsSentence>>()
+        });
+        let bytes = read(path);
+        let _ = remove_file(path);
+        assert_eq!(bytes.unwrap().iter().filter(|b| **b == b'\n').count(), 2);
+        let replayed = replayed.unwrap();
+        assert_eq!(replayed.len(), 3);
+        assert!(matches!(replayed[0], GpsSentence::GGA(_)));
+        assert!(matches!(replayed[1], GpsSentence::GSA(_)));
+    }
+
+    #[test]
+    fn replay_real_time() {
+        let log = format!("100.000 {}100.150 {}", GGA, GSA);
+        let mut gps = Gps::from_port(ReplayPort::new(Cursor::new(log.clone()), Pace::RealTime));
+        let start = Instant::now();
+        assert!(matches!(gps.update(), GpsSentence::GGA(_)));
+        assert!(start.elapsed() < Duration::from_millis(100));
+        assert!(matches!(gps.update(), GpsSentence::GSA(_)));
+        assert!(start.elapsed() >= Duration::from_millis(150));
+
+        let mut gps = Gps::from_port(ReplayPort::new(Cursor::new(log), Pace::Fast));
+        let start = Instant::now();
+        assert_eq!(gps.sentences().stop_after(1).valid().count(), 2);
+        assert!(start.elapsed() < Duration::from_millis(150));
+    }
+}
@@ -0,0 +1,518 @@
+//! # EPO
+//! Extended Prediction Orbit data, the MTK form of AGPS. An EPO file, such as MTK7d.EPO for 7 days
+//! or MTK14.EPO for 14 days, gives the orbits of each satellite ahead of time so the gps can get a
+//! fix in seconds from a cold start.
+//!
+//! gps.upload_epo(&epo, progress) switches the gps to binary mode, sends the file and switches it
+//! back to NMEA. pmtk_607_q_epo_info gives what the gps has after, and pmtk_127_cmd_clear_epo
+//! clears it.
+
+pub mod binary {
+    //! MTK binary packets, used instead of NMEA while the gps is in binary mode.
+    //!
+    //! Format: 0x04 0x24, length, command, data, checksum, 0x0D 0x0A
+    //! - length -> u16 little endian, bytes in the whole packet.
+    //! - command -> u16 little endian.
+    //! - checksum -> XOR of the length, command and data bytes.
+
+    /// EPO data: the sequence number (u16) and 3 satellites.
+    pub const EPO_PACKET: u16 = 722;
+    /// Ack for an EPO packet: the sequence number (u16) and the result, 1 for success.
+    pub const EPO_ACK_PACKET: u16 = 2;
+    /// Set the output format: 0 for NMEA, then the baud rate (u32), 0 to keep the baud rate.
+    pub const SET_OUTPUT_PACKET: u16 = 253;
+
+    const PREAMBLE: [u8; 2] = [0x04, 0x24];
+    const END: [u8; 2] = [0x0D, 0x0A];
+
+    /// # Binary packet
+    /// - command -> Packet type, such as EPO_PACKET.
+    /// - data -> The bytes after the command.
+    #[derive(Debug, PartialEq, Eq, Clone)]
+    pub struct BinaryPacket {
+        pub command: u16,
+        pub data: Vec<u8>,
+    }
+
+    impl BinaryPacket {
+        pub fn new(command: u16, data: Vec<u8>) -> BinaryPacket {
+            BinaryPacket { command, data }
+        }
+
+        /// The packet as sent, with its preamble, length, checksum and end.
+        pub fn to_bytes(&self) -> Vec<u8> {
+            let length = (self.data.len() + 9) as u16;
+            let mut bytes = Vec::with_capacity(length as usize);
+            bytes.extend_from_slice(&PREAMBLE);
+            bytes.extend_from_slice(&length.to_le_bytes());
+            bytes.extend_from_slice(&self.command.to_le_bytes());
+            bytes.extend_from_slice(&self.data);
+            bytes.push(bytes[2..].iter().fold(0, |xor, b| xor ^ b));
+            bytes.extend_from_slice(&END);
+            bytes
+        }
+
+        /// Reads a whole packet. None if the preamble, length, checksum or end is wrong.
+        pub fn parse(bytes: &[u8]) -> Option<BinaryPacket> {
+            if bytes.len() < 9 || bytes[..2] != PREAMBLE || bytes[bytes.len() - 2..] != END {
+                return None;
+            }
+            if u16::from_le_bytes([bytes[2], bytes[3]]) as usize != bytes.len() {
+                return None;
+            }
+            let checksum = bytes.len() - 3;
+            if bytes[2..checksum].iter().fold(0, |xor, b| xor ^ b) != bytes[checksum] {
+                return None;
+            }
+            Some(BinaryPacket {
+                command: u16::from_le_bytes([bytes[4], bytes[5]]),
+                data: bytes[6..checksum].to_vec(),
+            })
+        }
+    }
+}
+
+pub mod upload {
+    //! Read EPO files and send them to the gps.
+    //!
+    //! An EPO file is sets of 32 satellites, one set for each 6 hours. Each satellite is 60 bytes,
+    //! starting with the GPS hour (u24 little endian, hours since 1980-01-06) the set starts at.
+    use std::fmt;
+    use std::fs::read;
+    use std::io::{self, Read, Write};
+    use std::thread::sleep;
+    use std::time::{Duration, Instant};
+
+    use crate::error::GpsError;
+    use crate::open_gps::gps::Gps;
+    use crate::pmtk::command::PmtkCommand;
+    use crate::time::utc::{GpsTime, UtcDateTime};
+
+    use super::binary::{BinaryPacket, EPO_ACK_PACKET, EPO_PACKET, SET_OUTPUT_PACKET};
+
+    /// Bytes for each satellite.
+    pub const EPO_SV_SIZE: usize = 60;
+    /// Bytes for each 6 hour set of 32 satellites.
+    pub const EPO_SET_SIZE: usize = EPO_SV_SIZE * 32;
+    /// Satellites sent in each packet.
+    pub const SV_PER_PACKET: usize = 3;
+    /// Sequence number of the empty packet sent after the data.
+    pub const EPO_END_SEQUENCE: u16 = 0xFFFF;
+    /// Hours each set is for.
+    const SET_HOURS: u32 = 6;
+
+    /// # EpoError
+    /// - Io -> Reading the file, or writing to the gps, failed.
+    /// - Size -> The file is empty or not a whole number of sets, so is not an EPO file.
+    /// - Timeout -> There was no ack for the packet with the sequence number, after every retry.
+    /// - Failed -> The gps acked the packet with the sequence number as failed, after every retry.
+    #[derive(Debug)]
+    pub enum EpoError {
+        Io(io::Error),
+        Size(usize),
+        Timeout { sequence: u16 },
+        Failed { sequence: u16 },
+    }
+
+    impl fmt::Display for EpoError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                EpoError::Io(e) => write!(f, "EPO io error: {}", e),
+                EpoError::Size(size) => write!(f, "Not an EPO file, {} bytes is not a whole number of {} byte sets",
+                                               size, EPO_SET_SIZE),
+                EpoError::Timeout { sequence } => write!(f, "No ack for EPO packet {}", sequence),
+                EpoError::Failed { sequence } => write!(f, "EPO packet {} failed", sequence),
+            }
+        }
+    }
+
+    impl std::error::Error for EpoError {
+        fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+            match self {
+                EpoError::Io(e) => Some(e),
+                _ => None,
+            }
+        }
+    }
+
+    impl From<io::Error> for EpoError {
+        fn from(e: io::Error) -> EpoError {
+            EpoError::Io(e)
+        }
+    }
+
+    /// # EPO file
+    /// The sets of an EPO file, in file order.
+    #[derive(Debug, PartialEq, Eq, Clone)]
+    pub struct EpoFile {
+        data: Vec<u8>,
+    }
+
+    /// Reads an EPO file, such as MTK7d.EPO.
+    pub fn read_epo(path: &str) -> Result<EpoFile, EpoError> {
+        EpoFile::new(read(path)?)
+    }
+
+    impl EpoFile {
+        /// Gives EpoError::Size if the bytes are not a whole number of sets.
+        // is_multiple_of needs Rust 1.87.
+        #[allow(clippy::manual_is_multiple_of)]
+        pub fn new(data: Vec<u8>) -> Result<EpoFile, EpoError> {
+            if data.is_empty() || data.len() % EPO_SET_SIZE != 0 {
+                return Err(EpoError::Size(data.len()));
+            }
+            Ok(EpoFile { data })
+        }
+
+        pub fn bytes(&self) -> &[u8] {
+            &self.data
+        }
+
+        /// Number of 6 hour sets.
+        pub fn sets(&self) -> usize {
+            self.data.len() / EPO_SET_SIZE
+        }
+
+        /// The GPS hour each set starts at.
+        pub fn gps_hours(&self) -> Vec<u32> {
+            self.data.chunks(EPO_SET_SIZE).map(|set| u32::from_le_bytes([set[0], set[1], set[2], 0])).collect()
+        }
+
+        /// When the first set starts.
+        pub fn start(&self) -> UtcDateTime {
+            from_gps_hour(self.gps_hours()[0])
+        }
+
+        /// When the last set ends, after which the data is of no use.
+        pub fn end(&self) -> UtcDateTime {
+            from_gps_hour(self.gps_hours()[self.sets() - 1] + SET_HOURS)
+        }
+
+        /// The EPO packets to send, with their sequence numbers from 0. The last packet is padded
+        /// with 0 if the satellites do not fill it. The end packet is not included.
+        pub fn packets(&self) -> Vec<BinaryPacket> {
+            self.data.chunks(EPO_SV_SIZE * SV_PER_PACKET).enumerate()
+                .map(|(sequence, satellites)| epo_packet(sequence as u16, satellites))
+                .collect()
+        }
+    }
+
+    fn from_gps_hour(hour: u32) -> UtcDateTime {
+        UtcDateTime::from_gps_time(GpsTime { week: hour / 168, tow: (hour % 168 * 3600) as f64 })
+    }
+
+    fn epo_packet(sequence: u16, satellites: &[u8]) -> BinaryPacket {
+        let mut data = Vec::with_capacity(2 + EPO_SV_SIZE * SV_PER_PACKET);
+        data.extend_from_slice(&sequence.to_le_bytes());
+        data.extend_from_slice(satellites);
+        data.resize(2 + EPO_SV_SIZE * SV_PER_PACKET, 0);
+        BinaryPacket::new(EPO_PACKET, data)
+    }
+
+    impl<T: Read + Write> Gps<T> {
+        /// Sends the EPO data to the gps.
+        pub fn upload_epo<F: FnMut(usize, usize)>(&mut self, epo: &EpoFile, mut progress: F) -> Result<(), EpoError> {
+            //! The gps is switched to binary mode with $PMTK253,1,0, each packet is sent and its
+            //! binary ack checked, then the gps is switched back to NMEA, even after an error.
+            //!
+            //! progress is given the packets acked and the number of packets after each ack.
+            //!
+            //! A packet with no ack, or a failed ack, is sent again as set by gps.set_retry().
+            let binary = PmtkCommand::SetOutputFormat { binary: true, baud: 0 }.to_nmea()
+                .expect("SetOutputFormat has no mode that can not be sent");
+            self.port.write_all(binary.as_bytes())?;
+            self.port.flush()?;
+            let sent = self.send_epo(epo, &mut progress);
+            let nmea = BinaryPacket::new(SET_OUTPUT_PACKET, vec![0, 0, 0, 0, 0]).to_bytes();
+            let switched = self.port.write_all(&nmea).and_then(|_| self.port.flush());
+            sent?;
+            switched?;
+            Ok(())
+        }
+
+        fn send_epo<F: FnMut(usize, usize)>(&mut self, epo: &EpoFile, progress: &mut F) -> Result<(), EpoError> {
+            let packets = epo.packets();
+            for (index, packet) in packets.iter().enumerate() {
+                self.send_acked(packet, index as u16)?;
+                progress(index + 1, packets.len());
+            }
+            self.send_acked(&epo_packet(EPO_END_SEQUENCE, &[]), EPO_END_SEQUENCE)
+        }
+
+        /// Sends the packet until it is acked, or the retry attempts are used up.
+        fn send_acked(&mut self, packet: &BinaryPacket, sequence: u16) -> Result<(), EpoError> {
+            let bytes = packet.to_bytes();
+            let retry = self.retry;
+            let mut attempts = 0;
+            loop {
+                attempts += 1;
+                self.port.write_all(&bytes)?;
+                self.port.flush()?;
+                let error = match self.epo_ack(sequence, retry.timeout) {
+                    Ok(true) => return Ok(()),
+                    Ok(false) => EpoError::Failed { sequence },
+                    Err(GpsError::Io(e)) => return Err(EpoError::Io(e)),
+                    Err(_e) => EpoError::Timeout { sequence },
+                };
+                if attempts >= retry.attempts {
+                    return Err(error);
+                }
+                sleep(retry.backoff(attempts));
+            }
+        }
+
+        /// Reads packets until the ack for the sequence number, giving whether it was a success.
+        /// Acks for other packets are passed over.
+        fn epo_ack(&mut self, sequence: u16, timeout: Duration) -> Result<bool, GpsError> {
+            let deadline = Instant::now() + timeout;
+            while let Some(remaining) = deadline.checked_duration_since(Instant::now()) {
+                let packet = match self.read_packet_within(remaining) {
+                    Ok(packet) => packet,
+                    Err(GpsError::Timeout) if !self.framer.ended() => continue,
+                    Err(e) => return Err(e),
+                };
+                let ack = match BinaryPacket::parse(&packet) {
+                    Some(packet) if packet.command == EPO_ACK_PACKET && packet.data.len() >= 3 => packet.data,
+                    _ => continue,
+                };
+                if u16::from_le_bytes([ack[0], ack[1]]) == sequence {
+                    return Ok(ack[2] == 1);
+                }
+            }
+            Err(GpsError::Timeout)
+        }
+    }
+}
+
+#[cfg(test)]
+mod epo_test {
+    use std::collections::VecDeque;
+    use std::fs::{remove_file, write};
+    use std::io::{self, Read, Write};
+    use std::thread::sleep;
+    use std::time::{Duration, Instant};
+
+    use crate::framing::framer::Framer;
+    use crate::open_gps::gps::Gps;
+    use crate::pmtk::command::RetryPolicy;
+    use crate::time::utc::{UtcDate, UtcDateTime, UtcTime};
+
+    use super::binary::*;
+    use super::upload::*;
+
+    /// Gps that takes EPO data: it switches to binary mode on $PMTK253,1,0, acks each EPO packet and
+    /// switches back to NMEA on a set output packet.
+    #[derive(Default)]
+    struct MockReceiver {
+        binary: bool,
+        framer: Framer,
+        nmea: Vec<u8>,
+        output: VecDeque<u8>,
+        /// The sequence number of each EPO packet given.
+        sequences: Vec<u16>,
+        /// The satellite bytes of each EPO packet acked, in order.
+        satellites: Vec<u8>,
+        /// Sequence numbers to ack as failed, once each.
+        fail: Vec<u16>,
+        /// Sequence numbers to not ack, once each.
+        drop: Vec<u16>,
+        /// Sequence numbers to ack late, once each, after slow_ack.
+        slow: Vec<u16>,
+        slow_ack: Duration,
+        /// When the slow ack can be read, until then reads time out like a serial port.
+        ack_at: Option<Instant>,
+        switched_back: bool,
+    }
+
+    impl MockReceiver {
+        fn take(list: &mut Vec<u16>, sequence: u16) -> bool {
+            match list.iter().position(|item| *item == sequence) {
+                Some(index) => {
+                    list.remove(index);
+                    true
+                }
+                None => false,
+            }
+        }
+
+        fn ack(&mut self, sequence: u16, result: u8) {
+            let [low, high] = sequence.to_le_bytes();
+            self.output.extend(BinaryPacket::new(EPO_ACK_PACKET, vec![low, high, result]).to_bytes());
+        }
+
+        fn receive(&mut self, packet: BinaryPacket) {
+            match packet.command {
+                EPO_PACKET => {
+                    let sequence = u16::from_le_bytes([packet.data[0], packet.data[1]]);
+                    self.sequences.push(sequence);
+                    if MockReceiver::take(&mut self.drop, sequence) {
+                        return;
+                    }
+                    if MockReceiver::take(&mut self.fail, sequence) {
+                        return self.ack(sequence, 0);
+                    }
+                    if sequence != EPO_END_SEQUENCE {
+                        self.satellites.extend_from_slice(&packet.data[2..]);
+                    }
+                    if MockReceiver::take(&mut self.slow, sequence) {
+                        self.ack_at = Some(Instant::now() + self.slow_ack);
+                    }
+                    self.ack(sequence, 1);
+                }
+                SET_OUTPUT_PACKET => {
+                    self.binary = false;
+                    self.switched_back = true;
+                }
+                _ => {}
+            }
+        }
+    }
+
+    impl Write for MockReceiver {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            if !self.binary {
+                self.nmea.extend_from_slice(buf);
+                self.binary = self.nmea.ends_with(b"$PMTK253,1,0*37\r\n");
+                return Ok(buf.len());
+            }
+            self.framer.push(buf);
+            while let Some(bytes) = self.framer.next_packet() {
+                // A packet with a bad checksum is not acked.
+                if let Some(packet) = BinaryPacket::parse(&bytes) {
+                    self.receive(packet);
+                }
+            }
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl Read for MockReceiver {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            if matches!(self.ack_at, Some(ack_at) if Instant::now() < ack_at) {
+                sleep(Duration::from_millis(10));
+                return Err(io::Error::from(io::ErrorKind::TimedOut));
+            }
+            let size = buf.len().min(self.output.len());
+            for (byte, output) in buf.iter_mut().zip(self.output.drain(..size)) {
+                *byte = output;
+            }
+            Ok(size)
+        }
+    }
+
+    /// Two sets, starting at GPS week 2119, hour 24, with each satellite filled with its number.
+    fn epo_bytes() -> Vec<u8> {
+        let mut data = Vec::new();
+        for set in 0..2u32 {
+            let hour = 2119 * 168 + 24 + set * 6;
+            for satellite in 0..32u8 {
+                data.extend_from_slice(&hour.to_le_bytes()[..3]);
+                data.resize(data.len() + EPO_SV_SIZE - 3, satellite);
+            }
+        }
+        data
+    }
+
+    fn mock_gps(receiver: MockReceiver) -> Gps<MockReceiver> {
+        let mut gps = Gps::from_port(receiver);
+        gps.set_retry(RetryPolicy { timeout: Duration::from_millis(100), attempts: 2, backoff: Duration::from_millis(1) });
+        gps
+    }
+
+    #[test]
+    fn packets() {
+        let ack = BinaryPacket::new(EPO_ACK_PACKET, vec![0x0A, 0x00, 0x01]);
+        let bytes = ack.to_bytes();
+        assert_eq!(bytes, vec![0x04, 0x24, 0x0C, 0x00, 0x02, 0x00, 0x0A, 0x00, 0x01, 0x05, 0x0D, 0x0A]);
+        assert_eq!(BinaryPacket::parse(&bytes), Some(ack));
+        let mut bad = bytes.clone();
+        bad[9] ^= 1;
+        assert_eq!(BinaryPacket::parse(&bad), None);
+        assert_eq!(BinaryPacket::parse(&bytes[..11]), None);
+
+        let epo = EpoFile::new(epo_bytes()).unwrap();
+        let packets = epo.packets();
+        assert_eq!(packets.len(), 22);
+        assert!(packets.iter().all(|packet| packet.to_bytes().len() == 191));
+        assert_eq!(&packets[21].data[..2], &[21, 0]);
+        assert_eq!(&packets[21].data[2 + EPO_SV_SIZE..], &[0; 2 * EPO_SV_SIZE][..]);
+    }
+
+    #[test]
+    fn read_file() {
+        write("epo_test.EPO", epo_bytes()).unwrap();
+        write("epo_test_bad.EPO", &epo_bytes()[..100]).unwrap();
+        let epo = read_epo("epo_test.EPO");
+        let bad = read_epo("epo_test_bad.EPO");
+        let _ = remove_file("epo_test.EPO");
+        let _ = remove_file("epo_test_bad.EPO");
+        let epo = epo.unwrap();
+        assert!(matches!(bad, Err(EpoError::Size(100))));
+        assert!(matches!(read_epo("no_such_file.EPO"), Err(EpoError::Io(_))));
+
+        assert_eq!(epo.sets(), 2);
+        assert_eq!(epo.gps_hours(), vec![2119 * 168 + 24, 2119 * 168 + 30]);
+        // GPS week 2119 starts on 2020-08-16, and GPS time is 18 leap seconds ahead of UTC.
+        let date = UtcDate::from_ymd(2020, 8, 16).unwrap();
+        assert_eq!(epo.start(), UtcDateTime::new(date, UtcTime::parse("235942.000").unwrap()));
+        let date = UtcDate::from_ymd(2020, 8, 17).unwrap();
+        assert_eq!(epo.end(), UtcDateTime::new(date, UtcTime::parse("115942.000").unwrap()));
+    }
+
+    #[test]
+    fn upload() {
+        let mut gps = mock_gps(MockReceiver::default());
+        let mut progress = Vec::new();
+        let epo = EpoFile::new(epo_bytes()).unwrap();
+        gps.upload_epo(&epo, |sent, total| progress.push((sent, total))).unwrap();
+
+        let receiver = &gps.port;
+        assert!(receiver.nmea.starts_with(b"$PMTK253,1,0*37\r\n"));
+        assert!(receiver.switched_back && !receiver.binary);
+        let mut expected = epo_bytes();
+        expected.resize(22 * 3 * EPO_SV_SIZE, 0);
+        assert_eq!(receiver.satellites, expected);
+        assert_eq!(*receiver.sequences.last().unwrap(), EPO_END_SEQUENCE);
+        assert_eq!(progress.len(), 22);
+        assert_eq!(progress[0], (1, 22));
+        assert_eq!(progress[21], (22, 22));
+    }
+
+    #[test]
+    fn retry_missing_and_failed_acks() {
+        let receiver = MockReceiver { fail: vec![3], drop: vec![7], ..MockReceiver::default() };
+        let mut gps = mock_gps(receiver);
+        let epo = EpoFile::new(epo_bytes()).unwrap();
+        gps.upload_epo(&epo, |_sent, _total| {}).unwrap();
+        let sequences = &gps.port.sequences;
+        assert_eq!(sequences.iter().filter(|sequence| **sequence == 3).count(), 2);
+        assert_eq!(sequences.iter().filter(|sequence| **sequence == 7).count(), 2);
+        assert_eq!(sequences.len(), 22 + 1 + 2);
+
+        let receiver = MockReceiver { fail: vec![5, 5], ..MockReceiver::default() };
+        let mut gps = mock_gps(receiver);
+        let mut acked = 0;
+        assert!(matches!(gps.upload_epo(&epo, |sent, _total| acked = sent), Err(EpoError::Failed { sequence: 5 })));
+        assert_eq!(acked, 5);
+        assert!(gps.port.switched_back);
+
+        let receiver = MockReceiver { drop: vec![EPO_END_SEQUENCE, EPO_END_SEQUENCE], ..MockReceiver::default() };
+        let mut gps = mock_gps(receiver);
+        assert!(matches!(gps.upload_epo(&epo, |_sent, _total| {}), Err(EpoError::Timeout { sequence: EPO_END_SEQUENCE })));
+    }
+
+    #[test]
+    fn slow_ack() {
+        // The read_line timeout is 1 second, the ack is waited for as long as the retry timeout.
+        let receiver = MockReceiver { slow: vec![2], slow_ack: Duration::from_millis(1200), ..MockReceiver::default() };
+        let mut gps = mock_gps(receiver);
+        gps.set_retry(RetryPolicy::once(Duration::from_millis(1500)));
+        let epo = EpoFile::new(epo_bytes()).unwrap();
+        gps.upload_epo(&epo, |_sent, _total| {}).unwrap();
+        assert_eq!(gps.port.sequences.len(), 22 + 1);
+    }
+}
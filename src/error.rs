@@ -0,0 +1,79 @@
+//! Errors that can be given when opening, reading from or writing to the gps.
+//!
+//! GpsError covers everything from the port not being found to a sentence with a bad checksum, so a
+//! long running program can match on the error and carry on (or reopen the port) rather than panic.
+
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+use crate::nmea::parse_nmea::NmeaParseError;
+
+/// # GpsError
+/// - PortOpen -> The port could not be opened, probably the gps is unplugged or the name is wrong.
+/// - BaudRate -> The baud rate given is not a valid number.
+/// - Io -> Reading from or writing to the port failed.
+/// - Timeout -> No full sentence was given by the gps in time. Was PortConnection::NoConnection.
+/// - InvalidBytes -> Bytes given are not valid utf8, probably a baud rate mismatch.
+///   Was PortConnection::InvalidBytes.
+/// - Checksum -> The sentence checksum does not match the sentence.
+/// - InvalidSentence -> The line is not a sentence: no $ or checksum, cut short, not ascii or with an
+///   address that is not valid.
+/// - MalformedField -> A sentence field is missing or could not be parsed:
+///   [NmeaParseError](../nmea/parse_nmea/struct.NmeaParseError.html)
+///
+///   Checksum, InvalidSentence and MalformedField are only given by the async sentences() stream,
+///   and by `From<NmeaParseError>`. The blocking gps.update() and gps.try_update() give these lines
+///   as GpsSentence::InvalidSentence and GpsSentence::ParseError.
+/// - InvalidCommand -> The PMTK command has a value that can not be sent, such as DgpsMode::Unknown.
+#[derive(Debug)]
+pub enum GpsError {
+    PortOpen(String, serialport::Error),
+    BaudRate(String),
+    Io(io::Error),
+    Timeout,
+    InvalidBytes(Vec<u8>),
+    Checksum(String),
+    InvalidSentence(String),
+    MalformedField(NmeaParseError),
+    InvalidCommand(String),
+}
+
+impl fmt::Display for GpsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GpsError::PortOpen(port, e) => write!(f, "Port not found: {} - {}", port, e),
+            GpsError::BaudRate(baud_rate) => write!(f, "Invalid baud rate: {}", baud_rate),
+            GpsError::Io(e) => write!(f, "Port io error: {}", e),
+            GpsError::Timeout => write!(f, "No sentence given by the gps before timing out"),
+            GpsError::InvalidBytes(bytes) => write!(f, "Invalid bytes given: {:?}", bytes),
+            GpsError::Checksum(sentence) => write!(f, "Invalid checksum: {}", sentence),
+            GpsError::InvalidSentence(line) => write!(f, "Invalid sentence: {}", line),
+            GpsError::MalformedField(e) => write!(f, "Malformed field: {}", e),
+            GpsError::InvalidCommand(command) => write!(f, "Command can not be sent: {}", command),
+        }
+    }
+}
+
+impl Error for GpsError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            GpsError::PortOpen(_, e) => Some(e),
+            GpsError::Io(e) => Some(e),
+            GpsError::MalformedField(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for GpsError {
+    fn from(e: io::Error) -> GpsError {
+        GpsError::Io(e)
+    }
+}
+
+impl From<NmeaParseError> for GpsError {
+    fn from(e: NmeaParseError) -> GpsError {
+        GpsError::MalformedField(e)
+    }
+}
@@ -0,0 +1,558 @@
+//! # Fix
+//! Merges the sentences given for one position fix (an epoch) into a single Fix.
+//!
+//! The gps gives one sentence at a time: GGA, GSA, GSV, RMC, VTG... for each fix. Only GGA, RMC, GLL,
+//! ZDA and GNS have the UTC time of the fix, so the other sentences are put in the epoch of the last
+//! UTC seen.
+
+pub mod aggregate {
+    //! FixAggregator groups sentences by UTC epoch and gives a Fix when the epoch is complete.
+    //!
+    //! An epoch is complete when:
+    //! - Every expected sentence type has been seen. The expected types are set by
+    //!   gps.pmtk_314_api_set_nmea_output(), or by FixAggregator::expect(), otherwise they are learnt
+    //!   from the sentences seen so far.
+    //! - A sentence with a different UTC is given, which starts the next epoch.
+    //! - flush() is called, such as at the end of a file.
+    use serde::{Deserialize, Serialize};
+
+    use crate::nmea::gga::{GgaData, SatFix};
+    use crate::nmea::gll::GllData;
+    use crate::nmea::gsa::{DimensionFix, GsaData};
+    use crate::nmea::gsv::Satellites;
+    use crate::nmea::rmc::RmcData;
+    use crate::nmea::vtg::VtgData;
+    use crate::open_gps::gps::GpsSentence;
+    use crate::pmtk::send_pmtk::NmeaOutput;
+    use crate::time::utc::{DateTracker, UtcDate, UtcDateTime, UtcTime};
+
+    /// Knots to kph.
+    const KPH_PER_KNOT: f32 = 1.852;
+
+    /// The sentence types that make up a Fix.
+    #[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+    #[allow(clippy::upper_case_acronyms)]
+    pub enum SentenceType {
+        GGA,
+        VTG,
+        GSA,
+        GSV,
+        GLL,
+        RMC,
+    }
+
+    /// # Groups of Fix fields
+    /// Used to flag which fields are stale: copied from an earlier fix because the sentence that gives
+    /// them was not in this epoch.
+    /// - Date -> date (RMC)
+    /// - Position -> latitude, longitude (GGA, RMC or GLL)
+    /// - Altitude -> altitude, geoidal_sep, sat_fix (GGA)
+    /// - Velocity -> speed_knots, speed_kph, course (VTG or RMC)
+    /// - MagneticVariation -> magnetic_variation (RMC)
+    /// - Dop -> dimension_fix, pdop, hdop, vdop, satellites_used (GSA). hdop is taken from the GGA
+    ///   when there is no GSA, so it is only copied when there is no GGA either.
+    /// - SatellitesInView -> satellites_in_view (GSV)
+    #[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+    pub enum FixField {
+        Date,
+        Position,
+        Altitude,
+        Velocity,
+        MagneticVariation,
+        Dop,
+        SatellitesInView,
+    }
+
+    /// # Fix
+    /// One position fix, merged from all the sentences given for one UTC epoch.
+    ///
+    /// Fields are None when missing: not given by the gps, or the gps has no fix. Fields given by a
+    /// sentence not in this epoch are copied from the last fix and listed in stale.
+    ///
+    /// - utc -> UTC time of the fix.
+    /// - date -> UTC date, from the last RMC. Moved on a day when the time goes past midnight.
+    /// - datetime -> Both, for Unix time or GPS week and time of week.
+    /// - latitude, longitude -> Degrees, negative is South and West.
+    /// - altitude -> Mean sea level altitude, metres.
+    /// - geoidal_sep -> Geoid height above the WGS84 ellipsoid, metres.
+    /// - speed_knots, speed_kph -> Speed over ground.
+    /// - course -> True course, degrees.
+    /// - magnetic_variation -> Degrees, negative is West.
+    /// - sat_fix -> Fix type, from GGA.
+    /// - dimension_fix -> 2D or 3D fix, from GSA.
+    /// - pdop, hdop, vdop -> Dilution of precision.
+    /// - satellites_used -> Ids of the satellites used for the fix.
+    /// - satellites_in_view -> Every satellite seen.
+    /// - sentences -> The sentence types given for this epoch.
+    /// - stale -> The fields copied from an earlier fix.
+    #[derive(Debug, PartialEq, Clone, Default, Serialize, Deserialize)]
+    pub struct Fix {
+        pub utc: Option<UtcTime>,
+        pub date: Option<UtcDate>,
+        pub datetime: Option<UtcDateTime>,
+        pub latitude: Option<f32>,
+        pub longitude: Option<f32>,
+        pub altitude: Option<f32>,
+        pub geoidal_sep: Option<f32>,
+        pub speed_knots: Option<f32>,
+        pub speed_kph: Option<f32>,
+        pub course: Option<f32>,
+        pub magnetic_variation: Option<f32>,
+        pub sat_fix: SatFix,
+        pub dimension_fix: DimensionFix,
+        pub pdop: Option<f32>,
+        pub hdop: Option<f32>,
+        pub vdop: Option<f32>,
+        pub satellites_used: Vec<i32>,
+        pub satellites_in_view: Vec<Satellites>,
+        pub sentences: Vec<SentenceType>,
+        pub stale: Vec<FixField>,
+    }
+
+    impl Fix {
+        /// True if the field was copied from an earlier fix.
+        pub fn is_stale(&self, field: FixField) -> bool {
+            self.stale.contains(&field)
+        }
+
+        /// Copies the fields of a group from an earlier fix and flags them as stale.
+        fn copy_stale(&mut self, last: &Fix, field: FixField) {
+            match field {
+                FixField::Date => self.date = last.date,
+                FixField::Position => {
+                    self.latitude = last.latitude;
+                    self.longitude = last.longitude;
+                }
+                FixField::Altitude => {
+                    self.altitude = last.altitude;
+                    self.geoidal_sep = last.geoidal_sep;
+                    self.sat_fix = last.sat_fix.clone();
+                }
+                FixField::Velocity => {
+                    self.speed_knots = last.speed_knots;
+                    self.speed_kph = last.speed_kph;
+                    self.course = last.course;
+                }
+                FixField::MagneticVariation => self.magnetic_variation = last.magnetic_variation,
+                FixField::Dop => {
+                    self.dimension_fix = last.dimension_fix.clone();
+                    self.pdop = last.pdop;
+                    self.hdop = self.hdop.or(last.hdop);
+                    self.vdop = last.vdop;
+                    self.satellites_used = last.satellites_used.clone();
+                }
+                FixField::SatellitesInView => self.satellites_in_view = last.satellites_in_view.clone(),
+            }
+            self.stale.push(field);
+        }
+    }
+
+    /// The sentences given so far for one epoch.
+    #[derive(Debug, Default)]
+    struct Epoch {
+        utc: Option<UtcTime>,
+        gga: Option<GgaData>,
+        rmc: Option<RmcData>,
+        gll: Option<GllData>,
+        vtg: Option<VtgData>,
+        gsa: Vec<GsaData>,
+        gsv: Option<Vec<Satellites>>,
+        sentences: Vec<SentenceType>,
+    }
+
+    impl Epoch {
+        fn is_empty(&self) -> bool {
+            self.sentences.is_empty()
+        }
+
+        fn add(&mut self, sentence: GpsSentence) {
+            let sentence_type = match sentence {
+                GpsSentence::GGA(gga) => {
+                    self.gga = Some(gga);
+                    SentenceType::GGA
+                }
+                GpsSentence::RMC(rmc) => {
+                    self.rmc = Some(rmc);
+                    SentenceType::RMC
+                }
+                GpsSentence::GLL(gll) => {
+                    self.gll = Some(gll);
+                    SentenceType::GLL
+                }
+                GpsSentence::VTG(vtg) => {
+                    self.vtg = Some(vtg);
+                    SentenceType::VTG
+                }
+                // Multi-constellation receivers give a GSA and a GSV group for each constellation.
+                GpsSentence::GSA(gsa) => {
+                    self.gsa.push(gsa);
+                    SentenceType::GSA
+                }
+                GpsSentence::GSV(mut view) => {
+                    self.gsv.get_or_insert_with(Vec::new).append(&mut view.satellites);
+                    SentenceType::GSV
+                }
+                _ => return,
+            };
+            if !self.sentences.contains(&sentence_type) {
+                self.sentences.push(sentence_type);
+            }
+        }
+
+        /// Merges the sentences into a Fix. Missing groups are copied from the last fix.
+        fn into_fix(self, last: Option<&Fix>) -> Fix {
+            let mut fix = Fix { utc: self.utc, sentences: self.sentences, ..Fix::default() };
+            let mut missing: Vec<FixField> = Vec::new();
+
+            // Position: GGA, then RMC, then GLL.
+            if let Some(gga) = &self.gga {
+                fix.latitude = gga.lat;
+                fix.longitude = gga.long;
+            } else if let Some(rmc) = &self.rmc {
+                fix.latitude = rmc.latitude;
+                fix.longitude = rmc.longitude;
+            } else if let Some(gll) = &self.gll {
+                fix.latitude = gll.latitude;
+                fix.longitude = gll.longitude;
+            } else {
+                missing.push(FixField::Position);
+            }
+
+            match &self.gga {
+                Some(gga) => {
+                    fix.altitude = gga.msl_alt;
+                    fix.geoidal_sep = gga.geoidal_sep;
+                    fix.sat_fix = gga.sat_fix.clone();
+                }
+                None => missing.push(FixField::Altitude),
+            }
+
+            // Velocity: VTG, then RMC.
+            if let Some(vtg) = &self.vtg {
+                fix.speed_knots = vtg.speed_knots;
+                fix.speed_kph = vtg.speed_kph.or_else(|| vtg.speed_knots.map(|knots| knots * KPH_PER_KNOT));
+                fix.course = vtg.true_course;
+            } else if let Some(rmc) = &self.rmc {
+                fix.speed_knots = rmc.speed;
+                fix.speed_kph = rmc.speed.map(|knots| knots * KPH_PER_KNOT);
+                fix.course = rmc.course;
+            } else {
+                missing.push(FixField::Velocity);
+            }
+
+            match &self.rmc {
+                Some(rmc) => {
+                    fix.date = rmc.date;
+                    fix.magnetic_variation = rmc.mag_var;
+                }
+                None => {
+                    missing.push(FixField::Date);
+                    missing.push(FixField::MagneticVariation);
+                }
+            }
+
+            if self.gsa.is_empty() {
+                missing.push(FixField::Dop);
+                // GGA gives hdop too, which is newer than the hdop of the last GSA.
+                fix.hdop = self.gga.as_ref().and_then(|gga| gga.hdop);
+            } else {
+                let first = &self.gsa[0];
+                fix.dimension_fix = first.dimension_fix.clone();
+                fix.pdop = first.pdop;
+                fix.hdop = first.hdop;
+                fix.vdop = first.vdop;
+                for gsa in self.gsa.iter() {
+                    fix.satellites_used.extend(used_satellites(gsa));
+                }
+            }
+
+            match self.gsv {
+                Some(satellites) => fix.satellites_in_view = satellites,
+                None => missing.push(FixField::SatellitesInView),
+            }
+
+            if let Some(last) = last {
+                for field in missing {
+                    fix.copy_stale(last, field);
+                }
+            }
+            fix
+        }
+    }
+
+    fn used_satellites(gsa: &GsaData) -> Vec<i32> {
+        [
+            gsa.sat1, gsa.sat2, gsa.sat3, gsa.sat4, gsa.sat5, gsa.sat6,
+            gsa.sat7, gsa.sat8, gsa.sat9, gsa.sat10, gsa.sat11, gsa.sat12,
+        ]
+            .iter()
+            .filter_map(|id| *id)
+            .collect()
+    }
+
+    /// UTC of the sentence, if it has one.
+    fn sentence_utc(sentence: &GpsSentence) -> Option<UtcTime> {
+        match sentence {
+            GpsSentence::GGA(gga) => gga.utc,
+            GpsSentence::RMC(rmc) => rmc.utc,
+            GpsSentence::GLL(gll) => gll.utc,
+            GpsSentence::ZDA(zda) => zda.utc,
+            GpsSentence::GNS(gns) => gns.utc,
+            _ => None,
+        }
+    }
+
+    /// # FixAggregator
+    /// Give it sentences with push() and it gives a Fix when an epoch is complete.
+    ///
+    /// ```
+    /// use adafruit_gps::{FixAggregator, Gps, SentenceIter};
+    ///
+    /// let bytes = b"$GNGGA,131613.000,5132.7314,N,00005.9099,W,1,9,1.17,42.4,M,47.0,M,,*60\r\n";
+    /// let mut gps = Gps::from_bytes(bytes);
+    /// let mut aggregator = FixAggregator::new();
+    /// for sentence in gps.sentences().stop_after(1).valid() {
+    ///     if let Some(fix) = aggregator.push(sentence) {
+    ///         println!("{:?}", fix);
+    ///     }
+    /// }
+    /// let last = aggregator.flush().unwrap();
+    /// assert_eq!(last.altitude, Some(42.4));
+    /// ```
+    #[derive(Debug, Default)]
+    pub struct FixAggregator {
+        epoch: Epoch,
+        last: Option<Fix>,
+        expected: Option<Vec<SentenceType>>,
+        learnt: Vec<SentenceType>,
+        dates: DateTracker,
+        /// UTC of the epoch given as soon as it was complete. Sentences of that epoch given after it
+        /// are dropped until a new UTC is given.
+        flushed: Option<UtcTime>,
+    }
+
+    impl FixAggregator {
+        pub fn new() -> FixAggregator {
+            FixAggregator::default()
+        }
+
+        /// Expect the sentences given once every fix (frequency 1) in the nmea output.
+        /// Sentences given less often, such as GSV every 5th fix, are copied as stale.
+        pub fn expect(&mut self, output: &NmeaOutput) {
+            let frequencies = [
+                (output.gga, SentenceType::GGA),
+                (output.vtg, SentenceType::VTG),
+                (output.gsa, SentenceType::GSA),
+                (output.gsv, SentenceType::GSV),
+                (output.gll, SentenceType::GLL),
+                (output.rmc, SentenceType::RMC),
+            ];
+            self.expected = Some(
+                frequencies.iter()
+                    .filter(|(frequency, _)| *frequency == 1)
+                    .map(|(_, sentence_type)| *sentence_type)
+                    .collect(),
+            );
+        }
+
+        /// Adds a sentence to the epoch. Gives the Fix of an epoch when it is complete.
+        ///
+        /// Sentences without data, such as NoConnection, are ignored. Once an epoch is given because
+        /// every expected sentence was seen, sentences after it with the same UTC or no UTC, such as a
+        /// GSA not given every fix, are dropped rather than merged into the next second's Fix.
+        pub fn push(&mut self, sentence: GpsSentence) -> Option<Fix> {
+            if !sentence.is_valid() {
+                return None;
+            }
+            let utc = sentence_utc(&sentence);
+            if self.flushed.is_some() {
+                if utc.is_none() || utc == self.flushed {
+                    return None;
+                }
+                self.flushed = None;
+            }
+            let mut fix = None;
+            if let Some(utc) = utc {
+                match self.epoch.utc {
+                    Some(epoch_utc) if epoch_utc != utc => {
+                        // A new epoch: the last one is complete, and it shows what is given each fix.
+                        for sentence_type in self.epoch.sentences.iter() {
+                            if !self.learnt.contains(sentence_type) {
+                                self.learnt.push(*sentence_type);
+                            }
+                        }
+                        fix = self.flush();
+                    }
+                    _ => {}
+                }
+                self.epoch.utc = Some(utc);
+            }
+            let datetime = match &sentence {
+                GpsSentence::RMC(rmc) => rmc.datetime(),
+                GpsSentence::ZDA(zda) => zda.datetime(),
+                _ => None,
+            };
+            if let Some(datetime) = datetime {
+                self.dates.set(datetime.date, datetime.time);
+            }
+            self.epoch.add(sentence);
+
+            if fix.is_none() && self.is_complete() {
+                let utc = self.epoch.utc;
+                fix = self.flush();
+                self.flushed = utc;
+            }
+            fix
+        }
+
+        /// Gives the Fix of the current epoch, even if it is not complete.
+        pub fn flush(&mut self) -> Option<Fix> {
+            if self.epoch.is_empty() {
+                return None;
+            }
+            let epoch = std::mem::take(&mut self.epoch);
+            let mut fix = epoch.into_fix(self.last.as_ref());
+            fix.datetime = fix.utc.and_then(|utc| self.dates.datetime(utc));
+            if let Some(datetime) = fix.datetime {
+                fix.date = Some(datetime.date);
+            }
+            self.last = Some(fix.clone());
+            Some(fix)
+        }
+
+        /// True when every expected sentence type has been seen this epoch.
+        fn is_complete(&self) -> bool {
+            let expected = self.expected.as_ref().unwrap_or(&self.learnt);
+            !expected.is_empty() && expected.iter().all(|s| self.epoch.sentences.contains(s))
+        }
+    }
+}
+
+#[cfg(test)]
+mod aggregate_test {
+    use crate::nmea::gsa::DimensionFix;
+    use crate::open_gps::gps::Gps;
+    use crate::pmtk::send_pmtk::NmeaOutput;
+    use crate::time::utc::{UtcDate, UtcTime};
+
+    use super::aggregate::{FixAggregator, FixField, SentenceType};
+
+    const EPOCH_1: &str = "$GNGGA,131613.000,5132.7314,N,00005.9099,W,1,9,1.17,42.4,M,47.0,M,,*60\r\n\
+$GPGSA,A,3,29,02,26,25,31,14,,,,,,,1.42,1.17,0.80*07\r\n\
+$GPGSV,1,1,04,12,12,100,30,04,11,331,,16,06,282,,05,05,074,22*70\r\n\
+$GNRMC,131613.000,A,5132.7314,N,00005.9099,W,0.11,0.00,250520,,,A*6D\r\n\
+$GNVTG,0.00,T,,M,0.11,N,0.20,K,A*21\r\n";
+
+    const EPOCH_2: &str = "$GNGGA,131614.000,5132.7314,N,00005.9099,W,1,8,1.17,42.4,M,47.0,M,,*66\r\n\
+$GNRMC,131614.000,A,5132.7314,N,00005.9099,W,0.11,0.00,250520,,,A*6A\r\n\
+$GNVTG,0.00,T,,M,0.11,N,0.20,K,A*21\r\n";
+
+    fn fixes(bytes: &str, aggregator: &mut FixAggregator) -> Vec<super::aggregate::Fix> {
+        let mut gps = Gps::from_bytes(bytes.as_bytes());
+        let mut fixes = Vec::new();
+        loop {
+            let sentence = gps.update();
+            if !sentence.is_valid() {
+                break;
+            }
+            fixes.extend(aggregator.push(sentence));
+        }
+        fixes.extend(aggregator.flush());
+        fixes
+    }
+
+    #[test]
+    fn merge_epoch() {
+        let fix = fixes(EPOCH_1, &mut FixAggregator::new()).remove(0);
+        assert_eq!(fix.utc, UtcTime::parse("131613.000"));
+        assert_eq!(fix.date, UtcDate::parse("250520"));
+        assert_eq!(fix.datetime.map(|datetime| datetime.unix_timestamp()), Some(1_590_412_573.0));
+        assert_eq!(fix.latitude, Some(51.545525));
+        assert_eq!(fix.longitude, Some(-0.098498));
+        assert_eq!(fix.altitude, Some(42.4));
+        assert_eq!(fix.geoidal_sep, Some(47.0));
+        assert_eq!(fix.speed_kph, Some(0.2));
+        assert_eq!(fix.course, Some(0.0));
+        assert_eq!(fix.dimension_fix, DimensionFix::Dimension3d);
+        assert_eq!(fix.pdop, Some(1.42));
+        assert_eq!(fix.satellites_used, vec![29, 2, 26, 25, 31, 14]);
+        assert_eq!(fix.satellites_in_view.len(), 4);
+        assert!(fix.stale.is_empty());
+    }
+
+    #[test]
+    fn subset_is_stale() {
+        let mut aggregator = FixAggregator::new();
+        let fixes = fixes(&[EPOCH_1, EPOCH_2].concat(), &mut aggregator);
+        assert_eq!(fixes.len(), 2);
+        let fix = &fixes[1];
+        assert_eq!(fix.utc, UtcTime::parse("131614.000"));
+        assert_eq!(fix.sentences, vec![SentenceType::GGA, SentenceType::RMC, SentenceType::VTG]);
+        // Copied from the first fix.
+        assert!(fix.is_stale(FixField::Dop));
+        assert!(fix.is_stale(FixField::SatellitesInView));
+        assert_eq!(fix.pdop, Some(1.42));
+        assert_eq!(fix.satellites_in_view.len(), 4);
+        assert!(!fix.is_stale(FixField::Position));
+    }
+
+    #[test]
+    fn stale_dop_keeps_gga_hdop() {
+        let epoch_2 = EPOCH_2.replace(",8,1.17,", ",8,2.05,");
+        let fixes = fixes(&[EPOCH_1, &epoch_2].concat(), &mut FixAggregator::new());
+        let fix = &fixes[1];
+        assert!(fix.is_stale(FixField::Dop));
+        assert_eq!((fix.pdop, fix.hdop, fix.vdop), (Some(1.42), Some(2.05), Some(0.80)));
+        assert_eq!(fix.dimension_fix, DimensionFix::Dimension3d);
+    }
+
+    #[test]
+    fn expected_output_gives_fix_without_waiting() {
+        let mut aggregator = FixAggregator::new();
+        aggregator.expect(&NmeaOutput { gll: 0, rmc: 1, vtg: 1, gga: 1, gsa: 0, gsv: 0, zda: 0, pmtkchn_interval: 0 });
+        let mut gps = Gps::from_bytes(EPOCH_2.as_bytes());
+        assert_eq!(aggregator.push(gps.update()), None);
+        assert_eq!(aggregator.push(gps.update()), None);
+        let fix = aggregator.push(gps.update()).unwrap();
+        assert_eq!(fix.utc, UtcTime::parse("131614.000"));
+        // Nothing was given before, so nothing is stale, only missing.
+        assert!(fix.stale.is_empty());
+        assert_eq!(fix.pdop, None);
+        assert_eq!(fix.hdop, Some(1.17));
+    }
+
+    #[test]
+    fn late_sentence_after_complete_epoch_dropped() {
+        let mut aggregator = FixAggregator::new();
+        aggregator.expect(&NmeaOutput { gll: 0, rmc: 1, vtg: 1, gga: 1, gsa: 0, gsv: 0, zda: 0, pmtkchn_interval: 0 });
+        let epoch_1: Vec<&str> = EPOCH_1.lines().collect();
+        // GSA, given after the epoch was complete, has no UTC of its own.
+        let bytes = [epoch_1[0], epoch_1[3], epoch_1[4], epoch_1[1], EPOCH_2].join("\n");
+        let fixes = fixes(&bytes, &mut aggregator);
+        assert_eq!(fixes.len(), 2);
+        assert_eq!(fixes[0].utc, UtcTime::parse("131613.000"));
+        let fix = &fixes[1];
+        assert_eq!(fix.utc, UtcTime::parse("131614.000"));
+        assert_eq!(fix.sentences, vec![SentenceType::GGA, SentenceType::RMC, SentenceType::VTG]);
+        assert_eq!(fix.pdop, None);
+        assert!(fix.satellites_used.is_empty());
+    }
+
+    #[test]
+    fn zda_gives_date() {
+        let bytes = "$GNGGA,131613.000,5132.7314,N,00005.9099,W,1,9,1.17,42.4,M,47.0,M,,*60\r\n\
+$GPZDA,131613.000,25,05,2020,,*53\r\n";
+        let fix = fixes(bytes, &mut FixAggregator::new()).remove(0);
+        assert_eq!(fix.date, UtcDate::parse("250520"));
+        assert_eq!(fix.datetime.map(|datetime| datetime.unix_timestamp()), Some(1_590_412_573.0));
+    }
+
+    #[test]
+    fn next_fix() {
+        let bytes = [EPOCH_1, EPOCH_2].concat();
+        let mut gps = Gps::from_bytes(bytes.as_bytes());
+        assert_eq!(gps.next_fix().and_then(|fix| fix.utc), UtcTime::parse("131613.000"));
+        assert_eq!(gps.next_fix().and_then(|fix| fix.utc), UtcTime::parse("131614.000"));
+        assert_eq!(gps.next_fix(), None);
+    }
+}
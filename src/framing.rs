@@ -0,0 +1,506 @@
+//! Framing of the bytes given by the gps into sentences.
+//!
+//! Everything here works on raw bytes rather than &str so that garbage on the wire, such as a baud
+//! rate mismatch or a half received multi-byte character, can never cause a panic.
+
+pub mod check {
+    //! Checks a single line is a complete sentence with a valid checksum.
+    //!
+    //! A sentence is: ${body}*{two hex digits}, optionally followed by \r\n. AIS style sentences
+    //! starting with ! are also accepted.
+
+    /// # Verdict on a line
+    /// - Valid -> The checksum matches the body.
+    /// - BadChecksum -> The checksum is well formed but does not match. given is the checksum at
+    ///   the end of the sentence, calculated is the checksum of the body.
+    /// - MissingChecksum -> There is no *, so there is no checksum to check.
+    /// - Truncated -> The line is empty or ends before the two checksum digits, probably only part of
+    ///   the sentence was read.
+    /// - NoStart -> The line does not start with $ or !.
+    /// - InvalidChecksum -> The checksum is not exactly two hex digits.
+    /// - InvalidCharacter -> The body has a byte that is not printable ascii.
+    #[derive(Debug, PartialEq, Clone)]
+    pub enum Verdict {
+        Valid,
+        BadChecksum { given: u8, calculated: u8 },
+        MissingChecksum,
+        Truncated,
+        NoStart,
+        InvalidChecksum,
+        InvalidCharacter,
+    }
+
+    /// Removes leading and trailing whitespace, including the \r\n line ending.
+    pub fn trim(bytes: &[u8]) -> &[u8] {
+        let start = bytes.iter().position(|b| !b.is_ascii_whitespace()).unwrap_or(bytes.len());
+        let end = bytes.iter().rposition(|b| !b.is_ascii_whitespace()).map_or(start, |i| i + 1);
+        &bytes[start..end]
+    }
+
+    /// XOR of all the bytes given.
+    pub fn checksum(bytes: &[u8]) -> u8 {
+        bytes.iter().fold(0, |acc, b| acc ^ b)
+    }
+
+    fn hex_value(byte: u8) -> Option<u8> {
+        match byte {
+            b'0'..=b'9' => Some(byte - b'0'),
+            b'A'..=b'F' => Some(byte - b'A' + 10),
+            b'a'..=b'f' => Some(byte - b'a' + 10),
+            _ => None,
+        }
+    }
+
+    /// Gives the verdict on a line. Surrounding whitespace is ignored.
+    pub fn check_sentence(bytes: &[u8]) -> Verdict {
+        let line = trim(bytes);
+        let (start, rest) = match line.split_first() {
+            Some(split) => split,
+            None => return Verdict::Truncated,
+        };
+        if *start != b'$' && *start != b'!' {
+            return Verdict::NoStart;
+        }
+        let star = match rest.iter().rposition(|b| *b == b'*') {
+            Some(star) => star,
+            None => return Verdict::MissingChecksum,
+        };
+        let body = &rest[..star];
+        let given = &rest[star + 1..];
+        if given.len() < 2 {
+            return Verdict::Truncated;
+        }
+        if given.len() > 2 {
+            return Verdict::InvalidChecksum;
+        }
+        let given = match (hex_value(given[0]), hex_value(given[1])) {
+            (Some(high), Some(low)) => high * 16 + low,
+            _ => return Verdict::InvalidChecksum,
+        };
+        if body.iter().any(|b| !(b' '..=b'~').contains(b)) {
+            return Verdict::InvalidCharacter;
+        }
+        let calculated = checksum(body);
+        if given == calculated {
+            Verdict::Valid
+        } else {
+            Verdict::BadChecksum { given, calculated }
+        }
+    }
+}
+
+pub mod framer {
+    //! Splits a stream of bytes into lines.
+    //!
+    //! The port is read in bulk into a ring buffer, rather than one byte per read call. Partial lines
+    //! are kept between calls so a sentence split over two reads is not lost.
+
+    use std::collections::VecDeque;
+    use std::io::{ErrorKind, Read};
+    use std::time::{Duration, Instant};
+
+    use crate::error::GpsError;
+
+    /// Longest line given before it is cut, without a \n. PMTKLOX lines are the longest at ~230 bytes.
+    pub const MAX_LINE: usize = 256;
+    /// Longest MTK binary packet given, the EPO packets are 191 bytes.
+    pub const MAX_PACKET: usize = 256;
+    /// Shortest MTK binary packet: preamble, length, command, checksum and end with no data.
+    const MIN_PACKET: usize = 9;
+    /// Most bytes asked of the port per read call. The serial port buffer is 4095 bytes.
+    const READ_SIZE: usize = 1024;
+
+    /// # Framer
+    /// Ring buffer of bytes read from the port.
+    ///
+    /// - Lines are split on \n, the \r\n is kept on the line given.
+    /// - Garbage before a $ or ! is dropped. If a new $ starts before the \n the partial sentence
+    ///   before it is dropped.
+    /// - A line with no $ or ! at all is given as is, so a baud rate mismatch can still be seen.
+    /// - Lines longer than MAX_LINE without a \n are cut at MAX_LINE bytes.
+    #[derive(Debug, Default)]
+    pub struct Framer {
+        buffer: VecDeque<u8>,
+        ended: bool,
+    }
+
+    impl Framer {
+        pub fn new() -> Framer {
+            Framer { buffer: VecDeque::with_capacity(READ_SIZE * 4), ended: false }
+        }
+
+        /// Number of bytes waiting to be framed.
+        pub fn len(&self) -> usize {
+            self.buffer.len()
+        }
+
+        pub fn is_empty(&self) -> bool {
+            self.buffer.is_empty()
+        }
+
+        /// True when the last read of the port gave the end of the file, so waiting longer will not
+        /// give more bytes.
+        pub fn ended(&self) -> bool {
+            self.ended
+        }
+
+        /// Adds bytes read from the port.
+        pub fn push(&mut self, bytes: &[u8]) {
+            self.buffer.extend(bytes);
+        }
+
+        /// Gives the next full line, if there is one.
+        pub fn next_line(&mut self) -> Option<Vec<u8>> {
+            let end = match self.buffer.iter().take(MAX_LINE).position(|b| *b == b'\n') {
+                Some(newline) => newline + 1,
+                None if self.buffer.len() >= MAX_LINE => MAX_LINE,
+                None => return None,
+            };
+            let line: Vec<u8> = self.buffer.drain(..end).collect();
+            Some(resync(line))
+        }
+
+        /// Gives whatever is left in the buffer as a line, used at the end of a file.
+        pub fn take_remaining(&mut self) -> Option<Vec<u8>> {
+            if self.buffer.is_empty() {
+                return None;
+            }
+            let line: Vec<u8> = self.buffer.drain(..).collect();
+            Some(resync(line))
+        }
+
+        /// Gives the next full MTK binary packet, if there is one, as used when the gps is in binary
+        /// mode: 0x04 0x24, the length of the whole packet (u16 little endian), ..., 0x0D 0x0A.
+        ///
+        /// Bytes before the 0x04 0x24 are dropped, as is a start with a length that can not be right
+        /// or no 0x0D 0x0A at the end. The checksum is not checked.
+        pub fn next_packet(&mut self) -> Option<Vec<u8>> {
+            loop {
+                let start = (1..self.buffer.len()).find(|i| self.buffer[i - 1] == 0x04 && self.buffer[*i] == 0x24);
+                match start {
+                    Some(start) => self.buffer.drain(..start - 1),
+                    // Keep a 0x04 at the end, it may be the start of a packet.
+                    None => {
+                        let keep = self.buffer.back() == Some(&0x04);
+                        self.buffer.drain(..self.buffer.len() - keep as usize)
+                    }
+                };
+                if self.buffer.len() < 4 {
+                    return None;
+                }
+                let length = u16::from_le_bytes([self.buffer[2], self.buffer[3]]) as usize;
+                if !(MIN_PACKET..=MAX_PACKET).contains(&length) {
+                    self.buffer.drain(..2);
+                    continue;
+                }
+                if self.buffer.len() < length {
+                    return None;
+                }
+                if self.buffer[length - 2] != b'\r' || self.buffer[length - 1] != b'\n' {
+                    self.buffer.drain(..2);
+                    continue;
+                }
+                return Some(self.buffer.drain(..length).collect());
+            }
+        }
+
+        /// Reads from the port until a full line is framed.
+        ///
+        /// Errors:
+        /// - GpsError::Timeout when no full line is given before the timeout, or the end of a file is
+        ///   reached with nothing left in the buffer.
+        /// - GpsError::Io when the port can no longer be read.
+        pub fn read_line<R: Read>(&mut self, port: &mut R, timeout: Duration) -> Result<Vec<u8>, GpsError> {
+            self.read_until(port, timeout, Framer::next_line, Framer::take_remaining)
+        }
+
+        /// Reads from the port until a full binary packet is framed, see next_packet.
+        ///
+        /// Gives GpsError::Timeout at the end of a file, as a part packet is not given.
+        pub fn read_packet<R: Read>(&mut self, port: &mut R, timeout: Duration) -> Result<Vec<u8>, GpsError> {
+            self.read_until(port, timeout, Framer::next_packet, |_framer| None)
+        }
+
+        /// Reads from the port until next gives something. At the end of a file gives what is left
+        /// from remaining, or GpsError::Timeout.
+        fn read_until<R: Read>(
+            &mut self,
+            port: &mut R,
+            timeout: Duration,
+            next: fn(&mut Framer) -> Option<Vec<u8>>,
+            remaining: fn(&mut Framer) -> Option<Vec<u8>>,
+        ) -> Result<Vec<u8>, GpsError> {
+            let mut chunk = [0u8; READ_SIZE];
+            let start = Instant::now();
+            loop {
+                if let Some(framed) = next(self) {
+                    return Ok(framed);
+                }
+                if start.elapsed() > timeout {
+                    return Err(GpsError::Timeout);
+                }
+                match port.read(&mut chunk) {
+                    // End of file: nothing more will be given.
+                    Ok(0) => {
+                        self.ended = true;
+                        return remaining(self).ok_or(GpsError::Timeout);
+                    }
+                    Ok(size) => {
+                        self.ended = false;
+                        self.push(&chunk[..size])
+                    }
+                    Err(e) => match e.kind() {
+                        // Serial port read timed out, keep going until the timeout is up.
+                        ErrorKind::TimedOut | ErrorKind::WouldBlock | ErrorKind::Interrupted => (),
+                        _ => return Err(GpsError::Io(e)),
+                    },
+                }
+            }
+        }
+    }
+
+    /// Drops everything before the last $ or !. A line with neither is given unchanged.
+    fn resync(mut line: Vec<u8>) -> Vec<u8> {
+        if let Some(start) = line.iter().rposition(|b| *b == b'$' || *b == b'!') {
+            line.drain(..start);
+        }
+        line
+    }
+}
+
+#[cfg(test)]
+mod check_test {
+    use std::time::Duration;
+
+    use crate::nmea::parse_nmea::parse_sentence;
+    use crate::open_gps::gps::Gps;
+
+    use super::check::{check_sentence, Verdict};
+
+    #[test]
+    fn verdicts() {
+        assert_eq!(check_sentence(b"$PMTK220,100*2F"), Verdict::Valid);
+        assert_eq!(check_sentence(b"  $PMTK220,100*2f\r\n"), Verdict::Valid);
+        assert_eq!(check_sentence(b"!AIVDM,1,1,,A,13aEOK?P00PD2wVMdLDRhgvL289?,0*26"), Verdict::Valid);
+        assert_eq!(check_sentence(b"$PMTK220,100*2E"), Verdict::BadChecksum { given: 0x2E, calculated: 0x2F });
+        assert_eq!(check_sentence(b"$PMTK220,100"), Verdict::MissingChecksum);
+        assert_eq!(check_sentence(b"$PMTK220,100*2"), Verdict::Truncated);
+        assert_eq!(check_sentence(b"$PMTK220,100*"), Verdict::Truncated);
+        assert_eq!(check_sentence(b""), Verdict::Truncated);
+        assert_eq!(check_sentence(b"\r\n"), Verdict::Truncated);
+        assert_eq!(check_sentence(b"PMTK220,100*2F"), Verdict::NoStart);
+        assert_eq!(check_sentence(b"$PMTK220,100*2G"), Verdict::InvalidChecksum);
+        assert_eq!(check_sentence(b"$PMTK220,100*2F2F"), Verdict::InvalidChecksum);
+        assert_eq!(check_sentence("$PMTK220,1é0*2F".as_bytes()), Verdict::InvalidCharacter);
+    }
+
+    /// Inputs that have caused, or could cause, a panic when slicing.
+    const HOSTILE: &[&[u8]] = &[
+        b"",
+        b"$",
+        b"*",
+        b"**",
+        b"$*",
+        b"$**",
+        b"$*00",
+        b"*00",
+        b"\r\n",
+        b"\n",
+        b"$\r\n",
+        b"$GP",
+        b"$GPGGA",
+        b"$GPGGA*",
+        b"$GPGGA*5",
+        b"$GPGGA*56",
+        b"$G*56",
+        b"$GPGG*7F",
+        b"$GPGSV,*1E",
+        b"$GPGSV,4,1,99,*0D",
+        b"$GPGGA,,,,,,,,,,,,,,*7A",
+        b"$GPRMC,,,,,,,,,,,,*67",
+        b"$PMTK*",
+        b"$PMTK001*",
+        b"$PMTK001,*0E",
+        b"$PMTK01*03",
+        b"$PMTK705*36",
+        b"$\xff\xfe*00",
+        b"\xff\xff\xff\xff\xff\xff\xff\xff\xff\n",
+        b"$PMTK\xc3\xa9*00\r\n",
+        b"\xc3\xa9\xc3\xa9\xc3\xa9*00",
+        b"$\xe2\x82\xac\xe2\x82\xac\xe2\x82\xac",
+        b"\xe2\x82",
+        b"$GPGGA,123519,4807.038,N,01131.000,E,1,08,0.9,545.4,M,46.9,M,,*4\xc3\xa9",
+        b"$GPGGA,123519,4807.038,N,01131.000,E,1,08,0.9,545.4,M,46.9,M,,*47*47",
+        b"$$$$$$$$$$$$$$$$$$$$$$$$$$$$$$$$$$$$$$$$$$",
+        b"$GPGSA,A,3,29,02,26,25,31,14,,,,,,,1.42,1.17,0.80*07$GPGSA,A,3,*07",
+        b"\0\0\0\0\0\0\0\0",
+    ];
+
+    /// Simple deterministic pseudo random bytes, biased towards bytes that matter when framing.
+    fn random_lines(count: usize) -> Vec<Vec<u8>> {
+        let alphabet: &[u8] = b"$!*,.0123456789ABCDEFGNPMTKabcdef\r\n \xc3\xa9\xff\x00";
+        let mut state: u32 = 0x2545_F491;
+        let mut next = || {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state
+        };
+        (0..count)
+            .map(|_| {
+                let len = (next() % 40) as usize;
+                (0..len).map(|_| alphabet[next() as usize % alphabet.len()]).collect()
+            })
+            .collect()
+    }
+
+    fn survives(input: &[u8]) {
+        let _ = check_sentence(input);
+        if let Ok(s) = std::str::from_utf8(input) {
+            let _ = parse_sentence(s);
+        }
+        let mut gps = Gps::from_bytes(input);
+        let _ = gps.update();
+        let mut gps = Gps::from_bytes(input);
+        let _ = gps.pmtk_001(220, Duration::from_millis(10));
+        let mut gps = Gps::from_bytes(input);
+        let _ = gps.pmtk_500();
+        let mut gps = Gps::from_bytes(input);
+        let _ = gps.pmtk_startup();
+    }
+
+    #[test]
+    fn hostile_inputs() {
+        for input in HOSTILE {
+            survives(input);
+        }
+    }
+
+    #[test]
+    fn random_inputs() {
+        for input in random_lines(2_000) {
+            survives(&input);
+        }
+    }
+}
+
+#[cfg(test)]
+mod framer_test {
+    use std::io::{self, Read};
+    use std::time::Duration;
+
+    use crate::error::GpsError;
+
+    use super::framer::{Framer, MAX_LINE};
+
+    const GGA: &[u8] = b"$GNGGA,131613.000,5132.7314,N,00005.9099,W,1,9,1.17,42.4,M,47.0,M,,*60\r\n";
+    const GSA: &[u8] = b"$GPGSA,A,3,29,02,26,25,31,14,,,,,,,1.42,1.17,0.80*07\r\n";
+
+    /// Gives the bytes a few at a time, then times out, like a slow serial port.
+    struct Trickle {
+        bytes: Vec<u8>,
+        step: usize,
+    }
+
+    impl Read for Trickle {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            if self.bytes.is_empty() {
+                return Err(io::Error::new(io::ErrorKind::TimedOut, "no bytes"));
+            }
+            let size = self.step.min(buf.len()).min(self.bytes.len());
+            buf[..size].copy_from_slice(&self.bytes[..size]);
+            self.bytes.drain(..size);
+            Ok(size)
+        }
+    }
+
+    #[test]
+    fn split_lines() {
+        let mut framer = Framer::new();
+        framer.push(&[GGA, GSA].concat());
+        assert_eq!(framer.next_line(), Some(GGA.to_vec()));
+        assert_eq!(framer.next_line(), Some(GSA.to_vec()));
+        assert_eq!(framer.next_line(), None);
+    }
+
+    #[test]
+    fn partial_line_kept() {
+        let mut framer = Framer::new();
+        framer.push(&GGA[..20]);
+        assert_eq!(framer.next_line(), None);
+        framer.push(&GGA[20..]);
+        assert_eq!(framer.next_line(), Some(GGA.to_vec()));
+        assert!(framer.is_empty());
+    }
+
+    #[test]
+    fn resync_after_garbage() {
+        let mut framer = Framer::new();
+        framer.push(b"\xff\x00garbage");
+        framer.push(GGA);
+        // Half a sentence, then a new one starts.
+        framer.push(&GSA[..15]);
+        framer.push(GSA);
+        assert_eq!(framer.next_line(), Some(GGA.to_vec()));
+        assert_eq!(framer.next_line(), Some(GSA.to_vec()));
+    }
+
+    #[test]
+    fn garbage_without_start_is_given() {
+        let mut framer = Framer::new();
+        framer.push(b"\xff\xfe\xfd\n");
+        assert_eq!(framer.next_line(), Some(b"\xff\xfe\xfd\n".to_vec()));
+    }
+
+    #[test]
+    fn long_line_cut() {
+        let mut framer = Framer::new();
+        framer.push(&[b'A'; MAX_LINE + 10]);
+        assert_eq!(framer.next_line().map(|l| l.len()), Some(MAX_LINE));
+        assert_eq!(framer.next_line(), None);
+        assert_eq!(framer.len(), 10);
+    }
+
+    #[test]
+    fn read_line_over_reads() {
+        let mut port = Trickle { bytes: [GGA, GSA].concat(), step: 7 };
+        let mut framer = Framer::new();
+        let timeout = Duration::from_millis(100);
+        assert_eq!(framer.read_line(&mut port, timeout).unwrap(), GGA.to_vec());
+        assert_eq!(framer.read_line(&mut port, timeout).unwrap(), GSA.to_vec());
+        match framer.read_line(&mut port, timeout) {
+            Err(GpsError::Timeout) => (),
+            other => panic!("Expected Timeout, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn binary_packets() {
+        let ack = [0x04, 0x24, 0x0C, 0x00, 0x02, 0x00, 0x0A, 0x00, 0x01, 0x05, 0x0D, 0x0A];
+        let mut port: &[u8] = &[b"$GPGSA,A,1*0A\r\n\x04\x24\xFF\xFF".as_ref(), &ack[..5], &ack[5..], &[0x04]].concat();
+        let mut framer = Framer::new();
+        let timeout = Duration::from_millis(100);
+        assert_eq!(framer.read_packet(&mut port, timeout).unwrap(), ack.to_vec());
+        assert_eq!(framer.len(), 1);
+        assert!(matches!(framer.read_packet(&mut port, timeout), Err(GpsError::Timeout)));
+
+        let mut framer = Framer::new();
+        framer.push(&ack[..11]);
+        assert_eq!(framer.next_packet(), None);
+        framer.push(b"X");
+        assert_eq!(framer.next_packet(), None);
+        assert!(framer.is_empty());
+    }
+
+    #[test]
+    fn read_line_end_of_file() {
+        let mut port: &[u8] = b"$PMTK011,MTKGPS*08";
+        let mut framer = Framer::new();
+        let timeout = Duration::from_millis(100);
+        assert_eq!(framer.read_line(&mut port, timeout).unwrap(), b"$PMTK011,MTKGPS*08".to_vec());
+        match framer.read_line(&mut port, timeout) {
+            Err(GpsError::Timeout) => (),
+            other => panic!("Expected Timeout, got {:?}", other),
+        }
+    }
+}
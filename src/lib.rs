@@ -13,6 +13,19 @@
 //! The nmea module reads the data given by the GPS. Use the gps.update() trait to get easy to use
 //! data, but for specific use cases custom commands can be read.
 //!
+//! ## Ports
+//! Gps is generic over any Read + Write port. Gps::new opens the serial port, Gps::from_file reads a
+//! capture of the gps output and Gps::from_bytes reads from memory, which is handy for testing.
+//! Gps::from_port takes anything else, such as a pipe or a pseudo-terminal.
+//!
+//! The port is read in bulk by a Framer, which splits the bytes into lines and keeps partial lines
+//! between reads. Gps::set_timeout changes how long to wait for a full sentence, default 1 second.
+//!
+//! ## Async
+//! With the `async` feature, AsyncGps reads over any tokio AsyncRead + AsyncWrite port and gives
+//! sentences as a Stream of Result<GpsSentence, GpsError>. It also has async send_command, pmtk_001
+//! and pmtk_500.
+//!
 //! ## Hardware specs
 //! Please read the docs for the specific GPS module you are using.
 //!
@@ -27,12 +40,43 @@
 //! - GGA(GgaData) -> [GgaData](nmea/gga/struct.GgaData.html): Latitude, Longitude, Position fix, Satellites seen, HDOP, altitude, Geoidal Seperation, Age of difference correction.
 //! - VTG(VtgData) -> [VtgData](nmea/vtg/struct.VtgData.html): Course (true), Course (magnetic), speed knots, speed kph.
 //! - GSA(GsaData) -> [GsaData](nmea/gsa/struct.GsaData.html): List of satellites used, PDOP, HDOP, VDOP.
-//! - GSV(Vec<Satellites>) -> [Satellites](nmea/gsv/struct.Satellites.html): Satellites in view data: sat id, elevation, azimuth and SNR for each sat seen.
+//! - GSV(SkyView) -> [SkyView](nmea/gsv/struct.SkyView.html): Satellites in view for one constellation: sat id, elevation, azimuth and SNR for each sat seen.
 //! - GLL(GllData) -> [GllData](nmea/gll/struct.GllData.html): Latitude, Longitude only.
 //! - RMC(RmcData) -> [RmcData](nmea/rmc/struct.RmcData.html): UTC, Latitude, Longitude, speed, course, date, magnetic variation.
+//! - ZDA(ZdaData) -> [ZdaData](nmea/zda/struct.ZdaData.html): UTC, date with four digit year, local zone offset.
+//! - GNS(GnsData) -> [GnsData](nmea/gns/struct.GnsData.html): UTC, Latitude, Longitude, mode for each constellation, Satellites used, HDOP, altitude.
+//! - GST(GstData) -> [GstData](nmea/gst/struct.GstData.html): Standard deviation of the latitude, longitude and altitude errors.
+//! - GBS(GbsData) -> [GbsData](nmea/gbs/struct.GbsData.html): Expected errors and the most likely failed satellite.
+//! - GRS(GrsData) -> [GrsData](nmea/grs/struct.GrsData.html): Range residuals for each satellite used.
+//! - DTM(DtmData) -> [DtmData](nmea/dtm/struct.DtmData.html): Local datum and its offsets from the reference datum.
+//! - SystemMessage(SystemMessage) -> [SystemMessage](mtk/enum.SystemMessage.html): $PMTK010 startup,
+//!   EPO notification, normal mode and wake-up messages.
+//! - TextMessage(TextMessage) -> [TextMessage](mtk/struct.TextMessage.html): $PMTK011 text, given
+//!   after a restart.
+//! - AntennaStatus(AntennaStatus) -> [AntennaStatus](gtop/enum.AntennaStatus.html): $PGTOP internal or
+//!   external antenna, or external antenna shorted. Turned on with gps.pgcmd_33_antenna_status(true).
+//! - ChannelStatus(ChannelStatus) -> [ChannelStatus](mtk/struct.ChannelStatus.html): $PMTKCHN satellite
+//!   id, SNR and tracking status for each receiver channel. Turned on with pmtkchn_interval in NmeaOutput.
+//! - Other { talker, kind, fields } -> A sentence with a valid checksum but no parser, such as $PMTK
+//!   replies. Parsers for other sentences can be added with gps.register(), see
+//!   [Registry](parser/registry/struct.Registry.html).
 //! - NoConnection -> The gps is not connected, no bytes are being received
 //! - InvalidBytes -> Bytes being received are not valid, probably port baud rate and gps baud rate mismatch
 //! - InvalidSentence -> Sentence outputted has incorrect checksum, the sentence was probably incomplete.
+//!   check_sentence() gives the Verdict on why: bad checksum, missing checksum, truncated etc.
+//! - ParseError(NmeaParseError) -> Sentence checksum is valid but a field is missing or could not be
+//!   parsed. Gives the sentence type, field index and reason.
+//!
+//! ## Fixes
+//! gps.next_fix() merges the GGA, RMC, VTG, GSA and GSV sentences for one UTC epoch into a single
+//! [Fix](fix/aggregate/struct.Fix.html), with the date, position, altitude, speed, course, DOPs and
+//! satellites. Fields from sentences not given every fix are copied from the last fix and flagged as
+//! stale. FixAggregator does the same for sentences from anywhere else, such as a saved file.
+//!
+//! ## Sending NMEA
+//! GgaData, RmcData, VtgData, GsaData and GllData have to_nmea(talker), which gives the sentence with its
+//! checksum, and SkyView gives its GSV group. Parsing the sentences given gives the same data back, so
+//! filtered or corrected fixes can be sent on to other NMEA 0183 devices.
 //!
 //! # Some technical information
 //! ## Dilution of precision
@@ -63,25 +107,95 @@
 //! measuring the difference in height between objects.
 //!
 //! ## Saving data
-//! GpsSentence types can be written and read to a bytes file using the the append_to() and read_from()
+//! GpsSentence types can be written and read to a log file using the the append_to() and read_from()
 //! traits: See examples/example_io.rs for details.
-//! ```
+//! ```no_run
 //! use adafruit_gps::GpsSentence;
 //! use adafruit_gps::gga::GgaData;
-//! let data: Vec<GpsSentence> = GpsSentence::read_from("file"); // Read from file
+//! GpsSentence::GGA(GgaData::default()).append_to("file").unwrap(); // Append a single item to a file
+//!
+//! let data: Vec<GpsSentence> = GpsSentence::read_from("file").unwrap(); // Read from file
+//! ```
+//!
+//! The log has a header with its version, and each record has its length, receive time and a CRC, see
+//! [record::log](record/log/index.html). read_log() gives the receive times and any corrupt records that
+//! were skipped. Files written by 0.4.1, before the header, are read by read_from(), and
+//! migrate_legacy() rewrites them as logs.
+//!
+//! The sentences as given by the gps can also be kept, so they can be parsed again by a newer version.
+//! gps.capture() tees every line read, with the time it was received, to a .nmea log, and
+//! Gps::replay() reads the log back as fast as possible or in real time:
+//! ```no_run
+//! use adafruit_gps::{Gps, GpsSentence, Pace};
+//!
+//! let mut gps = Gps::new("/dev/serial0", "9600").unwrap();
+//! gps.capture("drive.nmea").unwrap();
+//! for _ in 0..100 {
+//!     gps.update();
+//! }
+//!
+//! let mut replay = Gps::replay("drive.nmea", Pace::RealTime).unwrap();
+//! while replay.update() != GpsSentence::NoConnection {}
+//! ```
 //!
-//! GpsSentence::GGA(GgaData::default()).append_to("file"); // Append a single item to a file
+//! ## LOCUS logger
+//! The MTK chip can log fixes to its own flash while the host is off. gps.pmtk_185_locus_logging()
+//! starts and stops it, gps.pmtk_183_locus_query() gives a LocusStatus with how many records are
+//! logged, gps.pmtk_184_locus_erase() clears it and gps.pmtk_622_locus_dump() reads the flash back
+//! as a LocusDump.
+//!
+//! ## EPO
+//! read_epo() reads an MTK7d.EPO or MTK14.EPO file, and gps.upload_epo() sends it to the gps in binary
+//! mode, checking the ack for each packet and giving the progress to a callback:
+//! ```no_run
+//! use adafruit_gps::{read_epo, Gps};
+//!
+//! let mut gps = Gps::new("/dev/serial0", "9600").unwrap();
+//! let epo = read_epo("MTK7d.EPO").unwrap();
+//! gps.upload_epo(&epo, |sent, total| println!("{}/{}", sent, total)).unwrap();
 //! ```
 //!
 //!
 //!
 
 
-pub use crate::nmea::{gga, gll, gsa, gsv, rmc, vtg};
+pub use crate::nmea::{dtm, gbs, gga, gll, gns, grs, gsa, gst, gsv, rmc, vtg, zda};
+pub use crate::nmea::parse_nmea::{NmeaParseError, ParseErrorReason};
+pub use crate::nmea::talker::Talker;
+pub use crate::capture::raw::{Pace, RawLog, ReplayPort};
+pub use crate::epo::binary::BinaryPacket;
+pub use crate::epo::upload::{read_epo, EpoError, EpoFile};
+pub use crate::error::GpsError;
+pub use crate::fix::aggregate::{Fix, FixAggregator, FixField, SentenceType};
+pub use crate::framing::check::{check_sentence, Verdict};
+pub use crate::framing::framer::Framer;
 pub use crate::open_gps::gps::{Gps, GpsSentence};
-pub use crate::pmtk::send_pmtk::{set_baud_rate, NmeaOutput};
+pub use crate::open_gps::port::{MemoryPort, ReadOnlyPort};
+pub use crate::open_gps::sentences::{SentenceIter, Sentences, StopPolicy, Talkers};
+pub use crate::locus::decode::{decode_locus, record_size, LocusContents, LocusError, LocusFix, PageHeader};
+pub use crate::locus::logger::{LocusDump, LocusState, LocusStatus, LocusType, LoxLine, LOCUS_PAGE_SIZE, LOX_LINE_SIZE};
+pub use crate::parser::registry::{Registry, SentenceParser};
+pub use crate::proprietary::{gtop, mtk};
+pub use crate::record::log::{migrate_legacy, read_log, LogContents, LogError, LogWriter, Record, RecordError};
+pub use crate::time::utc::{DateTracker, GpsTime, UtcDate, UtcDateTime, UtcTime};
+pub use crate::pmtk::command::{PmtkCommand, PmtkReply, PmtkResponse, RetryPolicy};
+pub use crate::pmtk::send_pmtk::{set_baud_rate, DgpsMode, EpoData, NmeaOutput, Pmtk001Ack, Sbas, SbasMode};
+#[cfg(feature = "async")]
+pub use crate::async_gps::gps::AsyncGps;
 
+mod capture;
+mod epo;
+mod error;
+mod fix;
+mod framing;
+mod locus;
 mod nmea;
 mod pmtk;
 mod open_gps;
+mod parser;
+mod proprietary;
+mod record;
+mod time;
+#[cfg(feature = "async")]
+mod async_gps;
 
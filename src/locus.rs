@@ -0,0 +1,629 @@
+//! # LOCUS
+//! LOCUS is the logger built into the MTK chip. Once started it saves a fix to its flash at the
+//! interval set by pmtk_187_locus_config, without the host being connected.
+//!
+//! - gps.pmtk_185_locus_logging(true) -> Start logging, false to stop.
+//! - gps.pmtk_184_locus_erase() -> Erase all the logged data.
+//! - gps.pmtk_183_locus_query() -> [LocusStatus](logger/struct.LocusStatus.html): how full the log is.
+//! - gps.pmtk_622_locus_dump() -> [LocusDump](logger/struct.LocusDump.html): the flash as bytes.
+//!
+//! dump.decode() gives the fixes in the dump, see [decode](decode/index.html).
+
+pub mod logger {
+    //! Parse the $PMTKLOG status and the $PMTKLOX lines of a flash dump.
+    //!
+    //! A dump given after PMTK622 is:
+    //! - $PMTKLOX,0,{lines}*checksum -> Start, and how many data lines follow.
+    //! - $PMTKLOX,1,{line},{word},...,{word}*checksum -> Data: up to 24 words of 8 hex characters.
+    //! - $PMTKLOX,2*checksum -> End, followed by $PMTK001,622,3
+    use crate::nmea::parse_nmea::*;
+
+    /// Bytes in a full $PMTKLOX,1 line: 24 words of 4 bytes.
+    pub const LOX_LINE_SIZE: usize = 96;
+    /// Bytes in each page, or sector, of the LOCUS flash. Each page starts with its header.
+    pub const LOCUS_PAGE_SIZE: usize = 4096;
+
+    /// # LOCUS log type
+    /// - Overlap -> 0: The oldest records are written over when the flash is full.
+    /// - FullStop -> 1: Logging stops when the flash is full.
+    /// - Other -> Any other value, as given.
+    #[derive(Debug, PartialEq, Eq, Clone, Copy)]
+    pub enum LocusType {
+        Overlap,
+        FullStop,
+        Other(u8),
+    }
+
+    impl From<u8> for LocusType {
+        fn from(value: u8) -> LocusType {
+            match value {
+                0 => LocusType::Overlap,
+                1 => LocusType::FullStop,
+                other => LocusType::Other(other),
+            }
+        }
+    }
+
+    /// # LOCUS logging status
+    /// - Logging -> 0
+    /// - Stopped -> 1
+    /// - Other -> Any other value, as given.
+    #[derive(Debug, PartialEq, Eq, Clone, Copy)]
+    pub enum LocusState {
+        Logging,
+        Stopped,
+        Other(u8),
+    }
+
+    impl From<u8> for LocusState {
+        fn from(value: u8) -> LocusState {
+            match value {
+                0 => LocusState::Logging,
+                1 => LocusState::Stopped,
+                other => LocusState::Other(other),
+            }
+        }
+    }
+
+    /// # PMTKLOG LOCUS status
+    /// - serial -> Serial number of the log.
+    /// - log_type -> [LocusType](enum.LocusType.html): what happens when the flash is full.
+    /// - mode -> Bitmask of when a record is logged: 0x01 AlwaysLocate, 0x02 FixOnly, 0x04 Normal,
+    ///   0x08 Interval, 0x10 Distance, 0x20 Speed.
+    /// - content -> Bitmask of the fields in each record, see the locus record decoding.
+    /// - interval -> Seconds between records in interval mode.
+    /// - distance -> Metres between records in distance mode.
+    /// - speed -> Speed over which to log in speed mode, m/s.
+    /// - status -> [LocusState](enum.LocusState.html): whether it is logging.
+    /// - records -> Number of records logged.
+    /// - percent -> How much of the flash is used, 0 to 100.
+    #[derive(Debug, PartialEq, Eq, Clone, Copy)]
+    pub struct LocusStatus {
+        pub serial: u32,
+        pub log_type: LocusType,
+        pub mode: u8,
+        pub content: u32,
+        pub interval: u32,
+        pub distance: u32,
+        pub speed: u32,
+        pub status: LocusState,
+        pub records: u32,
+        pub percent: u8,
+    }
+
+    impl LocusStatus {
+        pub fn is_logging(&self) -> bool {
+            self.status == LocusState::Logging
+        }
+    }
+
+    /// # PMTKLOX line
+    /// - Start -> $PMTKLOX,0: how many data lines will be given.
+    /// - Data -> $PMTKLOX,1: the line number, from 0, and its bytes.
+    /// - End -> $PMTKLOX,2: the dump is done.
+    #[derive(Debug, PartialEq, Eq, Clone)]
+    pub enum LoxLine {
+        Start { lines: u32 },
+        Data { line: u32, bytes: Vec<u8> },
+        End,
+    }
+
+    /// # LOCUS flash dump
+    /// - lines -> The bytes of each $PMTKLOX,1 line by line number. None for a line that was not
+    ///   given, such as one with a bad checksum.
+    #[derive(Debug, PartialEq, Eq, Clone, Default)]
+    pub struct LocusDump {
+        pub lines: Vec<Option<Vec<u8>>>,
+    }
+
+    impl LocusDump {
+        /// Keeps the bytes of a data line, in place of any given before with the same number.
+        pub fn insert(&mut self, line: u32, bytes: Vec<u8>) {
+            let line = line as usize;
+            if self.lines.len() <= line {
+                self.lines.resize(line + 1, None);
+            }
+            self.lines[line] = Some(bytes);
+        }
+
+        /// The numbers of the lines that were not given.
+        pub fn missing(&self) -> Vec<u32> {
+            self.lines.iter().enumerate().filter(|(_, line)| line.is_none()).map(|(index, _)| index as u32).collect()
+        }
+
+        /// The flash as bytes. Missing lines are given as erased flash, 0xFF, so the pages after them
+        /// stay in place.
+        pub fn data(&self) -> Vec<u8> {
+            let mut data = Vec::with_capacity(self.lines.len() * LOX_LINE_SIZE);
+            for line in self.lines.iter() {
+                match line {
+                    Some(bytes) => data.extend_from_slice(bytes),
+                    None => data.extend_from_slice(&[0xFF; LOX_LINE_SIZE]),
+                }
+            }
+            data
+        }
+
+        /// The flash split into its pages. The last page is short if the dump does not fill it.
+        pub fn pages(&self) -> Vec<Vec<u8>> {
+            self.data().chunks(LOCUS_PAGE_SIZE).map(|page| page.to_vec()).collect()
+        }
+    }
+
+    pub fn parse_pmtklog(args: Vec<&str>) -> Result<LocusStatus, NmeaParseError> {
+        //! Format: $PMTKLOG,Serial,Type,Mode,Content,Interval,Distance,Speed,Status,Number,Percent*checksum
+        //!
+        //! All the fields are decimal.
+        check_address(&args, "PMTKLOG")?;
+        let value = |index| -> Result<u32, NmeaParseError> {
+            parse_field(&args, index, "PMTKLOG")?
+                .ok_or_else(|| NmeaParseError::new("PMTKLOG", index, ParseErrorReason::Missing))
+        };
+        let small = |index| -> Result<u8, NmeaParseError> {
+            let field = value(index)?;
+            if field > u8::MAX as u32 {
+                return Err(NmeaParseError::new("PMTKLOG", index, ParseErrorReason::Invalid(field.to_string())));
+            }
+            Ok(field as u8)
+        };
+        Ok(LocusStatus {
+            serial: value(1)?,
+            log_type: LocusType::from(small(2)?),
+            mode: small(3)?,
+            content: value(4)?,
+            interval: value(5)?,
+            distance: value(6)?,
+            speed: value(7)?,
+            status: LocusState::from(small(8)?),
+            records: value(9)?,
+            percent: small(10)?,
+        })
+    }
+
+    pub fn parse_pmtklox(args: Vec<&str>) -> Result<LoxLine, NmeaParseError> {
+        //! Format: $PMTKLOX,Type,...*checksum
+        //!
+        //! Data words are 8 hex characters, given in flash order.
+        check_address(&args, "PMTKLOX")?;
+        match get_field(&args, 1, "PMTKLOX")? {
+            "0" => match parse_field(&args, 2, "PMTKLOX")? {
+                Some(lines) => Ok(LoxLine::Start { lines }),
+                None => Err(NmeaParseError::new("PMTKLOX", 2, ParseErrorReason::Missing)),
+            },
+            "1" => {
+                let line = match parse_field(&args, 2, "PMTKLOX")? {
+                    Some(line) => line,
+                    None => return Err(NmeaParseError::new("PMTKLOX", 2, ParseErrorReason::Missing)),
+                };
+                let mut bytes = Vec::with_capacity(LOX_LINE_SIZE);
+                for (index, word) in args.iter().enumerate().skip(3) {
+                    let invalid = || NmeaParseError::new("PMTKLOX", index, ParseErrorReason::Invalid(word.to_string()));
+                    if word.len() != 8 || !word.bytes().all(|b| b.is_ascii_hexdigit()) {
+                        return Err(invalid());
+                    }
+                    for pair in (0..8).step_by(2) {
+                        bytes.push(u8::from_str_radix(&word[pair..pair + 2], 16).map_err(|_e| invalid())?);
+                    }
+                }
+                Ok(LoxLine::Data { line, bytes })
+            }
+            "2" => Ok(LoxLine::End),
+            other => Err(NmeaParseError::new("PMTKLOX", 1, ParseErrorReason::Invalid(other.to_string()))),
+        }
+    }
+}
+
+pub mod decode {
+    //! Decode the pages of a LOCUS dump into fixes.
+    //!
+    //! Each 4 KB page starts with a 64 byte header, then records until the end of the page or the
+    //! first erased record, all 0xFF. The header gives the content bitmask, which sets the fields of
+    //! each record, in bit order, all little endian:
+    //! - bit 0, UTC -> 4 bytes: Unix time, seconds.
+    //! - bit 1, VALID -> 1 byte: fix type, 0 no fix, 1 GPS fix, 2 DGPS fix.
+    //! - bit 2, LAT -> 4 bytes: f32 degrees, negative for south.
+    //! - bit 3, LON -> 4 bytes: f32 degrees, negative for west.
+    //! - bit 4, HGT -> 2 bytes: i16 metres.
+    //! - bit 5, SPD -> 2 bytes: u16 km/h.
+    //! - bit 6, TRK -> 2 bytes: u16 heading, degrees.
+    //! - bit 7, HDOP -> 2 bytes: u16 HDOP x100.
+    //! - bit 8, NSAT -> 1 byte: satellites used.
+    //!
+    //! The last byte of each record is the XOR of the bytes before it. The basic content, 0x1F, is a
+    //! 16 byte record.
+    use std::fmt;
+
+    use crate::nmea::gga::{GgaData, SatFix};
+    use crate::nmea::talker::Talker;
+    use crate::time::utc::UtcDateTime;
+
+    use super::logger::{LocusDump, LocusType, LOCUS_PAGE_SIZE};
+
+    /// Bytes in the header at the start of each page.
+    pub const PAGE_HEADER_SIZE: usize = 64;
+
+    pub const CONTENT_UTC: u32 = 1 << 0;
+    pub const CONTENT_VALID: u32 = 1 << 1;
+    pub const CONTENT_LAT: u32 = 1 << 2;
+    pub const CONTENT_LON: u32 = 1 << 3;
+    pub const CONTENT_HGT: u32 = 1 << 4;
+    pub const CONTENT_SPD: u32 = 1 << 5;
+    pub const CONTENT_TRK: u32 = 1 << 6;
+    pub const CONTENT_HDOP: u32 = 1 << 7;
+    pub const CONTENT_NSAT: u32 = 1 << 8;
+
+    /// Size of each field, in content bit order.
+    const FIELD_SIZES: [usize; 9] = [4, 1, 4, 4, 2, 2, 2, 2, 1];
+
+    /// Bytes in each record for the content bitmask, with its checksum. None for a content with no
+    /// fields, or bits this crate does not know.
+    pub fn record_size(content: u32) -> Option<usize> {
+        if content == 0 || content >> FIELD_SIZES.len() != 0 {
+            return None;
+        }
+        let fields: usize = FIELD_SIZES.iter().enumerate()
+            .filter(|(bit, _)| content & (1 << bit) != 0)
+            .map(|(_, size)| size)
+            .sum();
+        Some(fields + 1)
+    }
+
+    /// # LOCUS page header
+    /// The settings the records in the page were logged with, as in
+    /// [LocusStatus](../logger/struct.LocusStatus.html).
+    /// - log_type -> Overlap or FullStop.
+    /// - mode -> Bitmask of when a record is logged.
+    /// - content -> Bitmask of the fields in each record.
+    /// - interval -> Seconds between records.
+    /// - distance -> Metres between records.
+    /// - speed -> Speed over which to log, m/s.
+    #[derive(Debug, PartialEq, Eq, Clone, Copy)]
+    pub struct PageHeader {
+        pub log_type: LocusType,
+        pub mode: u8,
+        pub content: u32,
+        pub interval: u16,
+        pub distance: u16,
+        pub speed: u16,
+    }
+
+    /// Reads the header at the start of a page. None if the page is erased, or shorter than a header.
+    ///
+    /// Format: 2 bytes not used here, type (u8), mode (u8), content (u32), interval (u16),
+    /// distance (u16), speed (u16), then the rest of the 64 bytes.
+    pub fn parse_page_header(page: &[u8]) -> Option<PageHeader> {
+        if page.len() < PAGE_HEADER_SIZE || page[..8].iter().all(|b| *b == 0xFF) {
+            return None;
+        }
+        Some(PageHeader {
+            log_type: LocusType::from(page[2]),
+            mode: page[3],
+            content: u32::from_le_bytes([page[4], page[5], page[6], page[7]]),
+            interval: u16::from_le_bytes([page[8], page[9]]),
+            distance: u16::from_le_bytes([page[10], page[11]]),
+            speed: u16::from_le_bytes([page[12], page[13]]),
+        })
+    }
+
+    /// # LOCUS fix
+    /// One record, with None for the fields not in its content.
+    /// - utc -> Time of the fix. None when the content has no UTC bit, so fixes can not be put in
+    ///   time order or used for speed.
+    /// - fix -> [SatFix](../../gga/enum.SatFix.html). Fix types other than 1 and 2, such as 6 for
+    ///   estimated, are NoFix.
+    /// - latitude, longitude -> Degrees, negative for south and west.
+    /// - height -> Metres.
+    /// - speed -> km/h.
+    /// - heading -> Degrees.
+    /// - hdop -> Horizontal dilution of precision.
+    /// - satellites -> Satellites used.
+    #[derive(Debug, PartialEq, Clone, Default)]
+    pub struct LocusFix {
+        pub utc: Option<UtcDateTime>,
+        pub fix: Option<SatFix>,
+        pub latitude: Option<f32>,
+        pub longitude: Option<f32>,
+        pub height: Option<f32>,
+        pub speed: Option<f32>,
+        pub heading: Option<f32>,
+        pub hdop: Option<f32>,
+        pub satellites: Option<u8>,
+    }
+
+    impl LocusFix {
+        /// The fix as GGA data, so it can be given with other GpsSentence, such as to geodesy.
+        /// The height is given as the msl_alt.
+        pub fn to_gga(&self) -> GgaData {
+            GgaData {
+                utc: self.utc.map(|utc| utc.time),
+                lat: self.latitude,
+                long: self.longitude,
+                sat_fix: self.fix.clone().unwrap_or_default(),
+                satellites_used: self.satellites.map_or(0, |satellites| satellites as i32),
+                hdop: self.hdop,
+                msl_alt: self.height,
+                talker: Talker::Gps,
+                ..GgaData::default()
+            }
+        }
+    }
+
+    /// Decodes one record. The checksum has already been checked.
+    fn parse_record(record: &[u8], content: u32) -> LocusFix {
+        let mut fix = LocusFix::default();
+        let mut at = 0;
+        let mut take = |size: usize| {
+            let field = &record[at..at + size];
+            at += size;
+            field
+        };
+        let u16_at = |field: &[u8]| u16::from_le_bytes([field[0], field[1]]);
+        let f32_at = |field: &[u8]| f32::from_le_bytes([field[0], field[1], field[2], field[3]]);
+        if content & CONTENT_UTC != 0 {
+            let field = take(4);
+            let seconds = u32::from_le_bytes([field[0], field[1], field[2], field[3]]);
+            fix.utc = Some(UtcDateTime::from_unix_millis(seconds as i64 * 1_000));
+        }
+        if content & CONTENT_VALID != 0 {
+            fix.fix = Some(match take(1)[0] {
+                1 => SatFix::GpsFix,
+                2 => SatFix::DgpsFix,
+                _ => SatFix::NoFix,
+            });
+        }
+        if content & CONTENT_LAT != 0 {
+            fix.latitude = Some(f32_at(take(4)));
+        }
+        if content & CONTENT_LON != 0 {
+            fix.longitude = Some(f32_at(take(4)));
+        }
+        if content & CONTENT_HGT != 0 {
+            fix.height = Some(u16_at(take(2)) as i16 as f32);
+        }
+        if content & CONTENT_SPD != 0 {
+            fix.speed = Some(u16_at(take(2)) as f32);
+        }
+        if content & CONTENT_TRK != 0 {
+            fix.heading = Some(u16_at(take(2)) as f32);
+        }
+        if content & CONTENT_HDOP != 0 {
+            fix.hdop = Some(u16_at(take(2)) as f32 / 100.0);
+        }
+        if content & CONTENT_NSAT != 0 {
+            fix.satellites = Some(take(1)[0]);
+        }
+        fix
+    }
+
+    /// # LOCUS decode error
+    /// Records passed over when decoding. offset is from the start of the dump.
+    /// - Checksum -> The record at offset failed its checksum.
+    /// - UnknownContent -> The page at offset has a content bitmask this crate can not decode, so
+    ///   the whole page was passed over.
+    #[derive(Debug, PartialEq, Eq, Clone, Copy)]
+    pub enum LocusError {
+        Checksum { offset: usize },
+        UnknownContent { offset: usize, content: u32 },
+    }
+
+    impl fmt::Display for LocusError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                LocusError::Checksum { offset } => write!(f, "Bad checksum for the LOCUS record at byte {}", offset),
+                LocusError::UnknownContent { offset, content } => {
+                    write!(f, "Unknown LOCUS content {:#x} for the page at byte {}", content, offset)
+                }
+            }
+        }
+    }
+
+    /// # LOCUS contents
+    /// - pages -> The header of each page that is not erased, in flash order.
+    /// - fixes -> Every record with a good checksum, in flash order.
+    /// - errors -> Records and pages that were passed over, in flash order.
+    #[derive(Debug, PartialEq, Clone, Default)]
+    pub struct LocusContents {
+        pub pages: Vec<PageHeader>,
+        pub fixes: Vec<LocusFix>,
+        pub errors: Vec<LocusError>,
+    }
+
+    /// Decodes the pages of the flash, such as from LocusDump.data().
+    pub fn decode_locus(data: &[u8]) -> LocusContents {
+        let mut contents = LocusContents::default();
+        for (index, page) in data.chunks(LOCUS_PAGE_SIZE).enumerate() {
+            let start = index * LOCUS_PAGE_SIZE;
+            let header = match parse_page_header(page) {
+                Some(header) => header,
+                None => continue,
+            };
+            contents.pages.push(header);
+            let size = match record_size(header.content) {
+                Some(size) => size,
+                None => {
+                    contents.errors.push(LocusError::UnknownContent { offset: start, content: header.content });
+                    continue;
+                }
+            };
+            for (number, record) in page[PAGE_HEADER_SIZE..].chunks_exact(size).enumerate() {
+                if record.iter().all(|b| *b == 0xFF) {
+                    break;
+                }
+                let (fields, checksum) = record.split_at(size - 1);
+                if fields.iter().fold(0, |xor, b| xor ^ b) != checksum[0] {
+                    let offset = start + PAGE_HEADER_SIZE + number * size;
+                    contents.errors.push(LocusError::Checksum { offset });
+                    continue;
+                }
+                contents.fixes.push(parse_record(fields, header.content));
+            }
+        }
+        contents
+    }
+
+    impl LocusDump {
+        /// Decodes the records in the dump.
+        pub fn decode(&self) -> LocusContents {
+            decode_locus(&self.data())
+        }
+    }
+}
+
+#[cfg(test)]
+mod logger_test {
+    use crate::nmea::parse_nmea::{parse_sentence, NmeaParseError, ParseErrorReason};
+
+    use super::logger::*;
+
+    fn lox(line: &str) -> Result<LoxLine, NmeaParseError> {
+        parse_pmtklox(parse_sentence(line).unwrap())
+    }
+
+    #[test]
+    fn status() {
+        let status = parse_pmtklog(parse_sentence("$PMTKLOG,456,0,11,31,2,0,0,0,3769,46*48").unwrap()).unwrap();
+        assert_eq!(status, LocusStatus {
+            serial: 456,
+            log_type: LocusType::Overlap,
+            mode: 11,
+            content: 31,
+            interval: 2,
+            distance: 0,
+            speed: 0,
+            status: LocusState::Logging,
+            records: 3769,
+            percent: 46,
+        });
+        assert!(status.is_logging());
+        assert_eq!(parse_pmtklog(vec!["$PMTKLOG", "456", "0", "11", "31", "2", "0", "0", "1", "3769"]),
+                   Err(NmeaParseError::new("PMTKLOG", 10, ParseErrorReason::Missing)));
+        assert_eq!(parse_pmtklog(vec!["$PMTKLOG", "456", "0", "11", "31", "2", "0", "0", "1", "3769", "300"]),
+                   Err(NmeaParseError::new("PMTKLOG", 10, ParseErrorReason::Invalid("300".to_string()))));
+    }
+
+    #[test]
+    fn lox_lines() {
+        assert_eq!(lox("$PMTKLOX,0,2*5B"), Ok(LoxLine::Start { lines: 2 }));
+        assert_eq!(lox("$PMTKLOX,1,0,0100010B,7F000000*5B"),
+                   Ok(LoxLine::Data { line: 0, bytes: vec![0x01, 0x00, 0x01, 0x0B, 0x7F, 0x00, 0x00, 0x00] }));
+        assert_eq!(lox("$PMTKLOX,2*47"), Ok(LoxLine::End));
+        assert_eq!(parse_pmtklox(vec!["$PMTKLOX", "1", "0", "0100010"]),
+                   Err(NmeaParseError::new("PMTKLOX", 3, ParseErrorReason::Invalid("0100010".to_string()))));
+        assert_eq!(parse_pmtklox(vec!["$PMTKLOX", "1", "0", "0100010G"]),
+                   Err(NmeaParseError::new("PMTKLOX", 3, ParseErrorReason::Invalid("0100010G".to_string()))));
+        assert_eq!(parse_pmtklox(vec!["$PMTKLOX", "3"]),
+                   Err(NmeaParseError::new("PMTKLOX", 1, ParseErrorReason::Invalid("3".to_string()))));
+    }
+
+    #[test]
+    fn reassemble_pages() {
+        let mut dump = LocusDump::default();
+        dump.insert(0, vec![0xAB; LOX_LINE_SIZE]);
+        dump.insert(43, vec![0xCD; LOX_LINE_SIZE]);
+        assert_eq!(dump.missing(), (1..43).collect::<Vec<u32>>());
+        let data = dump.data();
+        assert_eq!(data.len(), 44 * LOX_LINE_SIZE);
+        assert_eq!(&data[LOX_LINE_SIZE..LOX_LINE_SIZE + 2], &[0xFF, 0xFF]);
+        let pages = dump.pages();
+        assert_eq!(pages.len(), 2);
+        assert_eq!(pages[0].len(), LOCUS_PAGE_SIZE);
+        assert_eq!(pages[1].len(), 44 * LOX_LINE_SIZE - LOCUS_PAGE_SIZE);
+        assert_eq!(pages[1][pages[1].len() - 1], 0xCD);
+    }
+}
+
+#[cfg(test)]
+mod decode_test {
+    use crate::nmea::gga::SatFix;
+    use crate::open_gps::gps::Gps;
+    use crate::time::utc::{UtcDate, UtcDateTime, UtcTime};
+
+    use super::decode::*;
+    use super::logger::{LocusType, LOCUS_PAGE_SIZE};
+
+    /// A dump of the basic content, 0x1F, with the fourth record corrupted. Hand-assembled from the
+    /// LOCUS record layout, not captured from a module.
+    const BASIC: &str = include_str!("../tests/data/locus-basic.nmea");
+
+    /// One record with every field, 0x1FF.
+    const FULL: &str = "\
+$PMTKLOX,0,1*58\r
+$PMTKLOX,1,0,01000008,FF010000,01000000,0000FFFF,FFFFFFFF,FFFFFFFF,FFFFFFFF,FFFFFFFF,FFFFFFFF,FFFFFFFF,FFFFFFFF,FFFFFFFF,FFFFFFFF,FFFFFFFF,FFFFFFFF,FFFFFFFF,AF3AE25E,025D6D07,C21E3717,43FBFF24,000E0175,0009F0FF,FFFFFFFF,FFFFFFFF*2E\r
+$PMTKLOX,2*47\r
+";
+
+    fn utc(seconds: u32) -> Option<UtcDateTime> {
+        Some(UtcDateTime::new(UtcDate::from_ymd(2020, 6, 11).unwrap(), UtcTime::parse(&format!("1407{:02}.000", seconds)).unwrap()))
+    }
+
+    #[test]
+    fn sizes() {
+        assert_eq!(record_size(0x1F), Some(16));
+        assert_eq!(record_size(0x1FF), Some(23));
+        assert_eq!(record_size(CONTENT_UTC | CONTENT_LAT | CONTENT_LON), Some(13));
+        assert_eq!(record_size(0), None);
+        assert_eq!(record_size(0x200), None);
+    }
+
+    #[test]
+    fn basic_content() {
+        let mut gps = Gps::from_bytes(BASIC.as_bytes());
+        let contents = gps.pmtk_622_locus_dump().unwrap().decode();
+        assert_eq!(contents.pages, vec![PageHeader {
+            log_type: LocusType::FullStop,
+            mode: 0x0B,
+            content: 0x1F,
+            interval: 15,
+            distance: 0,
+            speed: 0,
+        }]);
+        assert_eq!(contents.errors, vec![LocusError::Checksum { offset: 64 + 3 * 16 }]);
+        assert_eq!(contents.fixes.len(), 5);
+        assert_eq!(contents.fixes[0], LocusFix {
+            utc: utc(43),
+            fix: Some(SatFix::NoFix),
+            latitude: Some(51.5455),
+            longitude: Some(-0.0985),
+            height: Some(42.0),
+            ..LocusFix::default()
+        });
+        let last = &contents.fixes[4];
+        assert_eq!((last.utc, last.fix.clone(), last.height), (UtcDateTime::from_unix_millis(1_591_884_538_000).into(),
+                                                       Some(SatFix::GpsFix), Some(47.0)));
+    }
+
+    #[test]
+    fn every_field() {
+        let mut gps = Gps::from_bytes(FULL.as_bytes());
+        let contents = gps.pmtk_622_locus_dump().unwrap().decode();
+        assert!(contents.errors.is_empty());
+        let fix = LocusFix {
+            utc: utc(43),
+            fix: Some(SatFix::DgpsFix),
+            latitude: Some(-33.8568),
+            longitude: Some(151.2153),
+            height: Some(-5.0),
+            speed: Some(36.0),
+            heading: Some(270.0),
+            hdop: Some(1.17),
+            satellites: Some(9),
+        };
+        assert_eq!(contents.fixes, vec![fix.clone()]);
+
+        let gga = fix.to_gga();
+        assert_eq!((gga.utc, gga.lat, gga.long, gga.sat_fix), (UtcTime::parse("140743.000"), Some(-33.8568),
+                                                                Some(151.2153), SatFix::DgpsFix));
+        assert_eq!((gga.satellites_used, gga.hdop, gga.msl_alt, gga.geoidal_sep), (9, Some(1.17), Some(-5.0), None));
+    }
+
+    #[test]
+    fn erased_and_unknown_pages() {
+        let mut data = vec![0xFF; LOCUS_PAGE_SIZE];
+        data.extend_from_slice(&[1, 0, 0, 0x08, 0x00, 0x04, 0, 0]);
+        data.resize(2 * LOCUS_PAGE_SIZE, 0xFF);
+        let contents = decode_locus(&data);
+        assert_eq!(contents.pages.len(), 1);
+        assert!(contents.fixes.is_empty());
+        assert_eq!(contents.errors, vec![LocusError::UnknownContent { offset: LOCUS_PAGE_SIZE, content: 0x400 }]);
+    }
+}
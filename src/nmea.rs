@@ -8,6 +8,12 @@
 //! - GSV -> Satellites in view data: sat id, elevation, azimuth and SNR for each sat seen.
 //! - RMC -> UTC, Latitude, Longitude, speed, course, date, magnetic variation.
 //! - GLL -> Latitude, Longitude.
+//! - ZDA -> UTC, day, month, four digit year, local zone hours and minutes.
+//! - GNS -> UTC, Latitude, Longitude, mode for each constellation, sats used, HDOP, altitude.
+//! - GST -> Pseudorange error statistics: standard deviation of latitude, longitude and altitude.
+//! - GBS -> Satellite fault detection: expected errors and the most likely failed satellite.
+//! - GRS -> Range residuals for each satellite used.
+//! - DTM -> Datum reference: local datum and its offsets from the reference datum.
 //!
 //! ## Sentence prefix: ${GP, GL, GA, GN}{GGA, GSA, GSV, RMC, VTG}
 //! GP is short for GPS (American)
@@ -18,6 +24,9 @@
 //!
 //! GN is multi-system.
 //!
+//! The talker of each sentence is kept in the talker field as a
+//! [Talker](talker/enum.Talker.html), so the GSA for each constellation can be told apart.
+//!
 //! ### Prefixes table ({} means heading of GP/GL/GA is added.
 //! |           |GGA     |GSA     |GSV    |RMC     |VTG  |
 //! |-----------|:------:|-------:|------:|-------:|-----|
@@ -31,52 +40,251 @@
 //! For GSA sentences, there is a sentence for each family of satellites seen (GPS, GLONASS and Galileo)
 //!, probably all in that order. It seems that the DOPs are all the same between them.
 
+pub mod talker {
+    //! # Talker
+    //!
+    //! The first two letters of the address: which constellation the sentence is from.
+    use serde::{Deserialize, Serialize};
+
+    /// # Talker
+    /// - Gps -> GP
+    /// - Glonass -> GL
+    /// - Galileo -> GA
+    /// - BeiDou -> BD or GB
+    /// - Qzss -> QZ or GQ
+    /// - NavIC -> GI
+    /// - Multi -> GN, more than one constellation was used, such as a GP+GL fix.
+    /// - Unknown -> Any other talker. Default.
+    #[derive(Debug, PartialEq, Eq, Hash, Serialize, Deserialize, Clone, Copy, Default)]
+    pub enum Talker {
+        Gps,
+        Glonass,
+        Galileo,
+        BeiDou,
+        Qzss,
+        NavIC,
+        Multi,
+        #[default]
+        Unknown,
+    }
+
+    impl Talker {
+        /// The talker of an address, with or without the $, such as $GPGGA -> Gps.
+        pub fn from_address(address: &str) -> Talker {
+            let address = address.strip_prefix('$').unwrap_or(address);
+            match address.get(0..2) {
+                Some("GP") => Talker::Gps,
+                Some("GL") => Talker::Glonass,
+                Some("GA") => Talker::Galileo,
+                Some("BD") | Some("GB") => Talker::BeiDou,
+                Some("QZ") | Some("GQ") => Talker::Qzss,
+                Some("GI") => Talker::NavIC,
+                Some("GN") => Talker::Multi,
+                _ => Talker::Unknown,
+            }
+        }
+
+        /// The two letters used in the address. BeiDou is GB and QZSS is GQ as in NMEA 4.1.
+        /// Unknown is GN.
+        pub fn code(&self) -> &'static str {
+            match self {
+                Talker::Gps => "GP",
+                Talker::Glonass => "GL",
+                Talker::Galileo => "GA",
+                Talker::BeiDou => "GB",
+                Talker::Qzss => "GQ",
+                Talker::NavIC => "GI",
+                Talker::Multi | Talker::Unknown => "GN",
+            }
+        }
+    }
+}
+
 pub mod parse_nmea {
     //! Main module for parsing any NMEA sentence and exporting NMEA parsing to lib.rs
+    use std::error::Error;
+    use std::fmt;
+    use std::str::FromStr;
+
+    use serde::{Deserialize, Serialize};
+
+    use crate::framing::check::{check_sentence, Verdict};
+
+    use super::gsv::GsvDiagnostic;
+    use super::talker::Talker;
+
+    /// Why a field could not be parsed.
+    /// - WrongSentence -> The sentence given is not the type expected, holds the header given.
+    /// - Missing -> The sentence is too short, the field is not there.
+    /// - Invalid -> The field is there but is not valid, holds the field given.
+    /// - Gsv -> A group of GSV messages was dropped, see [GsvDiagnostic](../gsv/enum.GsvDiagnostic.html).
+    #[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+    pub enum ParseErrorReason {
+        WrongSentence(String),
+        Missing,
+        Invalid(String),
+        Gsv(GsvDiagnostic),
+    }
+
+    /// Error given when a sentence has a valid checksum but one of its fields could not be parsed.
+    /// - sentence -> The sentence type: GGA, GSA, etc.
+    /// - field -> Index of the field, 0 is the header. Same indexing as the parse functions' docs.
+    /// - reason -> [ParseErrorReason](enum.ParseErrorReason.html)
+    #[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+    pub struct NmeaParseError {
+        pub sentence: String,
+        pub field: usize,
+        pub reason: ParseErrorReason,
+    }
+
+    impl NmeaParseError {
+        pub fn new(sentence: &str, field: usize, reason: ParseErrorReason) -> NmeaParseError {
+            NmeaParseError { sentence: sentence.to_string(), field, reason }
+        }
+    }
+
+    impl From<GsvDiagnostic> for NmeaParseError {
+        fn from(diagnostic: GsvDiagnostic) -> NmeaParseError {
+            NmeaParseError::new("GSV", diagnostic.field(), ParseErrorReason::Gsv(diagnostic))
+        }
+    }
+
+    impl fmt::Display for NmeaParseError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match &self.reason {
+                ParseErrorReason::WrongSentence(header) => {
+                    write!(f, "Sentence is not a {} format, it's {} format", self.sentence, header)
+                }
+                ParseErrorReason::Missing => write!(f, "{} field {} is missing", self.sentence, self.field),
+                ParseErrorReason::Invalid(value) => {
+                    write!(f, "{} field {} is not valid: {:?}", self.sentence, self.field, value)
+                }
+                ParseErrorReason::Gsv(diagnostic) => write!(f, "{}", diagnostic),
+            }
+        }
+    }
+
+    impl Error for NmeaParseError {}
+
+    /// Checks the header is ${talker}{sentence}, such as $GPGGA for GGA.
+    pub fn check_header(args: &[&str], sentence: &str) -> Result<(), NmeaParseError> {
+        let header = get_field(args, 0, sentence)?;
+        if header.get(3..) != Some(sentence) {
+            return Err(NmeaParseError::new(sentence, 0, ParseErrorReason::WrongSentence(header.to_string())));
+        }
+        Ok(())
+    }
+
+    /// Checks the header is the full address, such as $PMTK010 for proprietary sentences.
+    pub fn check_address(args: &[&str], address: &str) -> Result<(), NmeaParseError> {
+        let header = get_field(args, 0, address)?;
+        if header.strip_prefix('$') != Some(address) {
+            return Err(NmeaParseError::new(address, 0, ParseErrorReason::WrongSentence(header.to_string())));
+        }
+        Ok(())
+    }
+
+    /// The talker of the sentence, from its address.
+    pub fn parse_talker(args: &[&str]) -> Talker {
+        Talker::from_address(args.first().copied().unwrap_or(""))
+    }
+
+    /// Gets a field that must be there.
+    pub fn get_field<'a>(args: &[&'a str], index: usize, sentence: &str) -> Result<&'a str, NmeaParseError> {
+        match args.get(index) {
+            Some(field) => Ok(field),
+            None => Err(NmeaParseError::new(sentence, index, ParseErrorReason::Missing)),
+        }
+    }
+
+    /// Parses a field that must be there but can be empty. Empty is None.
+    pub fn parse_field<T: FromStr>(args: &[&str], index: usize, sentence: &str) -> Result<Option<T>, NmeaParseError> {
+        let field = get_field(args, index, sentence)?;
+        parse_value(field, index, sentence)
+    }
+
+    /// Parses a field that can be left off the end of the sentence, such as the mode added in
+    /// NMEA 2.3. Missing or empty is None.
+    pub fn parse_optional_field<T: FromStr>(args: &[&str], index: usize, sentence: &str) -> Result<Option<T>, NmeaParseError> {
+        match args.get(index) {
+            Some(field) => parse_value(field, index, sentence),
+            None => Ok(None),
+        }
+    }
+
+    fn parse_value<T: FromStr>(field: &str, index: usize, sentence: &str) -> Result<Option<T>, NmeaParseError> {
+        if field.is_empty() {
+            return Ok(None);
+        }
+        match field.parse::<T>() {
+            Ok(value) => Ok(Some(value)),
+            Err(_e) => Err(NmeaParseError::new(sentence, index, ParseErrorReason::Invalid(field.to_string()))),
+        }
+    }
+
+    /// Parses a hexadecimal field that can be left off the end of the sentence, such as the NMEA 4.1
+    /// system and signal ids. Missing or empty is None.
+    pub fn parse_optional_hex_field(args: &[&str], index: usize, sentence: &str) -> Result<Option<u8>, NmeaParseError> {
+        match args.get(index) {
+            Some(field) if !field.is_empty() => match u8::from_str_radix(field, 16) {
+                Ok(value) => Ok(Some(value)),
+                Err(_e) => Err(NmeaParseError::new(sentence, index, ParseErrorReason::Invalid(field.to_string()))),
+            },
+            _ => Ok(None),
+        }
+    }
+
+    /// Parses a number followed by its N/S/E/W field. S and W are negative.
+    pub fn parse_signed_field(args: &[&str], index: usize, sentence: &str) -> Result<Option<f32>, NmeaParseError> {
+        let value: Option<f32> = parse_field(args, index, sentence)?;
+        match (value, get_field(args, index + 1, sentence)?) {
+            (None, _) => Ok(None),
+            (Some(value), "N") | (Some(value), "E") => Ok(Some(value)),
+            (Some(value), "S") | (Some(value), "W") => Ok(Some(-value)),
+            (Some(_value), compass_direction) => {
+                let reason = ParseErrorReason::Invalid(compass_direction.to_string());
+                Err(NmeaParseError::new(sentence, index + 1, reason))
+            }
+        }
+    }
 
-    use crate::open_gps;
+    /// Parses a dddmm.mmmm field followed by its N/S/E/W field.
+    pub fn parse_degrees_field(args: &[&str], index: usize, sentence: &str) -> Result<Option<f32>, NmeaParseError> {
+        let degrees = get_field(args, index, sentence)?;
+        let compass_direction = get_field(args, index + 1, sentence)?;
+        if !degrees.is_empty() && !["N", "S", "E", "W"].contains(&compass_direction) {
+            let reason = ParseErrorReason::Invalid(compass_direction.to_string());
+            return Err(NmeaParseError::new(sentence, index + 1, reason));
+        }
+        _parse_degrees(degrees, compass_direction).map_err(|e| NmeaParseError::new(sentence, index, e))
+    }
 
-    pub fn _parse_degrees(degrees: &str, compass_direction: &str) -> Option<f32> {
+    pub fn _parse_degrees(degrees: &str, compass_direction: &str) -> Result<Option<f32>, ParseErrorReason> {
         // Parse NMEA lat/long data pair dddmm.mmmm into pure degrees value.
         // ddd is degrees, mm.mmmm is minutes
         // NMEA format is either ddmm.mmmmm or dddmm.mmmmm
         // Formula is ->
         if degrees.is_empty() {
-            return None;
+            return Ok(None);
         }
-        let deg: f32;
-        let minutes: f32;
-        let first_half: Vec<&str> = degrees.split('.').collect();
-
-        if first_half[0].len() == 4 {
-            deg = (&degrees[0..2]).parse::<f32>().unwrap();
-            minutes = ((&degrees[2..]).parse::<f32>().unwrap()) / 60.0;
-        } else {
-            deg = (&degrees[0..3]).parse::<f32>().unwrap();
-            minutes = ((&degrees[3..]).parse::<f32>().unwrap()) / 60.0;
+        let invalid = || ParseErrorReason::Invalid(degrees.to_string());
+        if !degrees.is_ascii() {
+            return Err(invalid());
         }
+        let first_half: Vec<&str> = degrees.split('.').collect();
+        let split = if first_half[0].len() == 4 { 2 } else { 3 };
 
-        let r: f32 = deg + minutes;
-        let r: f32 = format!("{:.6}", r).parse().unwrap(); // Round to 6 decimal places.
+        let deg: f32 = degrees.get(0..split).and_then(|d| d.parse().ok()).ok_or_else(invalid)?;
+        let minutes: f32 = degrees.get(split..).and_then(|m| m.parse().ok()).ok_or_else(invalid)?;
 
-        if (compass_direction == "N") | (compass_direction == "E") {
-            return Some(r);
-        } else if (compass_direction == "S") | (compass_direction == "W") {
-            return Some(r * -1.0);
-        } else {
-            panic!("Compass direction not north or south")
-        }
-    }
+        let r: f32 = deg + minutes / 60.0;
+        let r: f32 = format!("{:.6}", r).parse().unwrap_or(r); // Round to 6 decimal places.
 
-    pub fn _format_hhmmss(time: &str) -> String {
-        // Take in a string of hhmmss and return it as a formatted hh-mm-ss
-        if time.len() < 6 {
-            return "".to_string();
+        match compass_direction {
+            "N" | "E" => Ok(Some(r)),
+            "S" | "W" => Ok(Some(-r)),
+            _ => Err(ParseErrorReason::Invalid(compass_direction.to_string())),
         }
-        let hours = &time[0..2];
-        let mins = &time[2..4];
-        let secs = &time[4..6];
-        return format!("{}:{}:{}", hours, mins, secs);
     }
 
     pub fn parse_sentence(sentence: &str) -> Option<Vec<&str>> {
@@ -87,12 +295,90 @@ pub mod parse_nmea {
         if sentence.len() < 6 {
             return None;
         }
-        return if open_gps::gps::is_valid_checksum(sentence) {
-            let sentence: &str = &sentence[0..sentence.len() - 3]; // Remove checksum.
-            Some(sentence.split(",").collect())
-        } else {
-            None
+        if check_sentence(sentence.as_bytes()) != Verdict::Valid {
+            return None;
+        }
+        // Valid so the last 3 bytes are ascii: *XY
+        let sentence: &str = &sentence[0..sentence.len() - 3]; // Remove checksum.
+        Some(sentence.split(',').collect())
+    }
+}
+
+pub mod encode_nmea {
+    //! Turns parsed data back into NMEA fields and sentences, so fixes can be sent on to other
+    //! NMEA 0183 devices such as autopilots and chart plotters.
+    //!
+    //! Each sentence struct has a to_nmea(talker) that gives the full sentence with its checksum and
+    //! \r\n. Parsing the sentence given gives the same struct back.
+    use std::fmt::Display;
+
+    use crate::framing::check::checksum;
+
+    use super::parse_nmea::_parse_degrees;
+    use super::talker::Talker;
+
+    /// Adds the $, the checksum and \r\n to the fields, such as ["GPGGA", "131613.000", ...].
+    pub fn encode_sentence(fields: &[String]) -> String {
+        let body = fields.join(",");
+        format!("${}*{:02X}\r\n", body, checksum(body.as_bytes()))
+    }
+
+    /// The address for the talker and sentence type, such as GPGGA.
+    pub fn encode_address(talker: Talker, sentence: &str) -> String {
+        format!("{}{}", talker.code(), sentence)
+    }
+
+    /// The value, or an empty field for None.
+    pub fn encode_field<T: Display>(value: &Option<T>) -> String {
+        match value {
+            Some(value) => value.to_string(),
+            None => String::new(),
+        }
+    }
+
+    /// A number and its N/S/E/W field, the reverse of parse_signed_field. Empty fields for None.
+    pub fn encode_signed_field(value: Option<f32>, positive: &str, negative: &str) -> [String; 2] {
+        match value {
+            Some(value) if value < 0.0 => [(-value).to_string(), negative.to_string()],
+            Some(value) => [value.to_string(), positive.to_string()],
+            None => [String::new(), String::new()],
+        }
+    }
+
+    /// Degrees as ddmm.mmmm (width 2, latitude) or dddmm.mmmm (width 3, longitude) and its
+    /// N/S/E/W field. Empty fields for None.
+    ///
+    /// Parsing rounds to 6 decimal places of a degree, so the nearest ddmm.mmmm can read back as a
+    /// different f32. The neighbouring values are tried so that it always reads back the same.
+    pub fn encode_degrees_field(value: Option<f32>, width: usize, positive: &str, negative: &str) -> [String; 2] {
+        let value = match value {
+            Some(value) => value,
+            None => return [String::new(), String::new()],
         };
+        let direction = if value < 0.0 { negative } else { positive };
+        // Ten thousandths of a minute.
+        let ticks = (value.abs() as f64 * 600_000.0).round() as i64;
+        let format_ticks = |ticks: i64| {
+            let minutes = ticks % 600_000;
+            format!("{:0width$}{:02}.{:04}", ticks / 600_000, minutes / 10_000, minutes % 10_000, width = width)
+        };
+        let degrees = [ticks, ticks - 1, ticks + 1].iter()
+            .filter(|ticks| **ticks >= 0)
+            .map(|ticks| format_ticks(*ticks))
+            .find(|degrees| _parse_degrees(degrees, direction) == Ok(Some(value)))
+            .unwrap_or_else(|| format_ticks(ticks));
+        [degrees, direction.to_string()]
+    }
+
+    /// The NMEA 4.1 system and signal ids as hex, or no fields if neither is given.
+    pub fn encode_ids(system_id: Option<u8>, signal_id: Option<u8>) -> Vec<String> {
+        if system_id.is_none() && signal_id.is_none() {
+            return Vec::new();
+        }
+        vec![
+            encode_field(&system_id.map(|id| format!("{:X}", id))),
+            encode_field(&signal_id.map(|id| format!("{:X}", id))),
+        ]
     }
 }
 
@@ -102,9 +388,13 @@ pub mod gga {
     //!
     //!
 
+    use super::encode_nmea::*;
     use super::parse_nmea::*;
+    use super::talker::Talker;
     use serde::{Serialize, Deserialize};
 
+    use crate::time::utc::UtcTime;
+
     /// Satellite fix type
     /// - NoFix -> No satellites being received. Default.
     /// - GpsFix -> Just has a fix using satellites.
@@ -116,6 +406,7 @@ pub mod gga {
         DgpsFix,
     }
 
+    #[allow(clippy::derivable_impls)]
     impl Default for SatFix {
         fn default() -> SatFix {
             SatFix::NoFix
@@ -123,7 +414,7 @@ pub mod gga {
     }
 
     /// GGA data struct.
-    /// - utc -> UTC
+    /// - utc -> UTC time of the fix -> [UtcTime](../../time/utc/struct.UtcTime.html)
     /// - lat -> Latitude
     /// - long -> Longitude
     /// - sat_fix -> Satellite fix type -> [SatFix](nmea/gga/enum.SatFix.html)
@@ -132,9 +423,10 @@ pub mod gga {
     /// - msl_alt -> Altitude against Mean Sea Level in metres.
     /// - geoidal_sep -> Difference between WGS-84 earth ellipsoid and mean sea level in metres.
     /// - age_diff_corr -> Age in seconds since last update from reference station.
+    /// - talker -> [Talker](nmea/talker/enum.Talker.html) that gave the sentence.
     #[derive(Debug, PartialEq, Default, Serialize, Deserialize, Clone)]
     pub struct GgaData {
-        pub utc: f64,
+        pub utc: Option<UtcTime>,
         pub lat: Option<f32>,
         pub long: Option<f32>,
         pub sat_fix: SatFix,
@@ -143,41 +435,68 @@ pub mod gga {
         pub msl_alt: Option<f32>,
         pub geoidal_sep: Option<f32>,
         pub age_diff_corr: Option<f32>,
+        pub talker: Talker,
+    }
+
+    impl GgaData {
+        /// The GGA sentence for the talker, such as $GPGGA.
+        pub fn to_nmea(&self, talker: Talker) -> String {
+            let sat_fix = match self.sat_fix {
+                SatFix::NoFix => "0",
+                SatFix::GpsFix => "1",
+                SatFix::DgpsFix => "2",
+            };
+            let [lat, north_south] = encode_degrees_field(self.lat, 2, "N", "S");
+            let [long, east_west] = encode_degrees_field(self.long, 3, "E", "W");
+            encode_sentence(&[
+                encode_address(talker, "GGA"),
+                encode_field(&self.utc.map(|utc| utc.to_nmea())),
+                lat,
+                north_south,
+                long,
+                east_west,
+                sat_fix.to_string(),
+                self.satellites_used.to_string(),
+                encode_field(&self.hdop),
+                encode_field(&self.msl_alt),
+                "M".to_string(),
+                encode_field(&self.geoidal_sep),
+                "M".to_string(),
+                encode_field(&self.age_diff_corr),
+                String::new(),
+            ])
+        }
     }
 
-    /// Take a parse_sentence vec<&str> and output GgaData.
-    pub fn parse_gga(args: Vec<&str>) -> GgaData {
+    pub fn parse_gga(args: Vec<&str>) -> Result<GgaData, NmeaParseError> {
+        //! Take a parse_sentence vec<&str> and output GgaData.
+        //!
         //! ${GP,GL,GA,GN}GGA, UTC, lat, N/S, long, E/S, Fix quality, Sats used, HDOP, Alt, Alt Units,
         //! Geoidal separation, Geo units, Age of diff corr, * checksum
         //!
         //! Time, sat fix and sats used always given.
-        let header = args.get(0).unwrap();
-        if &header[3..5] != "GG" {
-            panic!(
-                "Sentence is not a GGA format, it's {} format",
-                header
-            )
-        }
+        check_header(&args, "GGA")?;
+        let talker = parse_talker(&args);
 
         // Parse time
-        let utc: f64 = args.get(1).unwrap().parse().unwrap();
+        let utc: Option<UtcTime> = parse_field(&args, 1, "GGA")?;
 
         // Parse lat
-        let lat: Option<f32> = _parse_degrees(args.get(2).unwrap(), args.get(3).unwrap());
-        let long: Option<f32> = _parse_degrees(args.get(4).unwrap(), args.get(5).unwrap());
+        let lat: Option<f32> = parse_degrees_field(&args, 2, "GGA")?;
+        let long: Option<f32> = parse_degrees_field(&args, 4, "GGA")?;
 
-        let sat_fix = match args.get(6).unwrap() {
-            &"0" => SatFix::NoFix,
-            &"1" => SatFix::GpsFix,
-            &"2" => SatFix::DgpsFix,
+        let sat_fix = match get_field(&args, 6, "GGA")? {
+            "0" => SatFix::NoFix,
+            "1" => SatFix::GpsFix,
+            "2" => SatFix::DgpsFix,
             _ => SatFix::NoFix,
         };
-        let satellites_used: i32 = args.get(7).unwrap().parse().unwrap();
-        let hdop = args.get(8).unwrap().parse::<f32>().ok();
-        let msl_alt: Option<f32> = args.get(9).unwrap().parse::<f32>().ok();
-        let geoidal_sep: Option<f32> = args.get(11).unwrap().parse::<f32>().ok();
-        let age_diff_corr: Option<f32> = args.get(13).unwrap().parse::<f32>().ok();
-        return GgaData {
+        let satellites_used: i32 = parse_field(&args, 7, "GGA")?.unwrap_or(0);
+        let hdop: Option<f32> = parse_field(&args, 8, "GGA")?;
+        let msl_alt: Option<f32> = parse_field(&args, 9, "GGA")?;
+        let geoidal_sep: Option<f32> = parse_field(&args, 11, "GGA")?;
+        let age_diff_corr: Option<f32> = parse_optional_field(&args, 13, "GGA")?;
+        Ok(GgaData {
             utc,
             lat,
             long,
@@ -187,7 +506,8 @@ pub mod gga {
             msl_alt,
             geoidal_sep,
             age_diff_corr,
-        };
+            talker,
+        })
     }
 }
 
@@ -196,6 +516,9 @@ pub mod gsa {
     //!
     //! Gives All the satellites that are being tracked and the HDOP, VDOP, PDOP.
 
+    use super::encode_nmea::*;
+    use super::parse_nmea::*;
+    use super::talker::Talker;
     use serde::{Serialize, Deserialize};
 
     /// Manual or automatic selection mode for 3d or 2d fix.
@@ -204,6 +527,7 @@ pub mod gsa {
         Manual,
         Automatic,
     }
+    #[allow(clippy::derivable_impls)]
     impl Default for Mode {
         fn default() -> Mode {Mode::Manual}
     }
@@ -218,6 +542,7 @@ pub mod gsa {
         Dimension2d,
         Dimension3d,
     }
+    #[allow(clippy::derivable_impls)]
     impl Default for DimensionFix {
         fn default() -> DimensionFix {DimensionFix::NotAvailable}
     }
@@ -231,6 +556,7 @@ pub mod gsa {
     /// - pdop -> Positional Dilution of Precisions
     /// - hdop -> Horizontal Dilution of Precisions
     /// - vdop -> Vertical Dilution of Precisions
+    /// - talker -> [Talker](nmea/talker/enum.Talker.html) that gave the sentence.
     #[derive(PartialEq, Debug, Default, Serialize, Deserialize, Clone)]
     pub struct GsaData {
         pub mode: Mode,
@@ -250,9 +576,33 @@ pub mod gsa {
         pub pdop: Option<f32>,
         pub hdop: Option<f32>,
         pub vdop: Option<f32>,
+        pub talker: Talker,
+    }
+
+    impl GsaData {
+        /// The GSA sentence for the talker, such as $GPGSA.
+        pub fn to_nmea(&self, talker: Talker) -> String {
+            let mode = match self.mode {
+                Mode::Manual => "M",
+                Mode::Automatic => "A",
+            };
+            let dimension_fix = match self.dimension_fix {
+                DimensionFix::NotAvailable => "1",
+                DimensionFix::Dimension2d => "2",
+                DimensionFix::Dimension3d => "3",
+            };
+            let sats = [
+                self.sat1, self.sat2, self.sat3, self.sat4, self.sat5, self.sat6, self.sat7, self.sat8,
+                self.sat9, self.sat10, self.sat11, self.sat12,
+            ];
+            let mut fields = vec![encode_address(talker, "GSA"), mode.to_string(), dimension_fix.to_string()];
+            fields.extend(sats.iter().map(|sat| encode_field(&sat.map(|id| format!("{:02}", id)))));
+            fields.extend(vec![encode_field(&self.pdop), encode_field(&self.hdop), encode_field(&self.vdop)]);
+            encode_sentence(&fields)
+        }
     }
 
-    pub fn parse_gsa(args: Vec<&str>) -> GsaData {
+    pub fn parse_gsa(args: Vec<&str>) -> Result<GsaData, NmeaParseError> {
         //! Format
         //! $G{}GSA, Mode, dimention_fix, Sat1, Sat2, Sat3, Sat4, Sat5, Sat6, Sat7, Sat8, Sat9, Sat10,
         //! Sat11, Sat12, PDOP, HDOP, VDOP  *checksum
@@ -268,43 +618,38 @@ pub mod gsa {
         //!
         //! Mode and DimentionFix should always be given. The other values don't have to be.
 
-        let header = args.get(0).unwrap();
-        if &header[3..6] != "GSA" {
-            panic!(
-                "Incorrect sentence header. Should be GSA, it is {}",
-                header
-            )
-        }
+        check_header(&args, "GSA")?;
+        let talker = parse_talker(&args);
 
-        let mode = match args.get(1).unwrap() {
-            &"M" => Mode::Manual,
-            &"A" => Mode::Automatic,
+        let mode = match get_field(&args, 1, "GSA")? {
+            "M" => Mode::Manual,
+            "A" => Mode::Automatic,
             _ => Mode::Manual, // Default.
         };
-        let dimention_fix = match args.get(2).unwrap() {
-            &"1" => DimensionFix::NotAvailable,
-            &"2" => DimensionFix::Dimension2d,
-            &"3" => DimensionFix::Dimension3d,
+        let dimention_fix = match get_field(&args, 2, "GSA")? {
+            "1" => DimensionFix::NotAvailable,
+            "2" => DimensionFix::Dimension2d,
+            "3" => DimensionFix::Dimension3d,
             _ => DimensionFix::NotAvailable,
         };
-        let sat1: Option<i32> = args.get(3).unwrap().parse::<i32>().ok();
-        let sat2: Option<i32> = args.get(4).unwrap().parse::<i32>().ok();
-        let sat3: Option<i32> = args.get(5).unwrap().parse::<i32>().ok();
-        let sat4: Option<i32> = args.get(6).unwrap().parse::<i32>().ok();
-        let sat5: Option<i32> = args.get(7).unwrap().parse::<i32>().ok();
-        let sat6: Option<i32> = args.get(8).unwrap().parse::<i32>().ok();
-        let sat7: Option<i32> = args.get(9).unwrap().parse::<i32>().ok();
-        let sat8: Option<i32> = args.get(10).unwrap().parse::<i32>().ok();
-        let sat9: Option<i32> = args.get(11).unwrap().parse::<i32>().ok();
-        let sat10: Option<i32> = args.get(12).unwrap().parse::<i32>().ok();
-        let sat11: Option<i32> = args.get(13).unwrap().parse::<i32>().ok();
-        let sat12: Option<i32> = args.get(14).unwrap().parse::<i32>().ok();
-
-        let pdop: Option<f32> = args.get(15).unwrap().parse::<f32>().ok();
-        let hdop: Option<f32> = args.get(16).unwrap().parse::<f32>().ok();
-        let vdop: Option<f32> = args.get(17).unwrap().parse::<f32>().ok();
-
-        return GsaData {
+        let sat1: Option<i32> = parse_field(&args, 3, "GSA")?;
+        let sat2: Option<i32> = parse_field(&args, 4, "GSA")?;
+        let sat3: Option<i32> = parse_field(&args, 5, "GSA")?;
+        let sat4: Option<i32> = parse_field(&args, 6, "GSA")?;
+        let sat5: Option<i32> = parse_field(&args, 7, "GSA")?;
+        let sat6: Option<i32> = parse_field(&args, 8, "GSA")?;
+        let sat7: Option<i32> = parse_field(&args, 9, "GSA")?;
+        let sat8: Option<i32> = parse_field(&args, 10, "GSA")?;
+        let sat9: Option<i32> = parse_field(&args, 11, "GSA")?;
+        let sat10: Option<i32> = parse_field(&args, 12, "GSA")?;
+        let sat11: Option<i32> = parse_field(&args, 13, "GSA")?;
+        let sat12: Option<i32> = parse_field(&args, 14, "GSA")?;
+
+        let pdop: Option<f32> = parse_field(&args, 15, "GSA")?;
+        let hdop: Option<f32> = parse_field(&args, 16, "GSA")?;
+        let vdop: Option<f32> = parse_field(&args, 17, "GSA")?;
+
+        Ok(GsaData {
             mode,
             dimension_fix: dimention_fix,
             sat1,
@@ -322,7 +667,8 @@ pub mod gsa {
             pdop,
             hdop,
             vdop,
-        };
+            talker,
+        })
     }
 }
 
@@ -332,7 +678,16 @@ pub mod gsv {
     //! GSV gives satellites in view. If there are many satellites in view it will require
     //! multiple sentences.
     //!
+    //! Each constellation gives its own group of GSV sentences (GPGSV, GLGSV...), and other sentences
+    //! can come between the messages of a group. GsvAssembler puts the messages of each group back
+    //! together into a SkyView.
+
+    use std::collections::{HashMap, VecDeque};
+    use std::fmt;
 
+    use super::encode_nmea::*;
+    use super::parse_nmea::*;
+    use super::talker::Talker;
     use serde::{Serialize, Deserialize};
 
     /// The struct for a single satellite. To be accessed as a vector.
@@ -340,15 +695,107 @@ pub mod gsv {
     /// - elevation -> Elevation of the satellite in degrees
     /// - azimuth -> The degrees from north the satellite is, if it was on the ground.
     /// - snr -> Signal to Noise ratio: Signal / Noise , 0-99, null if not tracking.
+    /// - talker -> [Talker](nmea/talker/enum.Talker.html) that gave the sentence.
     #[derive(PartialEq, Debug, Default, Serialize, Deserialize, Clone)]
     pub struct Satellites {
         pub id: Option<i32>,
         pub elevation: Option<f32>,
         pub azimuth: Option<f32>,
         pub snr: Option<f32>,
+        pub talker: Talker,
+    }
+
+    /// All the satellites in view of one constellation, from a complete group of GSV messages.
+    /// - talker -> [Talker](nmea/talker/enum.Talker.html) of the group.
+    /// - in_view -> Satellites in view, as given by field 3. The same as satellites.len().
+    /// - satellites -> [Satellites](nmea/gsv/struct.Satellites.html)
+    #[derive(PartialEq, Debug, Default, Serialize, Deserialize, Clone)]
+    pub struct SkyView {
+        pub talker: Talker,
+        pub in_view: u8,
+        pub satellites: Vec<Satellites>,
+    }
+
+    impl SkyView {
+        /// The GSV sentences for the talker, such as $GPGSV, with 4 satellites in each.
+        pub fn to_nmea(&self, talker: Talker) -> Vec<String> {
+            let groups: Vec<&[Satellites]> = if self.satellites.is_empty() {
+                vec![&[]]
+            } else {
+                self.satellites.chunks(4).collect()
+            };
+            groups.iter().enumerate().map(|(index, satellites)| {
+                let mut fields = vec![
+                    encode_address(talker, "GSV"),
+                    groups.len().to_string(),
+                    (index + 1).to_string(),
+                    format!("{:02}", self.in_view),
+                ];
+                for satellite in satellites.iter() {
+                    fields.push(encode_field(&satellite.id.map(|id| format!("{:02}", id))));
+                    fields.push(encode_field(&satellite.elevation));
+                    fields.push(encode_field(&satellite.azimuth));
+                    fields.push(encode_field(&satellite.snr));
+                }
+                encode_sentence(&fields)
+            }).collect()
+        }
+    }
+
+    /// One GSV sentence.
+    /// - talker -> [Talker](nmea/talker/enum.Talker.html) that gave the sentence.
+    /// - total -> Number of messages in the group.
+    /// - number -> This message's number, 1 to total.
+    /// - in_view -> Satellites in view over the whole group.
+    /// - satellites -> The satellites in this message, up to 4.
+    #[derive(PartialEq, Debug, Default, Serialize, Deserialize, Clone)]
+    pub struct GsvMessage {
+        pub talker: Talker,
+        pub total: u8,
+        pub number: u8,
+        pub in_view: u8,
+        pub satellites: Vec<Satellites>,
+    }
+
+    /// Why a group of GSV messages was dropped.
+    /// - OutOfOrder -> A message number was not the next one expected.
+    /// - Incomplete -> A new group started before this one was complete.
+    /// - InViewMismatch -> The group is complete but the satellites received do not match the
+    ///   satellites in view.
+    #[derive(PartialEq, Debug, Serialize, Deserialize, Clone)]
+    pub enum GsvDiagnostic {
+        OutOfOrder { talker: Talker, expected: u8, given: u8 },
+        Incomplete { talker: Talker, received: u8, total: u8 },
+        InViewMismatch { talker: Talker, in_view: u8, received: usize },
+    }
+
+    impl GsvDiagnostic {
+        /// The field the diagnostic is about: 2 for the message number, 3 for satellites in view.
+        pub fn field(&self) -> usize {
+            match self {
+                GsvDiagnostic::InViewMismatch { .. } => 3,
+                _ => 2,
+            }
+        }
     }
 
-    pub fn parse_gsv(args: Vec<&str>) -> Vec<Satellites> {
+    impl fmt::Display for GsvDiagnostic {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                GsvDiagnostic::OutOfOrder { talker, expected, given } => {
+                    write!(f, "{} GSV message {} given, expected {}", talker.code(), given, expected)
+                }
+                GsvDiagnostic::Incomplete { talker, received, total } => {
+                    write!(f, "{} GSV group dropped after {} of {} messages", talker.code(), received, total)
+                }
+                GsvDiagnostic::InViewMismatch { talker, in_view, received } => {
+                    write!(f, "{} GSV gives {} satellites in view but {} were received", talker.code(), in_view, received)
+                }
+            }
+        }
+    }
+
+    pub fn parse_gsv(args: Vec<&str>) -> Result<Vec<Satellites>, NmeaParseError> {
         //! Format $GPGSV, Number of messages, Message number, Sats in view,
         //!      sat ID, Sat elevation, Sat Azimuth, Sat SNE, Repeat 4 times, *checksum
         //!
@@ -360,37 +807,126 @@ pub mod gsv {
         //!
         //! $GPGSV,1,1,00*79 if no satellites are in view.
         //!
-        //! Max of 4 messages so 16 total satellites.
-        //!
-        //! Assumes that the sentences will always come one after another, I can just read the next sentences.
+        //! Only the satellites of this message are given, see parse_gsv_message and GsvAssembler
+        //! for the whole group.
 
-        let header = args.get(0).unwrap();
-        if &header[3..6] != "GSV" {
-            panic!(
-                "Incorrect sentence header. Should be GSV, it is {}",
-                header
-            )
-        }
+        check_header(&args, "GSV")?;
         let mut values = Vec::new();
-        let _meta = &args.get(0..4);
-        let sat1 = &args.get(4..8);
-        let sat2 = &args.get(8..12);
-        let sat3 = &args.get(12..16);
-        let sat4 = &args.get(16..20);
-        for sat in &[sat1, sat2, sat3, sat4] {
-            if sat.is_some() {
-                values.push(parse_sat(sat.unwrap()))
+        // Satellites start at 4, 8, 12 and 16. Only full sets of 4 fields are read.
+        for start in &[4, 8, 12, 16] {
+            if args.len() >= start + 4 {
+                values.push(parse_sat(&args, *start)?)
             }
         }
-        values
+        Ok(values)
+    }
+
+    /// Parses one GSV sentence with its message number.
+    pub fn parse_gsv_message(args: Vec<&str>) -> Result<GsvMessage, NmeaParseError> {
+        check_header(&args, "GSV")?;
+        let required = |index: usize| -> Result<u8, NmeaParseError> {
+            parse_field(&args, index, "GSV")?
+                .ok_or_else(|| NmeaParseError::new("GSV", index, ParseErrorReason::Missing))
+        };
+        let total = required(1)?;
+        let number = required(2)?;
+        let in_view = required(3)?;
+        if total == 0 {
+            return Err(NmeaParseError::new("GSV", 1, ParseErrorReason::Invalid(args[1].to_string())));
+        }
+        if number == 0 || number > total {
+            return Err(NmeaParseError::new("GSV", 2, ParseErrorReason::Invalid(args[2].to_string())));
+        }
+        Ok(GsvMessage { talker: parse_talker(&args), total, number, in_view, satellites: parse_gsv(args)? })
+    }
+
+    fn parse_sat(args: &[&str], start: usize) -> Result<Satellites, NmeaParseError> {
+        Ok(Satellites {
+            id: parse_field(args, start, "GSV")?,
+            elevation: parse_field(args, start + 1, "GSV")?,
+            azimuth: parse_field(args, start + 2, "GSV")?,
+            snr: parse_field(args, start + 3, "GSV")?,
+            talker: parse_talker(args),
+        })
+    }
+
+    /// A group being put together.
+    #[derive(Debug)]
+    struct Group {
+        received: u8,
+        in_view: u8,
+        satellites: Vec<Satellites>,
+    }
+
+    /// # GsvAssembler
+    /// Puts GSV messages back together into a SkyView for each constellation.
+    ///
+    /// Groups are keyed by talker and number of messages, so a GPGSV and a GLGSV group can be given
+    /// at the same time, and other sentences can come between messages. Partial groups are kept
+    /// until the next message is pushed.
+    ///
+    /// A group is dropped with a [GsvDiagnostic](enum.GsvDiagnostic.html) when a message is out of
+    /// order, a new group starts before it is complete, or the satellites received do not match the
+    /// satellites in view.
+    #[derive(Debug, Default)]
+    pub struct GsvAssembler {
+        groups: HashMap<(Talker, u8), Group>,
+        ready: VecDeque<Result<SkyView, GsvDiagnostic>>,
     }
 
-    fn parse_sat(args: &[&str]) -> Satellites {
-        Satellites {
-            id: args.get(0).unwrap().parse().ok(),
-            elevation: args.get(1).unwrap().parse().ok(),
-            azimuth: args.get(2).unwrap().parse().ok(),
-            snr: args.get(3).unwrap().parse().ok(),
+    impl GsvAssembler {
+        pub fn new() -> GsvAssembler {
+            GsvAssembler::default()
+        }
+
+        /// Adds a message. Complete groups and dropped groups are given by pop().
+        pub fn push(&mut self, message: GsvMessage) {
+            let talker = message.talker;
+            let key = (talker, message.total);
+            if message.number == 1 {
+                // A new cycle: whatever is left of the last one for this talker is not coming.
+                let mut stale: Vec<(Talker, u8)> = self.groups.keys().filter(|(t, _)| *t == talker).copied().collect();
+                stale.sort_by_key(|(_, total)| *total);
+                for stale_key in stale {
+                    if let Some(group) = self.groups.remove(&stale_key) {
+                        let diagnostic = GsvDiagnostic::Incomplete { talker, received: group.received, total: stale_key.1 };
+                        self.ready.push_back(Err(diagnostic));
+                    }
+                }
+                self.groups.insert(key, Group { received: 0, in_view: message.in_view, satellites: Vec::new() });
+            }
+            let expected = match self.groups.get(&key) {
+                Some(group) => group.received + 1,
+                None => 1,
+            };
+            if message.number != expected {
+                self.groups.remove(&key);
+                let diagnostic = GsvDiagnostic::OutOfOrder { talker, expected, given: message.number };
+                self.ready.push_back(Err(diagnostic));
+                return;
+            }
+            let group = self.groups.get_mut(&key).expect("Group exists as a message was expected");
+            group.received = message.number;
+            group.satellites.extend(message.satellites);
+            if message.number == message.total {
+                let group = self.groups.remove(&key).expect("Group exists as it was just added to");
+                self.ready.push_back(if group.satellites.len() == group.in_view as usize {
+                    Ok(SkyView { talker, in_view: group.in_view, satellites: group.satellites })
+                } else {
+                    Err(GsvDiagnostic::InViewMismatch { talker, in_view: group.in_view, received: group.satellites.len() })
+                });
+            }
+        }
+
+        /// The next complete SkyView, or the diagnostic of a dropped group, in the order they were
+        /// completed or dropped.
+        pub fn pop(&mut self) -> Option<Result<SkyView, GsvDiagnostic>> {
+            self.ready.pop_front()
+        }
+
+        /// True when a group has been started but is not complete.
+        pub fn is_partial(&self) -> bool {
+            !self.groups.is_empty()
         }
     }
 }
@@ -399,31 +935,66 @@ pub mod rmc {
     //! # Recommended Minimum data
     //!
     //! Gives UTC, latitude, longitude, Speed, True course, Magnetic course, Date, Magnatic variation
+    use super::encode_nmea::*;
     use super::parse_nmea::*;
+    use super::talker::Talker;
     use serde::{Serialize, Deserialize};
 
+    use crate::time::utc::{UtcDate, UtcDateTime, UtcTime};
+
     /// # RmcData
-    /// - utc: UTC
+    /// - utc: UTC time -> [UtcTime](../../time/utc/struct.UtcTime.html)
     /// - fix_status: Is there a fix with some satellites? True/False
     /// - latitude
     /// - longitude
     /// - speed: in Knots
     /// - course: Track angle in degrees against true north.
-    /// - data: the date as a string. ddmmyy.
+    /// - date: UTC date -> [UtcDate](../../time/utc/struct.UtcDate.html)
     /// - mag_var: Magnetic variation between true north and magnetic north.
+    /// - talker: [Talker](nmea/talker/enum.Talker.html) that gave the sentence.
     #[derive(PartialEq, Debug, Default, Serialize, Deserialize, Clone)]
     pub struct RmcData {
-        pub utc: f64,
+        pub utc: Option<UtcTime>,
         pub fix_status: bool,
         pub latitude: Option<f32>,
         pub longitude: Option<f32>,
         pub speed: Option<f32>,
         pub course: Option<f32>,
-        pub date: String,
+        pub date: Option<UtcDate>,
         pub mag_var: Option<f32>,
+        pub talker: Talker,
     }
 
-    pub fn parse_rmc(args: Vec<&str>) -> RmcData {
+    impl RmcData {
+        /// The date and time, if both are given.
+        pub fn datetime(&self) -> Option<UtcDateTime> {
+            Some(UtcDateTime::new(self.date?, self.utc?))
+        }
+
+        /// The RMC sentence for the talker, such as $GPRMC.
+        pub fn to_nmea(&self, talker: Talker) -> String {
+            let [latitude, north_south] = encode_degrees_field(self.latitude, 2, "N", "S");
+            let [longitude, east_west] = encode_degrees_field(self.longitude, 3, "E", "W");
+            let [mag_var, mag_var_direction] = encode_signed_field(self.mag_var, "E", "W");
+            encode_sentence(&[
+                encode_address(talker, "RMC"),
+                encode_field(&self.utc.map(|utc| utc.to_nmea())),
+                if self.fix_status { "A" } else { "V" }.to_string(),
+                latitude,
+                north_south,
+                longitude,
+                east_west,
+                encode_field(&self.speed),
+                encode_field(&self.course),
+                encode_field(&self.date.map(|date| date.to_nmea())),
+                mag_var,
+                mag_var_direction,
+                if self.fix_status { "A" } else { "N" }.to_string(),
+            ])
+        }
+    }
+
+    pub fn parse_rmc(args: Vec<&str>) -> Result<RmcData, NmeaParseError> {
         //! Magnetic variation, positive is east, negative is west.
         //! Data string format:
         //!   0     1         2       3           4       5       6           7       8           9
@@ -431,23 +1002,26 @@ pub mod rmc {
         //!         10                           11                  12
         //! magnetic variation (degrees), magnetic variation (E/W), Mode * checksum
 
-        let utc = args.get(1).unwrap().parse().unwrap_or(0.0);
-        let fix_status = match args.get(2).unwrap_or(&"V") {
-            &"A" => true,
-            &"V" => false,
+        check_header(&args, "RMC")?;
+        let talker = parse_talker(&args);
+
+        let utc: Option<UtcTime> = parse_field(&args, 1, "RMC")?;
+        let fix_status = match get_field(&args, 2, "RMC")? {
+            "A" => true,
+            "V" => false,
             _ => false,
         };
-        let latitude: Option<f32> = _parse_degrees(args.get(3).unwrap(), args.get(4).unwrap());
-        let longitude: Option<f32> = _parse_degrees(args.get(5).unwrap(), args.get(6).unwrap());
-        let speed: Option<f32> = args.get(7).unwrap().parse::<f32>().ok();
-        let course: Option<f32> = args.get(8).unwrap().parse::<f32>().ok();
-        let date: String = args.get(9).unwrap_or(&"").to_string();
-        let mag_var: Option<f32> = match args.get(12).unwrap_or(&"") {
-            &"E" => args.get(11).unwrap().parse::<f32>().ok(),
-            &"W" => Some(args.get(11).unwrap().parse::<f32>().unwrap() * -1.0),
+        let latitude: Option<f32> = parse_degrees_field(&args, 3, "RMC")?;
+        let longitude: Option<f32> = parse_degrees_field(&args, 5, "RMC")?;
+        let speed: Option<f32> = parse_field(&args, 7, "RMC")?;
+        let course: Option<f32> = parse_field(&args, 8, "RMC")?;
+        let date: Option<UtcDate> = parse_field(&args, 9, "RMC")?;
+        let mag_var: Option<f32> = match args.get(11).copied().unwrap_or("") {
+            "E" => parse_field(&args, 10, "RMC")?,
+            "W" => parse_field::<f32>(&args, 10, "RMC")?.map(|v| -v),
             _ => None,
         };
-        return RmcData {
+        Ok(RmcData {
             utc,
             fix_status,
             latitude,
@@ -456,7 +1030,8 @@ pub mod rmc {
             course,
             date,
             mag_var,
-        };
+            talker,
+        })
     }
 }
 
@@ -465,6 +1040,9 @@ pub mod vtg {
     //!
     //! Gives course headings and speed data.
 
+    use super::encode_nmea::*;
+    use super::parse_nmea::*;
+    use super::talker::Talker;
     use serde::{Serialize, Deserialize};
 
     #[derive(PartialEq, Debug, Serialize, Deserialize, Clone)]
@@ -474,6 +1052,7 @@ pub mod vtg {
         Estimated,
         Unknown,
     }
+    #[allow(clippy::derivable_impls)]
     impl Default for Mode {
         fn default() -> Mode {Mode::Unknown}
     }
@@ -484,6 +1063,7 @@ pub mod vtg {
     /// - speed_knots
     /// - speed_kpg
     /// - mode: [Mode (enum)](nmea/vtg/enum.Mode.html)
+    /// - talker: [Talker](nmea/talker/enum.Talker.html) that gave the sentence.
     #[derive(PartialEq, Debug, Default, Deserialize, Serialize, Clone)]
     pub struct VtgData {
         pub true_course: Option<f32>,
@@ -491,53 +1071,106 @@ pub mod vtg {
         pub speed_knots: Option<f32>,
         pub speed_kph: Option<f32>,
         pub mode: Mode,
+        pub talker: Talker,
     }
 
-    pub fn parse_vtg(args: Vec<&str>) -> VtgData {
+    impl VtgData {
+        /// The VTG sentence for the talker, such as $GPVTG.
+        pub fn to_nmea(&self, talker: Talker) -> String {
+            let mode = match self.mode {
+                Mode::Autonomous => "A",
+                Mode::Differential => "D",
+                Mode::Estimated => "E",
+                Mode::Unknown => "N",
+            };
+            encode_sentence(&[
+                encode_address(talker, "VTG"),
+                encode_field(&self.true_course),
+                "T".to_string(),
+                encode_field(&self.magnetic_course),
+                "M".to_string(),
+                encode_field(&self.speed_knots),
+                "N".to_string(),
+                encode_field(&self.speed_kph),
+                "K".to_string(),
+                mode.to_string(),
+            ])
+        }
+    }
+
+    pub fn parse_vtg(args: Vec<&str>) -> Result<VtgData, NmeaParseError> {
         //! Sentence format
         //!
         //! $GPVTG,  course, reference (True), course, reference (magnetic), Speed, knots,
         //! speed, kph, mode.
-        let true_course: Option<f32> = args.get(1).unwrap().parse::<f32>().ok();
-        let magnetic_course: Option<f32> = args.get(3).unwrap().parse::<f32>().ok();
-        let speed_knots: Option<f32> = args.get(5).unwrap().parse::<f32>().ok();
-        let speed_kph: Option<f32> = args.get(7).unwrap().parse::<f32>().ok();
-
-        let mode = match args.get(9).unwrap_or(&"N") {
-            &"A" => Mode::Autonomous,
-            &"D" => Mode::Differential,
-            &"E" => Mode::Estimated,
+        check_header(&args, "VTG")?;
+        let talker = parse_talker(&args);
+
+        let true_course: Option<f32> = parse_field(&args, 1, "VTG")?;
+        let magnetic_course: Option<f32> = parse_field(&args, 3, "VTG")?;
+        let speed_knots: Option<f32> = parse_field(&args, 5, "VTG")?;
+        let speed_kph: Option<f32> = parse_field(&args, 7, "VTG")?;
+
+        let mode = match args.get(9).copied().unwrap_or("N") {
+            "A" => Mode::Autonomous,
+            "D" => Mode::Differential,
+            "E" => Mode::Estimated,
             _ => Mode::Unknown,
         };
-        return VtgData {
+        Ok(VtgData {
             true_course,
             magnetic_course,
             speed_knots,
             speed_kph,
             mode,
-        };
+            talker,
+        })
     }
 }
 
 pub mod gll {
     //! # Longitude and Latitude data only
+    use super::encode_nmea::*;
     use super::parse_nmea::*;
+    use super::talker::Talker;
     use serde::{Serialize, Deserialize};
 
+    use crate::time::utc::UtcTime;
+
     /// # GllData
     /// - latitude
     /// - longitude
-    /// - utc
+    /// - utc -> [UtcTime](../../time/utc/struct.UtcTime.html)
     /// - is_valid: Is there a satellite signal? True / false
+    /// - talker: [Talker](nmea/talker/enum.Talker.html) that gave the sentence.
     #[derive(PartialEq, Debug, Default, Serialize, Deserialize, Clone)]
     pub struct GllData {
         pub latitude: Option<f32>,
         pub longitude: Option<f32>,
-        pub utc: Option<f64>,
+        pub utc: Option<UtcTime>,
         pub is_valid: bool,
+        pub talker: Talker,
+    }
+
+    impl GllData {
+        /// The GLL sentence for the talker, such as $GPGLL.
+        pub fn to_nmea(&self, talker: Talker) -> String {
+            let [latitude, north_south] = encode_degrees_field(self.latitude, 2, "N", "S");
+            let [longitude, east_west] = encode_degrees_field(self.longitude, 3, "E", "W");
+            encode_sentence(&[
+                encode_address(talker, "GLL"),
+                latitude,
+                north_south,
+                longitude,
+                east_west,
+                encode_field(&self.utc.map(|utc| utc.to_nmea())),
+                if self.is_valid { "A" } else { "V" }.to_string(),
+                if self.is_valid { "A" } else { "N" }.to_string(),
+            ])
+        }
     }
 
-    pub fn parse_gll(args: Vec<&str>) -> GllData {
+    pub fn parse_gll(args: Vec<&str>) -> Result<GllData, NmeaParseError> {
         // Format for the gpgll data string:
         // [1] Latitude(as hhmm.mmm),
         // [2] Latitude North or South,
@@ -547,102 +1180,810 @@ pub mod gll {
         // [6] A
         // [7] A
 
+        check_header(&args, "GLL")?;
+        let talker = parse_talker(&args);
+
         // Parse Latitude.
 
-        let latitude: Option<f32> = _parse_degrees(args.get(1).unwrap(), args.get(2).unwrap());
-        let longitude: Option<f32> = _parse_degrees(args.get(3).unwrap(), args.get(4).unwrap());
+        let latitude: Option<f32> = parse_degrees_field(&args, 1, "GLL")?;
+        let longitude: Option<f32> = parse_degrees_field(&args, 3, "GLL")?;
         // Parse time
-        let utc = args.get(5).unwrap_or(&"0").parse::<f64>().ok();
-        let is_valid = match args.get(6).unwrap_or(&"") {
-            &"A" => true,
-            &"V" => false,
-            _ => false,
-        };
-        return GllData {
+        let utc: Option<UtcTime> = parse_optional_field(&args, 5, "GLL")?;
+        let is_valid = matches!(args.get(6), Some(&"A"));
+        Ok(GllData {
             latitude,
             longitude,
             utc,
             is_valid,
-        };
+            talker,
+        })
     }
 }
 
-#[cfg(test)]
-mod nmea_tests {
+pub mod zda {
+    //! # Time and Date
+    //!
+    //! Gives UTC, the full date with a four digit year, and the local zone offset.
+    use super::parse_nmea::*;
+    use super::talker::Talker;
+    use serde::{Serialize, Deserialize};
 
-    mod parse_nmea {
-        use crate::nmea::parse_nmea;
+    use crate::time::utc::{UtcDate, UtcDateTime, UtcTime};
 
-        #[test]
-        fn parse_degrees() {
-            assert_eq!(parse_nmea::_parse_degrees("1020.12345", "N").unwrap(),
-                       10.335391);
-            assert_eq!(parse_nmea::_parse_degrees("11020.12345", "N").unwrap(),
-                       110.335391);
+    /// # ZdaData
+    /// - utc: UTC time -> [UtcTime](../../time/utc/struct.UtcTime.html)
+    /// - date: UTC date -> [UtcDate](../../time/utc/struct.UtcDate.html)
+    /// - local_zone_hours: Local zone hours, -13 to 13.
+    /// - local_zone_minutes: Local zone minutes, 0 to 59, with the same sign as the hours.
+    /// - talker: [Talker](nmea/talker/enum.Talker.html) that gave the sentence.
+    #[derive(PartialEq, Debug, Default, Serialize, Deserialize, Clone)]
+    pub struct ZdaData {
+        pub utc: Option<UtcTime>,
+        pub date: Option<UtcDate>,
+        pub local_zone_hours: Option<i8>,
+        pub local_zone_minutes: Option<u8>,
+        pub talker: Talker,
+    }
+
+    impl ZdaData {
+        /// The date and time, if both are given.
+        pub fn datetime(&self) -> Option<UtcDateTime> {
+            Some(UtcDateTime::new(self.date?, self.utc?))
+        }
+
+        /// Local time minus UTC, in minutes. None if the zone is not given.
+        pub fn local_offset_minutes(&self) -> Option<i32> {
+            let hours = self.local_zone_hours? as i32;
+            let minutes = self.local_zone_minutes.unwrap_or(0) as i32;
+            Some(if hours < 0 { hours * 60 - minutes } else { hours * 60 + minutes })
         }
     }
 
-    mod gga {
-        use crate::nmea::gga;
+    pub fn parse_zda(args: Vec<&str>) -> Result<ZdaData, NmeaParseError> {
+        //! Data string format:
+        //!   0     1    2     3      4        5                   6
+        //! $GPZDA,UTC, day, month, year, local zone hours, local zone minutes * checksum
+        //!
+        //! MTK chips leave the local zone fields empty or 00.
 
-        #[test]
-        fn gga_normal() {
-            //${GP,GL,GA,GN}GGA, UTC, lat, N/S, long, E/S, Fix quality, Sats used, HDOP, Alt, Alt Units,
-            // Geoidal separation, Geo units, Age of diff corr, * checksum
-            assert_eq!(
-                gga::parse_gga(vec![
-                    "$GPGGA",
-                    "19294.00",
-                    "29343.543",
-                    "N",
-                    "29343.543",
-                    "E",
-                    "1",
-                    "10",
-                    "1.01",
-                    "47.7",
-                    "M",
-                    "10.0",
-                    "M",
-                    "0.1"
-                ]),
-                gga::GgaData {
-                    utc: 19294.00,
-                    lat: Some(34.725716),
-                    long: Some(34.725716),
-                    sat_fix: gga::SatFix::GpsFix,
-                    satellites_used: 10,
-                    hdop: Some(1.01),
-                    msl_alt: Some(47.7),
-                    geoidal_sep: Some(10.0),
-                    age_diff_corr: Some(0.1),
+        check_header(&args, "ZDA")?;
+        let talker = parse_talker(&args);
+
+        let utc: Option<UtcTime> = parse_field(&args, 1, "ZDA")?;
+        let day: Option<u8> = parse_field(&args, 2, "ZDA")?;
+        let month: Option<u8> = parse_field(&args, 3, "ZDA")?;
+        let year: Option<u16> = parse_field(&args, 4, "ZDA")?;
+        let date = match (year, month, day) {
+            (Some(year), Some(month), Some(day)) => match UtcDate::from_ymd(year, month, day) {
+                Some(date) => Some(date),
+                None => {
+                    let field = format!("{},{},{}", args[2], args[3], args[4]);
+                    return Err(NmeaParseError::new("ZDA", 2, ParseErrorReason::Invalid(field)));
                 }
-            );
-        }
+            },
+            _ => None,
+        };
+        let local_zone_hours: Option<i8> = parse_optional_field(&args, 5, "ZDA")?;
+        let local_zone_minutes: Option<u8> = parse_optional_field(&args, 6, "ZDA")?;
+        Ok(ZdaData {
+            utc,
+            date,
+            local_zone_hours,
+            local_zone_minutes,
+            talker,
+        })
+    }
+}
 
-        #[test]
-        #[should_panic]
-        fn gga_incorrect_header() {
-            gga::parse_gga(vec![
-                "$GPGSV",
-                "19294.00",
-                "29343.543",
-                "N",
-                "29343.543",
-                "E",
-                "1",
-                "10",
-                "1.01",
-                "47.7",
-                "M",
-                "10.0",
-                "M",
-                "0.1",
-            ]);
-        }
+pub mod gns {
+    //! # GNSS fix data
+    //!
+    //! The multi-constellation GGA: UTC, Latitude, Longitude, a mode for each constellation,
+    //! Satellites used, HDOP, MSL altitude, Geoidal separation, Age of difference correction.
+    use super::encode_nmea::*;
+    use super::parse_nmea::*;
+    use super::talker::Talker;
+    use serde::{Serialize, Deserialize};
+
+    use crate::time::utc::UtcTime;
+
+    /// Positioning mode of one constellation.
+    /// - NoFix -> No fix from this constellation. Default.
+    /// - Autonomous -> Fix without differential corrections.
+    /// - Differential -> Differential fix, such as SBAS.
+    /// - Precise -> No degradation of the satellite signals.
+    /// - RtkFixed, RtkFloat -> Real time kinematic, with fixed or floating integers.
+    /// - Estimated -> Dead reckoning.
+    /// - Manual -> Position entered by hand.
+    /// - Simulator -> Simulated fix.
+    #[derive(Debug, PartialEq, Serialize, Deserialize, Clone, Copy, Default)]
+    pub enum Mode {
+        #[default]
+        NoFix,
+        Autonomous,
+        Differential,
+        Precise,
+        RtkFixed,
+        RtkFloat,
+        Estimated,
+        Manual,
+        Simulator,
     }
-    mod gsa {
-        use crate::nmea::gsa;
+
+    /// Navigational status, NMEA 4.1 only.
+    #[derive(Debug, PartialEq, Serialize, Deserialize, Clone, Copy)]
+    pub enum NavStatus {
+        Safe,
+        Caution,
+        Unsafe,
+        NotValid,
+    }
+
+    /// # GnsData
+    /// - utc -> UTC time of the fix -> [UtcTime](../../time/utc/struct.UtcTime.html)
+    /// - lat -> Latitude
+    /// - long -> Longitude
+    /// - modes -> [Mode](nmea/gns/enum.Mode.html) for each constellation, in the order GPS, GLONASS,
+    ///   Galileo, BeiDou, QZSS, NavIC. The MTK3333 gives GPS and GLONASS.
+    /// - satellites_used -> Number of satellites used, all constellations.
+    /// - hdop -> Horizontal Dilution of Precision.
+    /// - msl_alt -> Altitude against Mean Sea Level in metres.
+    /// - geoidal_sep -> Difference between WGS-84 earth ellipsoid and mean sea level in metres.
+    /// - age_diff_corr -> Age in seconds since last update from reference station.
+    /// - diff_station -> Id of the reference station.
+    /// - nav_status -> [NavStatus](nmea/gns/enum.NavStatus.html), NMEA 4.1 only.
+    /// - talker -> [Talker](nmea/talker/enum.Talker.html) that gave the sentence.
+    #[derive(Debug, PartialEq, Default, Serialize, Deserialize, Clone)]
+    pub struct GnsData {
+        pub utc: Option<UtcTime>,
+        pub lat: Option<f32>,
+        pub long: Option<f32>,
+        pub modes: Vec<Mode>,
+        pub satellites_used: i32,
+        pub hdop: Option<f32>,
+        pub msl_alt: Option<f32>,
+        pub geoidal_sep: Option<f32>,
+        pub age_diff_corr: Option<f32>,
+        pub diff_station: Option<i32>,
+        pub nav_status: Option<NavStatus>,
+        pub talker: Talker,
+    }
+
+    impl GnsData {
+        /// True if any constellation has a fix.
+        pub fn has_fix(&self) -> bool {
+            self.modes.iter().any(|mode| *mode != Mode::NoFix)
+        }
+
+        /// The GNS sentence for the talker, such as $GNGNS. The nav status is only added if given.
+        pub fn to_nmea(&self, talker: Talker) -> String {
+            let modes: String = self.modes.iter()
+                .map(|mode| match mode {
+                    Mode::NoFix => 'N',
+                    Mode::Autonomous => 'A',
+                    Mode::Differential => 'D',
+                    Mode::Precise => 'P',
+                    Mode::RtkFixed => 'R',
+                    Mode::RtkFloat => 'F',
+                    Mode::Estimated => 'E',
+                    Mode::Manual => 'M',
+                    Mode::Simulator => 'S',
+                })
+                .collect();
+            let [lat, north_south] = encode_degrees_field(self.lat, 2, "N", "S");
+            let [long, east_west] = encode_degrees_field(self.long, 3, "E", "W");
+            let mut fields = vec![
+                encode_address(talker, "GNS"),
+                encode_field(&self.utc.map(|utc| utc.to_nmea())),
+                lat,
+                north_south,
+                long,
+                east_west,
+                modes,
+                self.satellites_used.to_string(),
+                encode_field(&self.hdop),
+                encode_field(&self.msl_alt),
+                encode_field(&self.geoidal_sep),
+                encode_field(&self.age_diff_corr),
+                encode_field(&self.diff_station),
+            ];
+            if let Some(nav_status) = &self.nav_status {
+                fields.push(match nav_status {
+                    NavStatus::Safe => "S",
+                    NavStatus::Caution => "C",
+                    NavStatus::Unsafe => "U",
+                    NavStatus::NotValid => "V",
+                }.to_string());
+            }
+            encode_sentence(&fields)
+        }
+    }
+
+    pub fn parse_gns(args: Vec<&str>) -> Result<GnsData, NmeaParseError> {
+        //! Data string format:
+        //!   0     1    2   3    4     5      6           7        8      9       10
+        //! $GNGNS,UTC, lat, N/S, long, E/W, Mode, Sats used, HDOP, Alt, Geoidal separation,
+        //!         11                  12               13
+        //! Age of diff corr, Diff station id, Nav status * checksum
+        //!
+        //! Mode is one character for each constellation: N, A, D, P, R, F, E, M or S.
+        check_header(&args, "GNS")?;
+        let talker = parse_talker(&args);
+
+        let utc: Option<UtcTime> = parse_field(&args, 1, "GNS")?;
+        let lat: Option<f32> = parse_degrees_field(&args, 2, "GNS")?;
+        let long: Option<f32> = parse_degrees_field(&args, 4, "GNS")?;
+        let mode_field = get_field(&args, 6, "GNS")?;
+        let mut modes = Vec::new();
+        for mode in mode_field.chars() {
+            modes.push(match mode {
+                'N' => Mode::NoFix,
+                'A' => Mode::Autonomous,
+                'D' => Mode::Differential,
+                'P' => Mode::Precise,
+                'R' => Mode::RtkFixed,
+                'F' => Mode::RtkFloat,
+                'E' => Mode::Estimated,
+                'M' => Mode::Manual,
+                'S' => Mode::Simulator,
+                _ => return Err(NmeaParseError::new("GNS", 6, ParseErrorReason::Invalid(mode_field.to_string()))),
+            });
+        }
+        let satellites_used: i32 = parse_field(&args, 7, "GNS")?.unwrap_or(0);
+        let hdop: Option<f32> = parse_field(&args, 8, "GNS")?;
+        let msl_alt: Option<f32> = parse_field(&args, 9, "GNS")?;
+        let geoidal_sep: Option<f32> = parse_field(&args, 10, "GNS")?;
+        let age_diff_corr: Option<f32> = parse_optional_field(&args, 11, "GNS")?;
+        let diff_station: Option<i32> = parse_optional_field(&args, 12, "GNS")?;
+        let nav_status = match args.get(13).copied().unwrap_or("") {
+            "S" => Some(NavStatus::Safe),
+            "C" => Some(NavStatus::Caution),
+            "U" => Some(NavStatus::Unsafe),
+            "V" => Some(NavStatus::NotValid),
+            _ => None,
+        };
+        Ok(GnsData {
+            utc,
+            lat,
+            long,
+            modes,
+            satellites_used,
+            hdop,
+            msl_alt,
+            geoidal_sep,
+            age_diff_corr,
+            diff_station,
+            nav_status,
+            talker,
+        })
+    }
+}
+
+pub mod gst {
+    //! # Pseudorange error statistics
+    //!
+    //! Gives the standard deviation of the position error, for estimating how good the fix is.
+    use super::encode_nmea::*;
+    use super::parse_nmea::*;
+    use super::talker::Talker;
+    use serde::{Serialize, Deserialize};
+
+    use crate::time::utc::UtcTime;
+
+    /// # GstData
+    /// All errors are one standard deviation, in metres.
+    /// - utc -> UTC time of the fix -> [UtcTime](../../time/utc/struct.UtcTime.html)
+    /// - rms -> RMS of the standard deviation of the range inputs.
+    /// - semi_major -> Semi-major axis of the error ellipse.
+    /// - semi_minor -> Semi-minor axis of the error ellipse.
+    /// - orientation -> Orientation of the semi-major axis, degrees from true north.
+    /// - lat_error -> Latitude error.
+    /// - long_error -> Longitude error.
+    /// - alt_error -> Altitude error.
+    /// - talker -> [Talker](nmea/talker/enum.Talker.html) that gave the sentence.
+    #[derive(Debug, PartialEq, Default, Serialize, Deserialize, Clone)]
+    pub struct GstData {
+        pub utc: Option<UtcTime>,
+        pub rms: Option<f32>,
+        pub semi_major: Option<f32>,
+        pub semi_minor: Option<f32>,
+        pub orientation: Option<f32>,
+        pub lat_error: Option<f32>,
+        pub long_error: Option<f32>,
+        pub alt_error: Option<f32>,
+        pub talker: Talker,
+    }
+
+    impl GstData {
+        /// Horizontal error in metres, from the latitude and longitude errors.
+        pub fn horizontal_error(&self) -> Option<f32> {
+            Some((self.lat_error?.powi(2) + self.long_error?.powi(2)).sqrt())
+        }
+
+        /// The GST sentence for the talker, such as $GPGST.
+        pub fn to_nmea(&self, talker: Talker) -> String {
+            encode_sentence(&[
+                encode_address(talker, "GST"),
+                encode_field(&self.utc.map(|utc| utc.to_nmea())),
+                encode_field(&self.rms),
+                encode_field(&self.semi_major),
+                encode_field(&self.semi_minor),
+                encode_field(&self.orientation),
+                encode_field(&self.lat_error),
+                encode_field(&self.long_error),
+                encode_field(&self.alt_error),
+            ])
+        }
+    }
+
+    pub fn parse_gst(args: Vec<&str>) -> Result<GstData, NmeaParseError> {
+        //! Data string format:
+        //!   0     1   2       3           4           5           6           7           8
+        //! $GPGST,UTC, RMS, Semi-major, Semi-minor, Orientation, Lat error, Long error, Alt error * checksum
+        check_header(&args, "GST")?;
+        let talker = parse_talker(&args);
+
+        Ok(GstData {
+            utc: parse_field(&args, 1, "GST")?,
+            rms: parse_field(&args, 2, "GST")?,
+            semi_major: parse_field(&args, 3, "GST")?,
+            semi_minor: parse_field(&args, 4, "GST")?,
+            orientation: parse_field(&args, 5, "GST")?,
+            lat_error: parse_field(&args, 6, "GST")?,
+            long_error: parse_field(&args, 7, "GST")?,
+            alt_error: parse_field(&args, 8, "GST")?,
+            talker,
+        })
+    }
+}
+
+pub mod gbs {
+    //! # Satellite fault detection
+    //!
+    //! Receiver Autonomous Integrity Monitoring (RAIM): the expected position errors and the
+    //! satellite most likely to have failed.
+    use super::encode_nmea::*;
+    use super::parse_nmea::*;
+    use super::talker::Talker;
+    use serde::{Serialize, Deserialize};
+
+    use crate::time::utc::UtcTime;
+
+    /// # GbsData
+    /// Errors are in metres.
+    /// - utc -> UTC time of the fix -> [UtcTime](../../time/utc/struct.UtcTime.html)
+    /// - lat_error -> Expected latitude error.
+    /// - long_error -> Expected longitude error.
+    /// - alt_error -> Expected altitude error.
+    /// - failed_satellite -> Id of the most likely failed satellite.
+    /// - missed_detection -> Probability of missed detection of the failed satellite.
+    /// - bias -> Estimated bias on the failed satellite, in metres.
+    /// - bias_std_dev -> Standard deviation of the bias.
+    /// - system_id -> GNSS system id, NMEA 4.1 only.
+    /// - signal_id -> GNSS signal id, NMEA 4.1 only.
+    /// - talker -> [Talker](nmea/talker/enum.Talker.html) that gave the sentence.
+    #[derive(Debug, PartialEq, Default, Serialize, Deserialize, Clone)]
+    pub struct GbsData {
+        pub utc: Option<UtcTime>,
+        pub lat_error: Option<f32>,
+        pub long_error: Option<f32>,
+        pub alt_error: Option<f32>,
+        pub failed_satellite: Option<i32>,
+        pub missed_detection: Option<f32>,
+        pub bias: Option<f32>,
+        pub bias_std_dev: Option<f32>,
+        pub system_id: Option<u8>,
+        pub signal_id: Option<u8>,
+        pub talker: Talker,
+    }
+
+    impl GbsData {
+        /// The GBS sentence for the talker, such as $GPGBS. The system and signal ids are only added
+        /// if given.
+        pub fn to_nmea(&self, talker: Talker) -> String {
+            let mut fields = vec![
+                encode_address(talker, "GBS"),
+                encode_field(&self.utc.map(|utc| utc.to_nmea())),
+                encode_field(&self.lat_error),
+                encode_field(&self.long_error),
+                encode_field(&self.alt_error),
+                encode_field(&self.failed_satellite),
+                encode_field(&self.missed_detection),
+                encode_field(&self.bias),
+                encode_field(&self.bias_std_dev),
+            ];
+            fields.extend(encode_ids(self.system_id, self.signal_id));
+            encode_sentence(&fields)
+        }
+    }
+
+    pub fn parse_gbs(args: Vec<&str>) -> Result<GbsData, NmeaParseError> {
+        //! Data string format:
+        //!   0     1      2          3           4          5                 6
+        //! $GPGBS,UTC, Lat error, Long error, Alt error, Failed sat id, Missed detection,
+        //!   7         8              9          10
+        //! Bias, Bias std dev, System id, Signal id * checksum
+        check_header(&args, "GBS")?;
+        let talker = parse_talker(&args);
+
+        Ok(GbsData {
+            utc: parse_field(&args, 1, "GBS")?,
+            lat_error: parse_field(&args, 2, "GBS")?,
+            long_error: parse_field(&args, 3, "GBS")?,
+            alt_error: parse_field(&args, 4, "GBS")?,
+            failed_satellite: parse_field(&args, 5, "GBS")?,
+            missed_detection: parse_field(&args, 6, "GBS")?,
+            bias: parse_field(&args, 7, "GBS")?,
+            bias_std_dev: parse_field(&args, 8, "GBS")?,
+            system_id: parse_optional_hex_field(&args, 9, "GBS")?,
+            signal_id: parse_optional_hex_field(&args, 10, "GBS")?,
+            talker,
+        })
+    }
+}
+
+pub mod grs {
+    //! # Range residuals
+    //!
+    //! The difference between the measured and the calculated range for each satellite used.
+    use super::encode_nmea::*;
+    use super::parse_nmea::*;
+    use super::talker::Talker;
+    use serde::{Serialize, Deserialize};
+
+    use crate::time::utc::UtcTime;
+
+    /// How the residuals were calculated.
+    /// - UsedInGga -> Residuals were used to calculate the position given in the GGA. Default.
+    /// - Recomputed -> Residuals were recomputed after the GGA position was calculated.
+    #[derive(Debug, PartialEq, Serialize, Deserialize, Clone, Default)]
+    pub enum Mode {
+        #[default]
+        UsedInGga,
+        Recomputed,
+    }
+
+    /// # GrsData
+    /// - utc -> UTC time of the fix -> [UtcTime](../../time/utc/struct.UtcTime.html)
+    /// - mode -> [Mode](nmea/grs/enum.Mode.html)
+    /// - residuals -> 12 residuals in metres, in the order of the satellites in the GSA. None if
+    ///   there is no satellite in that place.
+    /// - system_id -> GNSS system id, NMEA 4.1 only.
+    /// - signal_id -> GNSS signal id, NMEA 4.1 only.
+    /// - talker -> [Talker](nmea/talker/enum.Talker.html) that gave the sentence.
+    #[derive(Debug, PartialEq, Default, Serialize, Deserialize, Clone)]
+    pub struct GrsData {
+        pub utc: Option<UtcTime>,
+        pub mode: Mode,
+        pub residuals: Vec<Option<f32>>,
+        pub system_id: Option<u8>,
+        pub signal_id: Option<u8>,
+        pub talker: Talker,
+    }
+
+    impl GrsData {
+        /// The GRS sentence for the talker, such as $GPGRS. Always gives 12 residuals. The system and
+        /// signal ids are only added if given.
+        pub fn to_nmea(&self, talker: Talker) -> String {
+            let mut fields = vec![
+                encode_address(talker, "GRS"),
+                encode_field(&self.utc.map(|utc| utc.to_nmea())),
+                match self.mode {
+                    Mode::UsedInGga => "0",
+                    Mode::Recomputed => "1",
+                }.to_string(),
+            ];
+            for index in 0..12 {
+                fields.push(encode_field(&self.residuals.get(index).copied().flatten()));
+            }
+            fields.extend(encode_ids(self.system_id, self.signal_id));
+            encode_sentence(&fields)
+        }
+    }
+
+    pub fn parse_grs(args: Vec<&str>) -> Result<GrsData, NmeaParseError> {
+        //! Data string format:
+        //!   0     1    2     3 - 14      15          16
+        //! $GPGRS,UTC, Mode, Residuals, System id, Signal id * checksum
+        //!
+        //! Residuals of satellites not used are left empty, or left off the end.
+        check_header(&args, "GRS")?;
+        let talker = parse_talker(&args);
+
+        let utc: Option<UtcTime> = parse_field(&args, 1, "GRS")?;
+        let mode = match get_field(&args, 2, "GRS")? {
+            "1" => Mode::Recomputed,
+            _ => Mode::UsedInGga,
+        };
+        let mut residuals = Vec::new();
+        for index in 3..15 {
+            residuals.push(parse_optional_field(&args, index, "GRS")?);
+        }
+        Ok(GrsData {
+            utc,
+            mode,
+            residuals,
+            system_id: parse_optional_hex_field(&args, 15, "GRS")?,
+            signal_id: parse_optional_hex_field(&args, 16, "GRS")?,
+            talker,
+        })
+    }
+}
+
+pub mod dtm {
+    //! # Datum reference
+    //!
+    //! Gives the local datum used for the position and its offset from the reference datum.
+    use super::encode_nmea::*;
+    use super::parse_nmea::*;
+    use super::talker::Talker;
+    use serde::{Serialize, Deserialize};
+
+    /// # Datum
+    /// - Wgs84 -> W84. Default.
+    /// - Wgs72 -> W72.
+    /// - Sgs85 -> S85.
+    /// - Pz90 -> P90, used by GLONASS.
+    /// - UserDefined -> 999.
+    /// - Other -> Any other IHO datum code.
+    #[derive(Debug, PartialEq, Serialize, Deserialize, Clone, Default)]
+    pub enum Datum {
+        #[default]
+        Wgs84,
+        Wgs72,
+        Sgs85,
+        Pz90,
+        UserDefined,
+        Other(String),
+    }
+
+    impl From<&str> for Datum {
+        fn from(code: &str) -> Datum {
+            match code {
+                "W84" => Datum::Wgs84,
+                "W72" => Datum::Wgs72,
+                "S85" => Datum::Sgs85,
+                "P90" => Datum::Pz90,
+                "999" => Datum::UserDefined,
+                code => Datum::Other(code.to_string()),
+            }
+        }
+    }
+
+    impl Datum {
+        /// The datum code used in the sentence, such as W84.
+        pub fn code(&self) -> &str {
+            match self {
+                Datum::Wgs84 => "W84",
+                Datum::Wgs72 => "W72",
+                Datum::Sgs85 => "S85",
+                Datum::Pz90 => "P90",
+                Datum::UserDefined => "999",
+                Datum::Other(code) => code,
+            }
+        }
+    }
+
+    /// # DtmData
+    /// - local_datum -> [Datum](nmea/dtm/enum.Datum.html) of the position given.
+    /// - subdivision -> Local datum subdivision code.
+    /// - lat_offset -> Latitude offset in minutes, north is positive.
+    /// - long_offset -> Longitude offset in minutes, east is positive.
+    /// - alt_offset -> Altitude offset in metres.
+    /// - reference_datum -> [Datum](nmea/dtm/enum.Datum.html) the offsets are from.
+    /// - talker -> [Talker](nmea/talker/enum.Talker.html) that gave the sentence.
+    #[derive(Debug, PartialEq, Default, Serialize, Deserialize, Clone)]
+    pub struct DtmData {
+        pub local_datum: Datum,
+        pub subdivision: Option<String>,
+        pub lat_offset: Option<f32>,
+        pub long_offset: Option<f32>,
+        pub alt_offset: Option<f32>,
+        pub reference_datum: Datum,
+        pub talker: Talker,
+    }
+
+    impl DtmData {
+        /// The DTM sentence for the talker, such as $GPDTM.
+        pub fn to_nmea(&self, talker: Talker) -> String {
+            let [lat_offset, north_south] = encode_signed_field(self.lat_offset, "N", "S");
+            let [long_offset, east_west] = encode_signed_field(self.long_offset, "E", "W");
+            encode_sentence(&[
+                encode_address(talker, "DTM"),
+                self.local_datum.code().to_string(),
+                encode_field(&self.subdivision),
+                lat_offset,
+                north_south,
+                long_offset,
+                east_west,
+                encode_field(&self.alt_offset),
+                self.reference_datum.code().to_string(),
+            ])
+        }
+    }
+
+    pub fn parse_dtm(args: Vec<&str>) -> Result<DtmData, NmeaParseError> {
+        //! Data string format:
+        //!   0          1              2             3          4     5            6
+        //! $GPDTM,Local datum, Subdivision code, Lat offset, N/S, Long offset, E/W,
+        //!      7              8
+        //! Alt offset, Reference datum * checksum
+        check_header(&args, "DTM")?;
+        let talker = parse_talker(&args);
+
+        let local_datum = Datum::from(get_field(&args, 1, "DTM")?);
+        let subdivision = match get_field(&args, 2, "DTM")? {
+            "" => None,
+            code => Some(code.to_string()),
+        };
+        let lat_offset: Option<f32> = parse_signed_field(&args, 3, "DTM")?;
+        let long_offset: Option<f32> = parse_signed_field(&args, 5, "DTM")?;
+        let alt_offset: Option<f32> = parse_field(&args, 7, "DTM")?;
+        let reference_datum = Datum::from(get_field(&args, 8, "DTM")?);
+        Ok(DtmData {
+            local_datum,
+            subdivision,
+            lat_offset,
+            long_offset,
+            alt_offset,
+            reference_datum,
+            talker,
+        })
+    }
+}
+
+#[cfg(test)]
+mod nmea_tests {
+
+    mod talker {
+        use crate::nmea::talker::Talker;
+
+        #[test]
+        fn from_address() {
+            assert_eq!(Talker::from_address("$GPGGA"), Talker::Gps);
+            assert_eq!(Talker::from_address("GLGSV"), Talker::Glonass);
+            assert_eq!(Talker::from_address("$GAGSA"), Talker::Galileo);
+            assert_eq!(Talker::from_address("$BDGSA"), Talker::BeiDou);
+            assert_eq!(Talker::from_address("$GBGSA"), Talker::BeiDou);
+            assert_eq!(Talker::from_address("$QZGSV"), Talker::Qzss);
+            assert_eq!(Talker::from_address("$GNRMC"), Talker::Multi);
+            assert_eq!(Talker::from_address("$PMTK001"), Talker::Unknown);
+            assert_eq!(Talker::from_address("$"), Talker::Unknown);
+        }
+
+        #[test]
+        fn code() {
+            for talker in [Talker::Gps, Talker::Glonass, Talker::Galileo, Talker::BeiDou, Talker::Qzss,
+                Talker::NavIC, Talker::Multi].iter() {
+                assert_eq!(Talker::from_address(talker.code()), *talker);
+            }
+        }
+    }
+
+    mod parse_nmea {
+        use crate::nmea::parse_nmea;
+        use crate::nmea::parse_nmea::{NmeaParseError, ParseErrorReason};
+
+        #[test]
+        // Degrees as worked out by hand, more precise than the f32 they are given as.
+        #[allow(clippy::excessive_precision)]
+        fn parse_degrees() {
+            assert_eq!(parse_nmea::_parse_degrees("1020.12345", "N").unwrap(),
+                       Some(10.335391));
+            assert_eq!(parse_nmea::_parse_degrees("11020.12345", "N").unwrap(),
+                       Some(110.335391));
+            assert_eq!(parse_nmea::_parse_degrees("11020.12345", "W").unwrap(),
+                       Some(-110.335391));
+            assert_eq!(parse_nmea::_parse_degrees("", "").unwrap(), None);
+        }
+
+        #[test]
+        fn parse_degrees_invalid() {
+            assert_eq!(parse_nmea::_parse_degrees("1020.12345", "X"),
+                       Err(ParseErrorReason::Invalid("X".to_string())));
+            assert_eq!(parse_nmea::_parse_degrees("10é0.1", "N"),
+                       Err(ParseErrorReason::Invalid("10é0.1".to_string())));
+            assert_eq!(parse_nmea::_parse_degrees("1", "N"),
+                       Err(ParseErrorReason::Invalid("1".to_string())));
+        }
+
+        #[test]
+        fn parse_degrees_field() {
+            assert_eq!(parse_nmea::parse_degrees_field(&["$GPGLL", "5132.7314", "Q"], 1, "GLL"),
+                       Err(NmeaParseError::new("GLL", 2, ParseErrorReason::Invalid("Q".to_string()))));
+            assert_eq!(parse_nmea::parse_degrees_field(&["$GPGLL", "5132.7314"], 1, "GLL"),
+                       Err(NmeaParseError::new("GLL", 2, ParseErrorReason::Missing)));
+        }
+    }
+
+    mod gga {
+        use crate::nmea::talker::Talker;
+        use crate::nmea::gga;
+        use crate::nmea::parse_nmea::{NmeaParseError, ParseErrorReason};
+        use crate::time::utc::UtcTime;
+
+        #[test]
+        fn gga_normal() {
+            //${GP,GL,GA,GN}GGA, UTC, lat, N/S, long, E/S, Fix quality, Sats used, HDOP, Alt, Alt Units,
+            // Geoidal separation, Geo units, Age of diff corr, * checksum
+            assert_eq!(
+                gga::parse_gga(vec![
+                    "$GPGGA",
+                    "192940.00",
+                    "3443.543",
+                    "N",
+                    "03443.543",
+                    "E",
+                    "1",
+                    "10",
+                    "1.01",
+                    "47.7",
+                    "M",
+                    "10.0",
+                    "M",
+                    "0.1"
+                ]),
+                Ok(gga::GgaData {
+                    utc: Some(UtcTime { hour: 19, minute: 29, second: 40, millisecond: 0 }),
+                    lat: Some(34.725716),
+                    long: Some(34.725716),
+                    sat_fix: gga::SatFix::GpsFix,
+                    satellites_used: 10,
+                    hdop: Some(1.01),
+                    msl_alt: Some(47.7),
+                    geoidal_sep: Some(10.0),
+                    age_diff_corr: Some(0.1),
+                    talker: Talker::Gps,
+                })
+            );
+        }
+
+        #[test]
+        fn gga_incorrect_header() {
+            assert_eq!(
+                gga::parse_gga(vec![
+                    "$GPGSV",
+                    "19294.00",
+                    "29343.543",
+                    "N",
+                    "29343.543",
+                    "E",
+                    "1",
+                    "10",
+                    "1.01",
+                    "47.7",
+                    "M",
+                    "10.0",
+                    "M",
+                    "0.1",
+                ]),
+                Err(NmeaParseError::new("GGA", 0, ParseErrorReason::WrongSentence("$GPGSV".to_string())))
+            );
+        }
+
+        #[test]
+        fn gga_truncated() {
+            assert_eq!(
+                gga::parse_gga(vec!["$GPGGA", "131613.000", "5132.7314", "N", "00005.9099", "W", "1"]),
+                Err(NmeaParseError::new("GGA", 7, ParseErrorReason::Missing))
+            );
+        }
+
+        #[test]
+        fn gga_bad_satellites_used() {
+            assert_eq!(
+                gga::parse_gga(vec![
+                    "$GPGGA", "131613.000", "5132.7314", "N", "00005.9099", "W", "1", "9x", "1.17",
+                    "42.4", "M", "47.0", "M", "", ""
+                ]),
+                Err(NmeaParseError::new("GGA", 7, ParseErrorReason::Invalid("9x".to_string())))
+            );
+        }
+    }
+    mod gsa {
+        use crate::nmea::talker::Talker;
+        use crate::nmea::gsa;
+        use crate::nmea::parse_nmea::{NmeaParseError, ParseErrorReason};
 
         #[test]
         fn gsa_normal() {
@@ -651,7 +1992,7 @@ mod nmea_tests {
                     "$GPGSA", "M", "2", "01", "02", "03", "04", "05", "06", "07", "08", "09", "10",
                     "11", "12", "1.0", "2.04", "32.04"
                 ]),
-                gsa::GsaData {
+                Ok(gsa::GsaData {
                     mode: gsa::Mode::Manual,
                     dimension_fix: gsa::DimensionFix::Dimension2d,
                     sat1: Some(1),
@@ -668,20 +2009,529 @@ mod nmea_tests {
                     sat12: Some(12),
                     pdop: Some(1.0),
                     hdop: Some(2.04),
-                    vdop: Some(32.04)
-                }
+                    vdop: Some(32.04),
+                    talker: Talker::Gps,
+                })
             )
         }
         #[test]
-        #[should_panic]
         fn gsa_incorrect_header() {
-            gsa::parse_gsa(vec![
-                "$GPGGA", "M", "2", "01", "02", "03", "04", "05", "06", "07", "08", "09", "10",
-                "11", "12", "1.0", "2.04", "32.04",
-            ]);
+            assert_eq!(
+                gsa::parse_gsa(vec![
+                    "$GPGGA", "M", "2", "01", "02", "03", "04", "05", "06", "07", "08", "09", "10",
+                    "11", "12", "1.0", "2.04", "32.04",
+                ]),
+                Err(NmeaParseError::new("GSA", 0, ParseErrorReason::WrongSentence("$GPGGA".to_string())))
+            );
+        }
+    }
+    mod gsv {
+        use crate::nmea::talker::Talker;
+        use crate::nmea::gsv;
+        use crate::nmea::parse_nmea::{NmeaParseError, ParseErrorReason};
+
+        #[test]
+        fn gsv_partial_last_message() {
+            let sats = gsv::parse_gsv(vec!["$GPGSV", "4", "4", "14", "32", "01", "215", "", "41", "", "", ""]);
+            assert_eq!(
+                sats,
+                Ok(vec![
+                    gsv::Satellites { id: Some(32), elevation: Some(1.0), azimuth: Some(215.0), snr: None, talker: Talker::Gps },
+                    gsv::Satellites { id: Some(41), elevation: None, azimuth: None, snr: None, talker: Talker::Gps },
+                ])
+            );
+        }
+
+        #[test]
+        fn gsv_invalid_snr() {
+            assert_eq!(
+                gsv::parse_gsv(vec!["$GPGSV", "1", "1", "01", "32", "01", "215", "--"]),
+                Err(NmeaParseError::new("GSV", 7, ParseErrorReason::Invalid("--".to_string())))
+            );
+        }
+
+        fn message(talker: Talker, total: u8, number: u8, in_view: u8, ids: &[i32]) -> gsv::GsvMessage {
+            let satellites = ids.iter()
+                .map(|id| gsv::Satellites { id: Some(*id), talker, ..Default::default() })
+                .collect();
+            gsv::GsvMessage { talker, total, number, in_view, satellites }
+        }
+
+        #[test]
+        fn gsv_message_numbers() {
+            let message = gsv::parse_gsv_message(vec!["$GLGSV", "3", "2", "09", "65", "40", "050", "35"]).unwrap();
+            assert_eq!((message.talker, message.total, message.number, message.in_view), (Talker::Glonass, 3, 2, 9));
+            assert_eq!(message.satellites.len(), 1);
+            assert_eq!(
+                gsv::parse_gsv_message(vec!["$GPGSV", "2", "3", "05"]),
+                Err(NmeaParseError::new("GSV", 2, ParseErrorReason::Invalid("3".to_string())))
+            );
+            assert_eq!(
+                gsv::parse_gsv_message(vec!["$GPGSV", "0", "0", "00"]),
+                Err(NmeaParseError::new("GSV", 1, ParseErrorReason::Invalid("0".to_string())))
+            );
+            assert_eq!(
+                gsv::parse_gsv_message(vec!["$GPGSV", "1", "1"]),
+                Err(NmeaParseError::new("GSV", 3, ParseErrorReason::Missing))
+            );
+        }
+
+        #[test]
+        fn gsv_interleaved_constellations() {
+            let mut assembler = gsv::GsvAssembler::new();
+            assembler.push(message(Talker::Gps, 2, 1, 5, &[1, 2, 3, 4]));
+            assembler.push(message(Talker::Glonass, 2, 1, 6, &[65, 66, 67, 68]));
+            assert_eq!(assembler.pop(), None);
+            assert!(assembler.is_partial());
+            assembler.push(message(Talker::Glonass, 2, 2, 6, &[69, 70]));
+            assembler.push(message(Talker::Gps, 2, 2, 5, &[5]));
+            let glonass = assembler.pop().unwrap().unwrap();
+            assert_eq!((glonass.talker, glonass.in_view, glonass.satellites.len()), (Talker::Glonass, 6, 6));
+            let gps = assembler.pop().unwrap().unwrap();
+            assert_eq!(gps.satellites.iter().map(|sat| sat.id.unwrap()).collect::<Vec<i32>>(), vec![1, 2, 3, 4, 5]);
+            assert_eq!(assembler.pop(), None);
+            assert!(!assembler.is_partial());
+        }
+
+        #[test]
+        fn gsv_out_of_order() {
+            let mut assembler = gsv::GsvAssembler::new();
+            assembler.push(message(Talker::Gps, 3, 1, 9, &[1, 2, 3, 4]));
+            assembler.push(message(Talker::Gps, 3, 3, 9, &[9]));
+            assert_eq!(assembler.pop(), Some(Err(gsv::GsvDiagnostic::OutOfOrder { talker: Talker::Gps, expected: 2, given: 3 })));
+            assembler.push(message(Talker::Gps, 3, 2, 9, &[5, 6, 7, 8]));
+            assert_eq!(assembler.pop(), Some(Err(gsv::GsvDiagnostic::OutOfOrder { talker: Talker::Gps, expected: 1, given: 2 })));
+            assert!(!assembler.is_partial());
+        }
+
+        #[test]
+        fn gsv_incomplete_cycle() {
+            let mut assembler = gsv::GsvAssembler::new();
+            assembler.push(message(Talker::Gps, 3, 1, 9, &[1, 2, 3, 4]));
+            assembler.push(message(Talker::Gps, 1, 1, 2, &[1, 2]));
+            assert_eq!(assembler.pop(), Some(Err(gsv::GsvDiagnostic::Incomplete { talker: Talker::Gps, received: 1, total: 3 })));
+            assert_eq!(assembler.pop().unwrap().unwrap().satellites.len(), 2);
+        }
+
+        #[test]
+        fn gsv_in_view_mismatch() {
+            let mut assembler = gsv::GsvAssembler::new();
+            assembler.push(message(Talker::BeiDou, 1, 1, 3, &[201, 202]));
+            let diagnostic = assembler.pop().unwrap().unwrap_err();
+            assert_eq!(diagnostic, gsv::GsvDiagnostic::InViewMismatch { talker: Talker::BeiDou, in_view: 3, received: 2 });
+            assert_eq!(NmeaParseError::from(diagnostic).field, 3);
+        }
+    }
+    mod rmc {
+        use crate::nmea::rmc;
+        use crate::nmea::parse_nmea::{NmeaParseError, ParseErrorReason};
+        use crate::time::utc::UtcDate;
+
+        #[test]
+        fn rmc_west_variation() {
+            let rmc = rmc::parse_rmc(vec![
+                "$GPRMC", "064951.000", "A", "2307.1256", "N", "12016.4438", "E", "0.03", "165.48",
+                "260406", "3.05", "W", "A"
+            ]).unwrap();
+            assert_eq!(rmc.mag_var, Some(-3.05));
+            assert_eq!(rmc.date, Some(UtcDate { year: 2006, month: 4, day: 26 }));
+            assert_eq!(rmc.datetime().map(|datetime| datetime.to_string()),
+                       Some("2006-04-26T06:49:51.000Z".to_string()));
+        }
+
+        #[test]
+        fn rmc_bad_hemisphere() {
+            assert_eq!(
+                rmc::parse_rmc(vec!["$GPRMC", "064951.000", "A", "2307.1256", "Z", "12016.4438", "E"]),
+                Err(NmeaParseError::new("RMC", 4, ParseErrorReason::Invalid("Z".to_string())))
+            );
+        }
+    }
+    mod vtg {
+        use crate::nmea::vtg;
+        use crate::nmea::parse_nmea::{NmeaParseError, ParseErrorReason};
+
+        #[test]
+        fn vtg_no_mode() {
+            let vtg = vtg::parse_vtg(vec!["$GPVTG", "165.48", "T", "", "M", "0.03", "N", "0.06", "K"]).unwrap();
+            assert_eq!(vtg.mode, vtg::Mode::Unknown);
+            assert_eq!(vtg.speed_kph, Some(0.06));
+        }
+
+        #[test]
+        fn vtg_truncated() {
+            assert_eq!(
+                vtg::parse_vtg(vec!["$GPVTG", "165.48", "T"]),
+                Err(NmeaParseError::new("VTG", 3, ParseErrorReason::Missing))
+            );
+        }
+    }
+    mod zda {
+        use crate::nmea::talker::Talker;
+        use crate::nmea::zda;
+        use crate::nmea::parse_nmea::{NmeaParseError, ParseErrorReason};
+        use crate::time::utc::{UtcDate, UtcTime};
+
+        #[test]
+        fn zda_normal() {
+            let zda = zda::parse_zda(vec!["$GPZDA", "172809.456", "12", "07", "1996", "00", "00"]).unwrap();
+            assert_eq!(zda, zda::ZdaData {
+                utc: Some(UtcTime { hour: 17, minute: 28, second: 9, millisecond: 456 }),
+                date: Some(UtcDate { year: 1996, month: 7, day: 12 }),
+                local_zone_hours: Some(0),
+                local_zone_minutes: Some(0),
+                talker: Talker::Gps,
+            });
+            assert_eq!(zda.datetime().map(|datetime| datetime.to_string()),
+                       Some("1996-07-12T17:28:09.456Z".to_string()));
+        }
+
+        #[test]
+        fn zda_local_zone() {
+            let zda = zda::parse_zda(vec!["$GPZDA", "050306.000", "29", "10", "2003", "-03", "30"]).unwrap();
+            assert_eq!(zda.local_offset_minutes(), Some(-210));
+            let zda = zda::parse_zda(vec!["$GNZDA", "050306.000", "29", "10", "2003", "05", "45"]).unwrap();
+            assert_eq!(zda.local_offset_minutes(), Some(345));
+        }
+
+        #[test]
+        fn zda_no_fix() {
+            // Before the first fix the date and zone are empty, or left off.
+            let zda = zda::parse_zda(vec!["$GPZDA", "000012.800", "", "", ""]).unwrap();
+            assert_eq!(zda.date, None);
+            assert_eq!(zda.local_offset_minutes(), None);
+        }
+
+        #[test]
+        fn zda_bad_date() {
+            assert_eq!(
+                zda::parse_zda(vec!["$GPZDA", "172809.456", "30", "02", "2020", "00", "00"]),
+                Err(NmeaParseError::new("ZDA", 2, ParseErrorReason::Invalid("30,02,2020".to_string())))
+            );
+            assert_eq!(
+                zda::parse_zda(vec!["$GPZDA", "172809.456", "12", "07"]),
+                Err(NmeaParseError::new("ZDA", 4, ParseErrorReason::Missing))
+            );
+        }
+    }
+    mod gns {
+        use crate::nmea::talker::Talker;
+        use crate::nmea::gns::{self, Mode};
+        use crate::nmea::parse_nmea::{NmeaParseError, ParseErrorReason};
+        use crate::time::utc::UtcTime;
+
+        #[test]
+        fn gns_gps_glonass() {
+            let gns = gns::parse_gns(vec![
+                "$GNGNS", "131613.000", "5132.7314", "N", "00005.9099", "W", "AA", "14", "1.17", "42.4",
+                "47.0", "", ""
+            ]).unwrap();
+            assert_eq!(gns, gns::GnsData {
+                utc: Some(UtcTime { hour: 13, minute: 16, second: 13, millisecond: 0 }),
+                lat: Some(51.545525),
+                long: Some(-0.098498),
+                modes: vec![Mode::Autonomous, Mode::Autonomous],
+                satellites_used: 14,
+                hdop: Some(1.17),
+                msl_alt: Some(42.4),
+                geoidal_sep: Some(47.0),
+                age_diff_corr: None,
+                diff_station: None,
+                nav_status: None,
+                talker: Talker::Multi,
+            });
+            assert!(gns.has_fix());
+        }
+
+        #[test]
+        fn gns_no_fix() {
+            let gns = gns::parse_gns(vec!["$GNGNS", "000012.800", "", "", "", "", "NN", "00", "", "", "", "", ""]).unwrap();
+            assert_eq!(gns.modes, vec![Mode::NoFix, Mode::NoFix]);
+            assert!(!gns.has_fix());
+        }
+
+        #[test]
+        fn gns_nav_status() {
+            let gns = gns::parse_gns(vec![
+                "$GNGNS", "014035.00", "4332.69262", "S", "17235.48549", "E", "RRN", "13", "0.9", "25.63",
+                "11.24", "", "", "S"
+            ]).unwrap();
+            assert_eq!(gns.modes, vec![Mode::RtkFixed, Mode::RtkFixed, Mode::NoFix]);
+            assert_eq!(gns.nav_status, Some(gns::NavStatus::Safe));
+        }
+
+        #[test]
+        fn gns_bad_mode() {
+            assert_eq!(
+                gns::parse_gns(vec!["$GNGNS", "000012.800", "", "", "", "", "NX", "00", "", "", ""]),
+                Err(NmeaParseError::new("GNS", 6, ParseErrorReason::Invalid("NX".to_string())))
+            );
+        }
+    }
+    mod gst {
+        use crate::nmea::gst;
+
+        #[test]
+        fn gst_errors() {
+            let gst = gst::parse_gst(vec![
+                "$GPGST", "172814.0", "0.006", "0.023", "0.020", "273.6", "0.023", "0.020", "0.031"
+            ]).unwrap();
+            assert_eq!(gst.semi_major, Some(0.023));
+            assert_eq!(gst.orientation, Some(273.6));
+            assert_eq!(gst.alt_error, Some(0.031));
+            assert_eq!(gst.horizontal_error().map(|e| (e * 1000.0).round()), Some(30.0));
+        }
+
+        #[test]
+        fn gst_no_fix() {
+            let gst = gst::parse_gst(vec!["$GPGST", "000012.800", "", "", "", "", "", "", ""]).unwrap();
+            assert_eq!(gst.horizontal_error(), None);
+        }
+    }
+    mod gbs {
+        use crate::nmea::gbs;
+        use crate::nmea::parse_nmea::{NmeaParseError, ParseErrorReason};
+
+        #[test]
+        fn gbs_nmea_41() {
+            let gbs = gbs::parse_gbs(vec![
+                "$GPGBS", "235458.00", "1.4", "1.3", "3.1", "03", "", "-21.4", "3.8", "1", "0"
+            ]).unwrap();
+            assert_eq!(gbs.failed_satellite, Some(3));
+            assert_eq!(gbs.missed_detection, None);
+            assert_eq!(gbs.bias, Some(-21.4));
+            assert_eq!(gbs.system_id, Some(1));
+            assert_eq!(gbs.signal_id, Some(0));
+        }
+
+        #[test]
+        fn gbs_nmea_30() {
+            let gbs = gbs::parse_gbs(vec!["$GPGBS", "235503.00", "1.6", "1.4", "3.2", "", "", "", ""]).unwrap();
+            assert_eq!(gbs.lat_error, Some(1.6));
+            assert_eq!(gbs.failed_satellite, None);
+            assert_eq!(gbs.system_id, None);
+        }
+
+        #[test]
+        fn gbs_bad_signal_id() {
+            assert_eq!(
+                gbs::parse_gbs(vec!["$GPGBS", "235458.00", "1.4", "1.3", "3.1", "", "", "", "", "1", "Z"]),
+                Err(NmeaParseError::new("GBS", 10, ParseErrorReason::Invalid("Z".to_string())))
+            );
+        }
+    }
+    mod grs {
+        use crate::nmea::grs::{self, Mode};
+
+        #[test]
+        fn grs_residuals() {
+            let grs = grs::parse_grs(vec![
+                "$GPGRS", "220320.0", "0", "-0.8", "-0.2", "-0.1", "-0.2", "0.8", "0.6", "", "", "", "", "", ""
+            ]).unwrap();
+            assert_eq!(grs.mode, Mode::UsedInGga);
+            assert_eq!(grs.residuals.len(), 12);
+            assert_eq!(grs.residuals[..7], [Some(-0.8), Some(-0.2), Some(-0.1), Some(-0.2), Some(0.8), Some(0.6), None]);
+        }
+
+        #[test]
+        fn grs_short() {
+            let grs = grs::parse_grs(vec!["$GNGRS", "220320.0", "1", "0.4", "-1.1"]).unwrap();
+            assert_eq!(grs.mode, Mode::Recomputed);
+            assert_eq!(grs.residuals.len(), 12);
+            assert_eq!(grs.residuals.iter().flatten().count(), 2);
+        }
+    }
+    mod dtm {
+        use crate::nmea::talker::Talker;
+        use crate::nmea::dtm::{self, Datum};
+        use crate::nmea::parse_nmea::{NmeaParseError, ParseErrorReason};
+
+        #[test]
+        fn dtm_user_defined() {
+            let dtm = dtm::parse_dtm(vec!["$GPDTM", "999", "", "0.08", "S", "0.07", "W", "-47.7", "W84"]).unwrap();
+            assert_eq!(dtm, dtm::DtmData {
+                local_datum: Datum::UserDefined,
+                subdivision: None,
+                lat_offset: Some(-0.08),
+                long_offset: Some(-0.07),
+                alt_offset: Some(-47.7),
+                reference_datum: Datum::Wgs84,
+                talker: Talker::Gps,
+            });
+        }
+
+        #[test]
+        fn dtm_other_datum() {
+            let dtm = dtm::parse_dtm(vec!["$GPDTM", "N27", "A", "0.0", "N", "0.0", "E", "0.0", "W84"]).unwrap();
+            assert_eq!(dtm.local_datum, Datum::Other("N27".to_string()));
+            assert_eq!(dtm.subdivision, Some("A".to_string()));
+        }
+
+        #[test]
+        fn dtm_bad_hemisphere() {
+            assert_eq!(
+                dtm::parse_dtm(vec!["$GPDTM", "W84", "", "0.08", "Q", "0.07", "E", "0.0", "W84"]),
+                Err(NmeaParseError::new("DTM", 4, ParseErrorReason::Invalid("Q".to_string())))
+            );
+        }
+    }
+    mod encode {
+        use crate::nmea::{dtm, gbs, gga, gll, gns, grs, gsa, gst, gsv, rmc, vtg};
+        use crate::nmea::encode_nmea::encode_degrees_field;
+        use crate::nmea::parse_nmea::{_parse_degrees, parse_sentence, NmeaParseError};
+        use crate::nmea::talker::Talker;
+
+        /// Parses a full sentence, checksum included.
+        fn parse<T>(sentence: &str, parser: fn(Vec<&str>) -> Result<T, NmeaParseError>) -> T {
+            parser(parse_sentence(sentence).expect("Valid checksum")).unwrap()
+        }
+
+        #[test]
+        fn gga_round_trip() {
+            let gga = parse("$GNGGA,131613.000,5132.7314,N,00005.9099,W,1,9,1.17,42.4,M,47.0,M,,*60", gga::parse_gga);
+            let sentence = gga.to_nmea(gga.talker);
+            assert!(sentence.starts_with("$GNGGA,131613.000,5132.731"));
+            assert!(sentence.contains(",N,00005.909") && sentence.contains(",W,1,9,1.17,42.4,M,47,M,,*"));
+            assert!(sentence.ends_with("\r\n"));
+            assert_eq!(parse(&sentence, gga::parse_gga), gga);
+            let empty = gga::GgaData::default();
+            assert_eq!(parse(&empty.to_nmea(Talker::Gps), gga::parse_gga), gga::GgaData { talker: Talker::Gps, ..empty });
+        }
+
+        #[test]
+        fn rmc_round_trip() {
+            let rmc = parse("$GPRMC,064951.000,A,2307.1256,S,12016.4438,E,0.03,165.48,260406,3.05,W,A*31", rmc::parse_rmc);
+            assert_eq!(rmc.mag_var, Some(-3.05));
+            let sentence = rmc.to_nmea(Talker::Gps);
+            assert!(sentence.starts_with("$GPRMC,064951.000,A,2307.125"));
+            assert!(sentence.contains(",S,12016.4438,E,0.03,165.48,260406,3.05,W,A*"));
+            assert_eq!(parse(&sentence, rmc::parse_rmc), rmc);
+        }
+
+        #[test]
+        fn vtg_gll_gsa_round_trip() {
+            let vtg = parse("$GPVTG,165.48,T,,M,0.03,N,0.06,K,A*36", vtg::parse_vtg);
+            assert_eq!(vtg.to_nmea(Talker::Gps), "$GPVTG,165.48,T,,M,0.03,N,0.06,K,A*36\r\n");
+            let gll = parse("$GPGLL,2503.6319,N,12136.0099,E,053740.000,A,A*52", gll::parse_gll);
+            assert_eq!(parse(&gll.to_nmea(Talker::Gps), gll::parse_gll), gll);
+            let gsa = parse("$GLGSA,A,3,65,66,74,75,,,,,,,,,1.42,1.17,0.80*14", gsa::parse_gsa);
+            assert_eq!(parse(&gsa.to_nmea(Talker::Glonass), gsa::parse_gsa), gsa);
+        }
+
+        #[test]
+        fn gsv_round_trip() {
+            let satellites = (1..10)
+                .map(|id| gsv::Satellites {
+                    id: Some(id),
+                    elevation: Some(id as f32 * 5.0),
+                    azimuth: Some(id as f32 * 30.0),
+                    snr: if id % 2 == 0 { Some(30.0) } else { None },
+                    talker: Talker::Galileo,
+                })
+                .collect();
+            let view = gsv::SkyView { talker: Talker::Galileo, in_view: 9, satellites };
+            let sentences = view.to_nmea(Talker::Galileo);
+            assert_eq!(sentences.len(), 3);
+            assert!(sentences[2].starts_with("$GAGSV,3,3,09,09,45,270,*"));
+            let mut assembler = gsv::GsvAssembler::new();
+            for sentence in sentences.iter() {
+                assembler.push(parse(sentence, gsv::parse_gsv_message));
+            }
+            assert_eq!(assembler.pop(), Some(Ok(view)));
+            assert_eq!(gsv::SkyView::default().to_nmea(Talker::Gps), vec!["$GPGSV,1,1,00*79\r\n".to_string()]);
+        }
+
+        // The GNS, GST, GBS, GRS and DTM sentences below are receiver output printed in the
+        // NovAtel, Trimble and u-blox manuals, checksums as given.
+        #[test]
+        fn gns_round_trip() {
+            let gns = parse("$GNGNS,014035.00,4332.69262,S,17235.48549,E,RR,13,0.9,25.63,11.24,,*70", gns::parse_gns);
+            assert_eq!(gns.modes, vec![gns::Mode::RtkFixed, gns::Mode::RtkFixed]);
+            assert_eq!(gns.nav_status, None);
+            let sentence = gns.to_nmea(Talker::Multi);
+            assert!(sentence.starts_with("$GNGNS,014035.000,4332.692"));
+            assert!(sentence.contains(",E,RR,13,0.9,25.63,11.24,,*"));
+            assert_eq!(parse(&sentence, gns::parse_gns), gns);
+            let gns = parse("$GNGNS,103600.01,5114.51176,N,00012.29380,W,ANNN,07,1.18,111.5,45.6,,,V*00", gns::parse_gns);
+            assert_eq!(gns.nav_status, Some(gns::NavStatus::NotValid));
+            let sentence = gns.to_nmea(Talker::Multi);
+            assert!(sentence.contains(",W,ANNN,7,1.18,111.5,45.6,,,V*"));
+            assert_eq!(parse(&sentence, gns::parse_gns), gns);
+        }
+
+        #[test]
+        fn gst_round_trip() {
+            let gst = parse("$GPGST,172814.0,0.006,0.023,0.020,273.6,0.023,0.020,0.031*6A", gst::parse_gst);
+            assert_eq!(gst.rms, Some(0.006));
+            let sentence = gst.to_nmea(Talker::Gps);
+            assert!(sentence.starts_with("$GPGST,172814.000,0.006,0.023,0.02,273.6,0.023,0.02,0.031*"));
+            assert_eq!(parse(&sentence, gst::parse_gst), gst);
+        }
+
+        #[test]
+        fn gbs_round_trip() {
+            let gbs = parse("$GPGBS,015509.00,-0.031,-0.186,0.219,19,0.000,-0.354,6.972*4D", gbs::parse_gbs);
+            assert_eq!(gbs.failed_satellite, Some(19));
+            assert_eq!(gbs.system_id, None);
+            let sentence = gbs.to_nmea(Talker::Gps);
+            assert!(sentence.starts_with("$GPGBS,015509.000,-0.031,-0.186,0.219,19,0,-0.354,6.972*"));
+            assert_eq!(parse(&sentence, gbs::parse_gbs), gbs);
+            let ids = gbs::GbsData { system_id: Some(1), signal_id: Some(0xB), ..gbs };
+            let sentence = ids.to_nmea(Talker::Gps);
+            assert!(sentence.contains(",6.972,1,B*"));
+            assert_eq!(parse(&sentence, gbs::parse_gbs), ids);
+        }
+
+        #[test]
+        fn grs_round_trip() {
+            let grs = parse("$GPGRS,082632.00,1,0.54,0.83,1.00,1.02,-2.12,2.64,-0.71,-1.18,0.25,,,*70", grs::parse_grs);
+            assert_eq!(grs.mode, grs::Mode::Recomputed);
+            let sentence = grs.to_nmea(Talker::Gps);
+            assert!(sentence.starts_with("$GPGRS,082632.000,1,0.54,0.83,1,1.02,-2.12,2.64,-0.71,-1.18,0.25,,,*"));
+            assert_eq!(parse(&sentence, grs::parse_grs), grs);
+            let grs = parse("$GNGRS,104148.00,1,2.6,2.2,-1.6,-1.1,-1.7,-1.5,5.8,1.7,,,,,1,1*52", grs::parse_grs);
+            assert_eq!((grs.system_id, grs.signal_id), (Some(1), Some(1)));
+            let sentence = grs.to_nmea(Talker::Multi);
+            assert!(sentence.contains(",5.8,1.7,,,,,1,1*"));
+            assert_eq!(parse(&sentence, grs::parse_grs), grs);
+        }
+
+        #[test]
+        fn dtm_round_trip() {
+            let dtm = parse("$GPDTM,W84,,0.0,N,0.0,E,0.0,W84*6F", dtm::parse_dtm);
+            assert_eq!(dtm.local_datum, dtm::Datum::Wgs84);
+            assert_eq!(dtm.to_nmea(Talker::Gps), "$GPDTM,W84,,0,N,0,E,0,W84*71\r\n");
+            assert_eq!(parse(&dtm.to_nmea(Talker::Gps), dtm::parse_dtm), dtm);
+            let other = dtm::DtmData {
+                local_datum: dtm::Datum::Other("N27".to_string()),
+                subdivision: Some("A".to_string()),
+                lat_offset: Some(-0.08),
+                long_offset: Some(0.07),
+                alt_offset: Some(-47.7),
+                ..dtm::DtmData::default()
+            };
+            let sentence = other.to_nmea(Talker::Gps);
+            assert!(sentence.starts_with("$GPDTM,N27,A,0.08,S,0.07,E,-47.7,W84*"));
+            assert_eq!(parse(&sentence, dtm::parse_dtm), dtm::DtmData { talker: Talker::Gps, ..other });
+        }
+
+        #[test]
+        fn degrees_round_trip() {
+            // Every f32 that parsing can give reads back the same, even where the nearest ddmm.mmmm
+            // does not.
+            let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+            for _ in 0..100_000 {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                let ticks = state % 1_080_000_000;
+                let field = format!("{:03}{:02}.{:04}", ticks / 600_000, ticks % 600_000 / 10_000, ticks % 10_000);
+                let value = _parse_degrees(&field, "W").unwrap();
+                let [degrees, direction] = encode_degrees_field(value, 3, "E", "W");
+                assert_eq!(_parse_degrees(&degrees, &direction).unwrap(), value, "{}", field);
+            }
+            assert_eq!(encode_degrees_field(Some(-0.5), 2, "N", "S"), ["0030.0000".to_string(), "S".to_string()]);
+            assert_eq!(encode_degrees_field(None, 2, "N", "S"), [String::new(), String::new()]);
         }
     }
-    mod gsv {}
-    mod rmc {}
-    mod vtg {}
 }
@@ -1,31 +1,50 @@
-/// # Gps
-/// This module controls the opening and reading of the port to the gps.
-///
+//! # Gps
+//! This module controls the opening and reading of the port to the gps.
+//!
 
 
 pub mod gps {
     //! This is the main module around which all other modules interact.
     //! It contains the Gps structure, open port and GpsData that are central to using this module.
-    use std::fs::{File, OpenOptions};
+    use std::fs::File;
     use std::io::{Read, Write};
-    use std::str;
-    use std::time::{Duration, SystemTime};
+    use std::time::Duration;
 
-    use bincode::serialize;
     use serde::{Deserialize, Serialize};
     use serialport::prelude::*;
 
-    use crate::nmea::gga::{GgaData, parse_gga};
-    use crate::nmea::gll::{GllData, parse_gll};
-    use crate::nmea::gsa::{GsaData, parse_gsa};
-    use crate::nmea::gsv::{parse_gsv, Satellites};
-    use crate::nmea::parse_nmea::parse_sentence;
-    use crate::nmea::rmc::{parse_rmc, RmcData};
-    use crate::nmea::vtg::{parse_vtg, VtgData};
+    use crate::capture::raw::RawLog;
+    use crate::error::GpsError;
+    use crate::framing::check::{check_sentence, Verdict};
+    use crate::fix::aggregate::{Fix, FixAggregator};
+    use crate::framing::framer::Framer;
+    use crate::nmea::dtm::DtmData;
+    use crate::nmea::gbs::GbsData;
+    use crate::nmea::gga::GgaData;
+    use crate::nmea::gll::GllData;
+    use crate::nmea::gns::GnsData;
+    use crate::nmea::grs::GrsData;
+    use crate::nmea::gsa::GsaData;
+    use crate::nmea::gst::GstData;
+    use crate::nmea::gsv::{parse_gsv_message, GsvAssembler, GsvDiagnostic, GsvMessage, SkyView};
+    use crate::nmea::parse_nmea::{NmeaParseError, parse_sentence};
+    use crate::nmea::rmc::RmcData;
+    use crate::nmea::talker::Talker;
+    use crate::nmea::vtg::VtgData;
+    use crate::nmea::zda::ZdaData;
+    use crate::parser::registry::{split_address, Registry, SentenceParser};
+    use crate::pmtk::command::RetryPolicy;
+    use crate::proprietary::gtop::AntennaStatus;
+    use crate::proprietary::mtk::{ChannelStatus, SystemMessage, TextMessage};
+    use crate::record::log::{parse_log, read_legacy, LogError, LogWriter};
+
+    use super::port::{MemoryPort, ReadOnlyPort};
 
     /// Opens the port to the GPS, probably /dev/serial0
-        /// Default baud rate is 9600
-    pub fn open_port(port_name: &str, baud_rate: u32) -> Box<dyn SerialPort> {
+    /// Default baud rate is 9600
+    ///
+    /// Gives GpsError::PortOpen if the port is not found.
+    pub fn open_port(port_name: &str, baud_rate: u32) -> Result<Box<dyn SerialPort>, GpsError> {
         let settings = SerialPortSettings {
             baud_rate,
             data_bits: DataBits::Eight,
@@ -34,224 +53,686 @@ pub mod gps {
             stop_bits: StopBits::One,
             timeout: Duration::from_millis(1000),
         };
-        match serialport::open_with_settings(port_name, &settings) {
-            Ok(port) => return port,
-            Err(_e) => panic!("Port not found: {} - {}", port_name, _e),
-        }
+        serialport::open_with_settings(port_name, &settings)
+            .map_err(|e| GpsError::PortOpen(port_name.to_string(), e))
     }
 
     /// Checks if a sentence is a valid sentence by checksumming the sentence and comparing it to
     /// the given checksum. Returns true for valid sentence, false for invalid.
     /// The format of the sentence should be $sentence*checksum
+    ///
+    /// For why a sentence is not valid use [check_sentence](../../framing/check/fn.check_sentence.html).
     pub fn is_valid_checksum(s: &str) -> bool {
-        let s = s.trim();
-        // String should be: $..., *XY
-
-        let star = &s[s.len() - 3..s.len() - 2];
-        let checksum = &s[s.len() - 2..s.len()];
-        let body = &s[0..s.len() - 3];
-
-        if star != "*" {
-            // Check third last item is a *
-            return false;
-        }
-
-        match u8::from_str_radix(&checksum, 16) {
-            // Convert to base 16.
-            Ok(expected_checksum) => {
-                let mut actual: u8 = 0;
-                for i in body[1..].as_bytes() {
-                    // Skip $ sign. bitwise xor for each i in body
-                    actual ^= *i;
-                }
-                if actual == expected_checksum {
-                    return true;
-                } else {
-                    return false;
-                }
-            }
-            Err(_e) => return false,
-        }
-    }
-
-    /// Enum for if the port connection to the gps is valid, gave invalid bytes, or is not connected
-    #[derive(PartialEq, Debug)]
-    pub enum PortConnection {
-        Valid(String),
-        InvalidBytes(Vec<u8>),
-        NoConnection,
+        check_sentence(s.as_bytes()) == Verdict::Valid
     }
 
     /// Enum for the gps.update() method.
+    ///
+    /// ParseError is given when a sentence has a valid checksum but a field could not be parsed,
+    /// such as a truncated sentence.
+    ///
+    /// GSV is given once all the messages of a group are read, as a SkyView for each constellation.
+    /// A dropped group is given as a ParseError, see [GsvDiagnostic](../../nmea/gsv/enum.GsvDiagnostic.html).
+    ///
+    /// Other is given for a sentence with a valid checksum but no parser, see gps.register().
+    /// - talker -> GP, GN... or P for proprietary sentences.
+    /// - kind -> The rest of the address: GGA, or MTK869 for $PMTK869.
+    /// - fields -> The fields after the address, without the checksum.
     #[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
     pub enum GpsSentence {
         GGA(GgaData),
         VTG(VtgData),
         GSA(GsaData),
-        GSV(Vec<Satellites>),
+        GSV(SkyView),
         GLL(GllData),
         RMC(RmcData),
+        ZDA(ZdaData),
+        GNS(GnsData),
+        GST(GstData),
+        GBS(GbsData),
+        GRS(GrsData),
+        DTM(DtmData),
+        SystemMessage(SystemMessage),
+        TextMessage(TextMessage),
+        AntennaStatus(AntennaStatus),
+        ChannelStatus(ChannelStatus),
+        Other { talker: String, kind: String, fields: Vec<String> },
         NoConnection,
         InvalidBytes,
         InvalidSentence,
+        ParseError(NmeaParseError),
     }
 
     /// This is the main struct around which all commands are centered. It allows for communication
     /// with the GPS module via the open port.
     ///
-    /// Satellite data: true if you want the individual satellite data
-    /// Navigation data: true if you want the navigation data (lat, long, etc)
-    pub struct Gps {
-        pub port: Box<dyn SerialPort>,
+    /// The port can be anything that is Read + Write: the serial port to the gps (the default), a
+    /// file, a pipe, a pseudo-terminal or an in-memory buffer. Reading sentences and sending PMTK
+    /// commands work the same over all of them.
+    pub struct Gps<T: Read + Write = Box<dyn SerialPort>> {
+        pub port: T,
+        pub(crate) framer: Framer,
+        timeout: Duration,
+        parsers: Registry,
+        gsv: GsvAssembler,
+        capture: Option<RawLog>,
+        capture_error: Option<std::io::Error>,
+        pub(crate) fixes: FixAggregator,
+        pub(crate) retry: RetryPolicy,
     }
 
-    impl Gps {
-        pub fn new(port: &str, baud_rate: &str) -> Gps {
-            Gps { port: open_port(port, baud_rate.parse().unwrap()) }
+    impl Gps<Box<dyn SerialPort>> {
+        /// Opens the serial port to the gps at the given baud rate.
+        ///
+        /// Gives GpsError::BaudRate if the baud rate is not a number, or GpsError::PortOpen if the
+        /// port is not found.
+        pub fn new(port: &str, baud_rate: &str) -> Result<Gps<Box<dyn SerialPort>>, GpsError> {
+            let baud_rate: u32 = baud_rate.parse()
+                .map_err(|_e| GpsError::BaudRate(baud_rate.to_string()))?;
+            Ok(Gps::from_port(open_port(port, baud_rate)?))
+        }
+    }
+
+    impl Gps<ReadOnlyPort<File>> {
+        /// Reads sentences from a file, such as the output of `cat /dev/serial0 > file`.
+        ///
+        /// Commands sent to the gps are discarded.
+        pub fn from_file(path: &str) -> Result<Gps<ReadOnlyPort<File>>, GpsError> {
+            let file = File::open(path)?;
+            Ok(Gps::from_port(ReadOnlyPort::new(file)))
+        }
+    }
+
+    impl Gps<MemoryPort> {
+        /// Reads sentences from a byte slice. Commands sent to the gps are kept in port.output.
+        pub fn from_bytes(bytes: &[u8]) -> Gps<MemoryPort> {
+            Gps::from_port(MemoryPort::new(bytes))
+        }
+    }
+
+    impl<T: Read + Write> Gps<T> {
+        /// Use any Read + Write port to talk to the gps.
+        pub fn from_port(port: T) -> Gps<T> {
+            Gps {
+                port,
+                framer: Framer::new(),
+                timeout: Duration::from_secs(1),
+                parsers: Registry::new(),
+                gsv: GsvAssembler::new(),
+                capture: None,
+                capture_error: None,
+                fixes: FixAggregator::new(),
+                retry: RetryPolicy::default(),
+            }
+        }
+
+        /// Parse sentences with the given address, without the $, with parser. Replaces the parser
+        /// of a sentence this crate knows.
+        ///
+        /// The address can be a full address such as PGTOP or GPGGA, or a sentence type such as GGA
+        /// for any talker. See [Registry](../../parser/registry/struct.Registry.html).
+        pub fn register<P: SentenceParser + 'static>(&mut self, address: &str, parser: P) {
+            self.parsers.register(address, parser);
+        }
+
+        /// The parsers used by update(), to remove or replace them.
+        pub fn parsers_mut(&mut self) -> &mut Registry {
+            &mut self.parsers
+        }
+
+        /// How long read_line waits for a full sentence before giving GpsError::Timeout.
+        /// Default is 1 second.
+        pub fn set_timeout(&mut self, timeout: Duration) {
+            self.timeout = timeout;
+        }
+
+        /// Tee every line read from the gps, with the time it was received, to a .nmea log file.
+        /// The file is appended to. See [capture](../../capture/index.html) for the format.
+        pub fn capture(&mut self, path: &str) -> Result<(), GpsError> {
+            self.capture = Some(RawLog::open(path)?);
+            self.capture_error = None;
+            Ok(())
+        }
+
+        /// Tee every line read from the gps, with the time it was received, to any writer.
+        pub fn capture_to<W: Write + Send + 'static>(&mut self, writer: W) {
+            self.capture = Some(RawLog::new(writer));
+            self.capture_error = None;
+        }
+
+        /// Stops the capture started by capture() or capture_to().
+        pub fn stop_capture(&mut self) {
+            self.capture = None;
+        }
+
+        /// The error that stopped the capture, if a line could not be written to the log. Cleared
+        /// when a new capture is started.
+        pub fn capture_error(&self) -> Option<&std::io::Error> {
+            self.capture_error.as_ref()
         }
 
         /// Reads a full sentence from the serial buffer, returns a String.
-        /// "Invalid bytes given" when there are no bytes given.
-        pub fn read_line(&mut self) -> PortConnection {
-            // Maximum port buffer size is 4095.
-            // Returns whatever is in the port.
-            // Start of a line is $ (36) and end is \n (10).
-            // The serial buffer reads from bottom to top. New data is added to the top. The amount read
-            // from the serial buffer is the size of the buffer vec.
-
-            // 127 is the maximum valid utf8 number.
-            let mut buffer: Vec<u8> = vec![0; 1]; // Reads what is in the buffer, be it nothing or max.
-            let mut output: Vec<u8> = Vec::new();
-            let p = &mut self.port;
-            let mut cont = true;
-            let start = SystemTime::now();
-            while cont {
-                // If there is no connection, this match statement is looped over with a 1 second time out
-                // as given by the port open.
-                if start.elapsed().unwrap() > Duration::from_secs(1) {
-                    return PortConnection::NoConnection;
-                }
-                match p.read(buffer.as_mut_slice()) {
-                    Ok(buffer_size) => {
-                        output.extend_from_slice(&buffer[..buffer_size]);
+        ///
+        /// The port is read in bulk and any bytes after the sentence are kept for the next call.
+        /// When capturing, every line is logged as it was received, even if it is not valid.
+        ///
+        /// Errors:
+        /// - GpsError::Timeout when no full sentence is given before the timeout (see set_timeout),
+        ///   or the end of a file is reached.
+        /// - GpsError::InvalidBytes when the bytes given are not valid utf8.
+        /// - GpsError::Io when the port can no longer be read, such as when the gps is unplugged.
+        ///
+        /// A line that can not be written to the capture log is still given. The capture is stopped
+        /// and the error is kept for capture_error().
+        pub fn read_line(&mut self) -> Result<String, GpsError> {
+            self.read_line_within(self.timeout)
+        }
 
-                        if output.get(output.len() - 1).unwrap() == &10u8 || output.len() > 255 {
-                            cont = false;
-                        }
-                    }
-                    Err(_e) => (),
+        /// read_line, but waiting no longer than the given timeout.
+        pub(crate) fn read_line_within(&mut self, timeout: Duration) -> Result<String, GpsError> {
+            let output = self.framer.read_line(&mut self.port, timeout)?;
+            if let Some(capture) = self.capture.as_mut() {
+                if let Err(e) = capture.write_line(&output) {
+                    self.capture = None;
+                    self.capture_error = Some(e);
                 }
             }
-            let string = str::from_utf8(&output);
-            return if string.is_ok() {
-                PortConnection::Valid(string.unwrap().to_string())
-            } else {
-                PortConnection::InvalidBytes(output)
-            };
+            match String::from_utf8(output) {
+                Ok(string) => Ok(string),
+                Err(e) => Err(GpsError::InvalidBytes(e.into_bytes())),
+            }
         }
 
-        /// Keeps reading sentences until all the required sentences are read.
-        /// Returns GpsData.
+        /// Reads an MTK binary packet, for when the gps is in binary mode, waiting no longer than the
+        /// given timeout. Packets are not captured.
+        pub(crate) fn read_packet_within(&mut self, timeout: Duration) -> Result<Vec<u8>, GpsError> {
+            self.framer.read_packet(&mut self.port, timeout)
+        }
+
+        /// Reads the next sentence from the gps.
+        ///
+        /// GSV messages are held until their group is complete, so other sentences given between
+        /// them are returned first.
+        ///
+        /// GpsError::InvalidBytes is given as GpsSentence::InvalidBytes, and any other error as
+        /// GpsSentence::NoConnection. Use try_update() to tell a timeout from a port that can no
+        /// longer be read.
         pub fn update(&mut self) -> GpsSentence {
-            let port_output = self.read_line();
-
-            return match port_output {
-                PortConnection::NoConnection => GpsSentence::NoConnection,
-                PortConnection::InvalidBytes(_vector) => GpsSentence::InvalidBytes,
-                PortConnection::Valid(string) => {
-                    let sentence: Option<Vec<&str>> = parse_sentence(string.as_str());
-                    if sentence.is_some() {
-                        let sentence = sentence.unwrap();
-                        let header = sentence.get(0).unwrap();
-                        // At this point sentences needs to be is_valid str.
-                        if &header[3..5] == "GG" {
-                            return GpsSentence::GGA(parse_gga(sentence));
-                        } else if &header[3..6] == "VTG" {
-                            return GpsSentence::VTG(parse_vtg(sentence));
-                        } else if &header[3..6] == "GSA" {
-                            return GpsSentence::GSA(parse_gsa(sentence));
-                        } else if &header[3..6] == "GLL" {
-                            return GpsSentence::GLL(parse_gll(sentence));
-                        } else if &header[3..6] == "RMC" {
-                            return GpsSentence::RMC(parse_rmc(sentence));
-                        } else if &header[3..6] == "GSV" {
-                            // Assumes that each GSV sentence if given in exact sequence, and not out of order.
-                            let number_of_messages: i32 = sentence.get(1).unwrap().parse().unwrap();
-
-                            let mut gsv_values: Vec<Satellites> = parse_gsv(sentence); // First sentence
-                            for _message in 1..number_of_messages { // If number of messages is 1, this is all skipped.
-                                // Read lines and add it for each message.
-                                let line = self.read_line();
-                                match line {
-                                    PortConnection::Valid(line) => {
-                                        let sentence = parse_sentence(line.as_str());
-                                        let sentence = sentence.unwrap();
-                                        gsv_values.append(parse_gsv(sentence).as_mut())
-                                    }
-                                    _ => ()
-                                };
-                            }
-                            return GpsSentence::GSV(gsv_values);
+            match self.try_update() {
+                Ok(sentence) => sentence,
+                Err(GpsError::InvalidBytes(_vector)) => GpsSentence::InvalidBytes,
+                Err(_e) => GpsSentence::NoConnection,
+            }
+        }
+
+        /// Reads the next sentence from the gps, like update(), but gives the errors of read_line.
+        /// A line with a bad checksum is Ok(GpsSentence::InvalidSentence) and a bad field is
+        /// Ok(GpsSentence::ParseError), not GpsError::Checksum or GpsError::MalformedField.
+        ///
+        /// Errors:
+        /// - GpsError::Timeout when no full sentence is given before the timeout, or the end of a
+        ///   file is reached.
+        /// - GpsError::InvalidBytes when the bytes given are not valid utf8.
+        /// - GpsError::Io when the port can no longer be read, such as when the gps is unplugged.
+        pub fn try_update(&mut self) -> Result<GpsSentence, GpsError> {
+            loop {
+                if let Some(view) = self.gsv.pop() {
+                    return Ok(sky_view(view));
+                }
+                match parse_line(&self.read_line()?, &self.parsers) {
+                    Parsed::Sentence(sentence) => return Ok(sentence),
+                    Parsed::Gsv(message) => self.gsv.push(message),
+                }
+            }
+        }
+
+        /// Reads sentences until all the sentences for one fix are read, and merges them into a Fix.
+        ///
+        /// The sentences expected each fix are set by pmtk_314_api_set_nmea_output(), otherwise they
+        /// are learnt from the sentences given. Gives None when there is no connection, or at the end
+        /// of a file.
+        pub fn next_fix(&mut self) -> Option<Fix> {
+            loop {
+                match self.update() {
+                    GpsSentence::NoConnection => return self.fixes.flush(),
+                    sentence => {
+                        if let Some(fix) = self.fixes.push(sentence) {
+                            return Some(fix);
                         }
                     }
-                    GpsSentence::InvalidSentence
                 }
+            }
+        }
+
+    }
+
+    /// A line parsed by parse_line.
+    ///
+    /// GSV is given over multiple lines, so the reader gives each message to its GsvAssembler. This
+    /// is shared between the blocking and async readers.
+    pub(crate) enum Parsed {
+        Sentence(GpsSentence),
+        Gsv(GsvMessage),
+    }
+
+    /// Parses a line read from the gps with the parser registered for its address. GSV with no parser
+    /// registered is given to the GsvAssembler.
+    pub(crate) fn parse_line(line: &str, parsers: &Registry) -> Parsed {
+        let sentence = match parse_sentence(line) {
+            Some(sentence) => sentence,
+            None => return Parsed::Sentence(GpsSentence::InvalidSentence),
+        };
+        let address = &sentence[0][1..];
+        let gsv = matches!(split_address(address), Some((talker, "GSV")) if talker != "P");
+        if gsv && parsers.get(address).is_none() {
+            return match parse_gsv_message(sentence) {
+                Ok(message) => Parsed::Gsv(message),
+                Err(e) => Parsed::Sentence(GpsSentence::ParseError(e)),
             };
         }
+        Parsed::Sentence(parsers.parse(&sentence))
+    }
+
+    /// A complete or dropped GSV group from a GsvAssembler as a GpsSentence.
+    pub(crate) fn sky_view(view: Result<SkyView, GsvDiagnostic>) -> GpsSentence {
+        match view {
+            Ok(view) => GpsSentence::GSV(view),
+            Err(diagnostic) => GpsSentence::ParseError(NmeaParseError::from(diagnostic)),
+        }
     }
 
     // todo - ensure that appending is done by accident if the same program is run multiple times.
     // Some kind of init or new()? Make a new file first and then append?
     impl GpsSentence {
-        /// Reads a bytes file of structs to a vector.
+        /// True for a sentence with data, false for NoConnection, InvalidBytes, InvalidSentence and
+        /// ParseError.
+        pub fn is_valid(&self) -> bool {
+            !matches!(
+                self,
+                GpsSentence::NoConnection
+                    | GpsSentence::InvalidBytes
+                    | GpsSentence::InvalidSentence
+                    | GpsSentence::ParseError(_)
+            )
+        }
+
+        /// The talker that gave the sentence, such as Talker::Glonass for $GLGSA. None for
+        /// sentences without data and proprietary sentences.
+        pub fn talker(&self) -> Option<Talker> {
+            match self {
+                GpsSentence::GGA(data) => Some(data.talker),
+                GpsSentence::VTG(data) => Some(data.talker),
+                GpsSentence::GSA(data) => Some(data.talker),
+                GpsSentence::GSV(view) => Some(view.talker),
+                GpsSentence::GLL(data) => Some(data.talker),
+                GpsSentence::RMC(data) => Some(data.talker),
+                GpsSentence::ZDA(data) => Some(data.talker),
+                GpsSentence::GNS(data) => Some(data.talker),
+                GpsSentence::GST(data) => Some(data.talker),
+                GpsSentence::GBS(data) => Some(data.talker),
+                GpsSentence::GRS(data) => Some(data.talker),
+                GpsSentence::DTM(data) => Some(data.talker),
+                GpsSentence::Other { talker, .. } if talker != "P" => Some(Talker::from_address(talker)),
+                _ => None,
+            }
+        }
+
+        /// Reads a log file written by append_to() to a vector, skipping any corrupt records. Files
+        /// written by version 0.4.1, before the log header, are read as well.
         ///
-        /// Benches at 263,860ns to read a 1,000 long vec.
-        pub fn read_from(file: &str) -> Vec<GpsSentence> {
-            let mut f = File::open(file).expect("No file found");
+        /// Gives LogError::Unreadable if the file has bytes but not one sentence could be read from
+        /// it. Use [read_log](../../record/log/fn.read_log.html) for the receive times and where
+        /// corrupt records were skipped.
+        pub fn read_from(file: &str) -> Result<Vec<GpsSentence>, LogError> {
             let mut buffer = Vec::new();
-            let _ = f.read_to_end(&mut buffer);
-            let split = buffer.split(|num| num == &10);
-            let mut struct_vec: Vec<GpsSentence> = Vec::new();
-            for item in split {
-                match bincode::deserialize(item) {
-                    Ok(t) => {
-                        struct_vec.push(t)
-                    }
-                    _ => {}
-                }
+            File::open(file)?.read_to_end(&mut buffer)?;
+            let contents = match parse_log(&buffer) {
+                Err(LogError::NotALog) => read_legacy(&buffer),
+                contents => contents?,
+            };
+            if contents.records.is_empty() && !contents.errors.is_empty() {
+                return Err(LogError::Unreadable(contents.errors));
             }
-
-            return struct_vec;
+            Ok(contents.sentences())
         }
 
-        /// Append a GpsSentence struct to a file.
-        /// If you wish to write a vector of bytes, run it over an iterator and add each struct
-        /// individually. You must clone the struct that is being iterated over.
+        /// Append a GpsSentence struct to a log file, received now. The file is made, with its header,
+        /// if it does not exist.
+        /// If you wish to write a vector of sentences, run it over an iterator and add each struct
+        /// individually, or keep the file open with a LogWriter.
         /// ```
         /// use adafruit_gps::GpsSentence;
-        /// let v: Vec<GpsSentence> = vec![GpsSenence];
+        /// let v: Vec<GpsSentence> = vec![GpsSentence::NoConnection];
         /// for s in v.iter() {
-        ///     s.clone().append_to("vector");
+        ///     s.append_to("doc_vec_test").unwrap();
         /// }
-        /// let read: Vec<GpsSentence> = GpsSentence::read_from("vec_test");
+        /// let read: Vec<GpsSentence> = GpsSentence::read_from("doc_vec_test").unwrap();
+        /// let _ = std::fs::remove_file("doc_vec_test");
+        /// assert_eq!(read, v);
         /// ```
         ///
-        /// Benches at 55,000,000 ns (0.05 s) for a 1,000 long vector, both as append directly
-        /// or when iterating over a vector.
-        ///
-        /// Append with a \n (10) byte at the end so it can be read back into a vector.
-        pub fn append_to(self, file: &str) {
-            let mut f = OpenOptions::new().append(true).create(true).open(file).unwrap();
-            // has to open a file if none exist.
+        /// Gives LogError::NotALog if the file is not a log, such as one written by older versions.
+        /// Those can be rewritten with [migrate_legacy](../../record/log/fn.migrate_legacy.html).
+        pub fn append_to(&self, file: &str) -> Result<(), LogError> {
+            LogWriter::open(file)?.write(self)
+        }
+    }
+}
+
+pub mod port {
+    //! Ports other than the serial port that the Gps struct can read from and write to.
+    use std::io::{Cursor, Read, Result, Write};
+
+    /// In-memory port. Reads are taken from the bytes given, anything written to the port (such
+    /// as PMTK commands) is kept in output.
+    #[derive(Debug, Default)]
+    pub struct MemoryPort {
+        input: Cursor<Vec<u8>>,
+        pub output: Vec<u8>,
+    }
+
+    impl MemoryPort {
+        pub fn new(bytes: &[u8]) -> MemoryPort {
+            MemoryPort { input: Cursor::new(bytes.to_vec()), output: Vec::new() }
+        }
+    }
+
+    impl Read for MemoryPort {
+        fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+            self.input.read(buf)
+        }
+    }
+
+    impl Write for MemoryPort {
+        fn write(&mut self, buf: &[u8]) -> Result<usize> {
+            self.output.write(buf)
+        }
 
-            let _ = f.write(serialize(&self).unwrap().as_ref());
-            let breakline: [u8; 1] = [10];
-            let _ = f.write(&breakline);
+        fn flush(&mut self) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    /// Port that can only be read from, such as a file or a pipe. Anything written to it is
+    /// discarded.
+    #[derive(Debug)]
+    pub struct ReadOnlyPort<R: Read> {
+        inner: R,
+    }
+
+    impl<R: Read> ReadOnlyPort<R> {
+        pub fn new(inner: R) -> ReadOnlyPort<R> {
+            ReadOnlyPort { inner }
+        }
+    }
+
+    impl<R: Read> Read for ReadOnlyPort<R> {
+        fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+            self.inner.read(buf)
+        }
+    }
+
+    impl<R: Read> Write for ReadOnlyPort<R> {
+        fn write(&mut self, buf: &[u8]) -> Result<usize> {
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> Result<()> {
+            Ok(())
         }
     }
 }
 
+pub mod sentences {
+    //! Iterators over the sentences given by the gps, so ordinary iterator combinators can be used
+    //! instead of a loop around gps.update().
+    //!
+    //! ```no_run
+    //! use adafruit_gps::{Gps, SentenceIter};
+    //!
+    //! let mut gps = Gps::new("/dev/serial0", "9600").unwrap();
+    //! for gga in gps.sentences().stop_after(5).gga().take(10) {
+    //!     println!("{:?}", gga.lat);
+    //! }
+    //! ```
+    use std::io::{Read, Write};
+    use std::iter::{Filter, FilterMap};
+
+    use crate::nmea::dtm::DtmData;
+    use crate::nmea::gbs::GbsData;
+    use crate::nmea::gga::GgaData;
+    use crate::nmea::gll::GllData;
+    use crate::nmea::gns::GnsData;
+    use crate::nmea::grs::GrsData;
+    use crate::nmea::gsa::GsaData;
+    use crate::nmea::gst::GstData;
+    use crate::nmea::gsv::SkyView;
+    use crate::nmea::rmc::RmcData;
+    use crate::nmea::talker::Talker;
+    use crate::nmea::vtg::VtgData;
+    use crate::nmea::zda::ZdaData;
+    use crate::proprietary::gtop::AntennaStatus;
+    use crate::proprietary::mtk::{ChannelStatus, SystemMessage, TextMessage};
+
+    use super::gps::{Gps, GpsSentence};
+
+    /// # Stop policy
+    /// - Never -> Keep calling gps.update() forever. Default.
+    /// - AfterNoConnection(n) -> Stop after n NoConnection in a row. Use 1 for a file or bytes, which
+    ///   give NoConnection at the end.
+    #[derive(Debug, PartialEq, Clone, Copy, Default)]
+    pub enum StopPolicy {
+        #[default]
+        Never,
+        AfterNoConnection(u32),
+    }
+
+    /// Iterator of gps.update(), made by gps.sentences().
+    pub struct Sentences<'a, T: Read + Write> {
+        gps: &'a mut Gps<T>,
+        policy: StopPolicy,
+        no_connection: u32,
+    }
+
+    impl<T: Read + Write> Gps<T> {
+        /// Iterator of gps.update(). Never stops unless a StopPolicy is given with stop_after().
+        pub fn sentences(&mut self) -> Sentences<'_, T> {
+            Sentences { gps: self, policy: StopPolicy::Never, no_connection: 0 }
+        }
+    }
+
+    impl<'a, T: Read + Write> Sentences<'a, T> {
+        /// Stop after n NoConnection in a row. The NoConnection sentences are still given.
+        pub fn stop_after(self, n: u32) -> Sentences<'a, T> {
+            self.policy(StopPolicy::AfterNoConnection(n))
+        }
+
+        pub fn policy(mut self, policy: StopPolicy) -> Sentences<'a, T> {
+            self.policy = policy;
+            self
+        }
+    }
+
+    impl<'a, T: Read + Write> Iterator for Sentences<'a, T> {
+        type Item = GpsSentence;
+
+        fn next(&mut self) -> Option<GpsSentence> {
+            if let StopPolicy::AfterNoConnection(n) = self.policy {
+                if self.no_connection >= n {
+                    return None;
+                }
+            }
+            let sentence = self.gps.update();
+            if sentence == GpsSentence::NoConnection {
+                self.no_connection += 1;
+            } else {
+                self.no_connection = 0;
+            }
+            Some(sentence)
+        }
+    }
+
+    /// Iterator of only one sentence type, given by the SentenceIter typed adapters.
+    pub type Only<I, T> = FilterMap<I, fn(GpsSentence) -> Option<T>>;
+
+    /// Iterator of the sentences from some talkers, given by SentenceIter::talkers().
+    pub struct Talkers<I> {
+        iter: I,
+        talkers: Vec<Talker>,
+    }
+
+    impl<I: Iterator<Item = GpsSentence>> Iterator for Talkers<I> {
+        type Item = GpsSentence;
+
+        fn next(&mut self) -> Option<GpsSentence> {
+            loop {
+                let sentence = self.iter.next()?;
+                match sentence.talker() {
+                    Some(talker) if !self.talkers.contains(&talker) => continue,
+                    _ => return Some(sentence),
+                }
+            }
+        }
+    }
+
+    /// Adapters for any iterator of GpsSentence, such as gps.sentences() or GpsSentence::read_from().
+    ///
+    /// - valid() -> Only sentences with data, see GpsSentence::is_valid().
+    /// - talkers() -> Only sentences from the given talkers, such as only GLONASS. Sentences without
+    ///   a talker, such as NoConnection or proprietary sentences, are kept.
+    /// - gga(), vtg(), gsa(), gsv(), gll(), rmc(), zda(), gns(), gst(), gbs(), grs(), dtm() -> Only that
+    ///   sentence type, as its data struct.
+    /// - system_messages(), text_messages() -> Only $PMTK010 and $PMTK011 messages, such as to log
+    ///   restarts.
+    /// - antenna_status() -> Only $PGTOP antenna status, such as to alarm on antenna faults.
+    /// - channel_status() -> Only $PMTKCHN status of each receiver channel.
+    pub trait SentenceIter: Iterator<Item = GpsSentence> + Sized {
+        fn valid(self) -> Filter<Self, fn(&GpsSentence) -> bool> {
+            self.filter(GpsSentence::is_valid)
+        }
+
+        fn talkers(self, talkers: &[Talker]) -> Talkers<Self> {
+            Talkers { iter: self, talkers: talkers.to_vec() }
+        }
+
+        fn gga(self) -> Only<Self, GgaData> {
+            self.filter_map(|s| match s {
+                GpsSentence::GGA(data) => Some(data),
+                _ => None,
+            })
+        }
+
+        fn vtg(self) -> Only<Self, VtgData> {
+            self.filter_map(|s| match s {
+                GpsSentence::VTG(data) => Some(data),
+                _ => None,
+            })
+        }
+
+        fn gsa(self) -> Only<Self, GsaData> {
+            self.filter_map(|s| match s {
+                GpsSentence::GSA(data) => Some(data),
+                _ => None,
+            })
+        }
+
+        fn gsv(self) -> Only<Self, SkyView> {
+            self.filter_map(|s| match s {
+                GpsSentence::GSV(data) => Some(data),
+                _ => None,
+            })
+        }
+
+        fn gll(self) -> Only<Self, GllData> {
+            self.filter_map(|s| match s {
+                GpsSentence::GLL(data) => Some(data),
+                _ => None,
+            })
+        }
+
+        fn rmc(self) -> Only<Self, RmcData> {
+            self.filter_map(|s| match s {
+                GpsSentence::RMC(data) => Some(data),
+                _ => None,
+            })
+        }
+
+        fn zda(self) -> Only<Self, ZdaData> {
+            self.filter_map(|s| match s {
+                GpsSentence::ZDA(data) => Some(data),
+                _ => None,
+            })
+        }
+
+        fn gns(self) -> Only<Self, GnsData> {
+            self.filter_map(|s| match s {
+                GpsSentence::GNS(data) => Some(data),
+                _ => None,
+            })
+        }
+
+        fn gst(self) -> Only<Self, GstData> {
+            self.filter_map(|s| match s {
+                GpsSentence::GST(data) => Some(data),
+                _ => None,
+            })
+        }
+
+        fn gbs(self) -> Only<Self, GbsData> {
+            self.filter_map(|s| match s {
+                GpsSentence::GBS(data) => Some(data),
+                _ => None,
+            })
+        }
+
+        fn grs(self) -> Only<Self, GrsData> {
+            self.filter_map(|s| match s {
+                GpsSentence::GRS(data) => Some(data),
+                _ => None,
+            })
+        }
+
+        fn dtm(self) -> Only<Self, DtmData> {
+            self.filter_map(|s| match s {
+                GpsSentence::DTM(data) => Some(data),
+                _ => None,
+            })
+        }
+
+        fn system_messages(self) -> Only<Self, SystemMessage> {
+            self.filter_map(|s| match s {
+                GpsSentence::SystemMessage(message) => Some(message),
+                _ => None,
+            })
+        }
+
+        fn text_messages(self) -> Only<Self, TextMessage> {
+            self.filter_map(|s| match s {
+                GpsSentence::TextMessage(message) => Some(message),
+                _ => None,
+            })
+        }
+
+        fn antenna_status(self) -> Only<Self, AntennaStatus> {
+            self.filter_map(|s| match s {
+                GpsSentence::AntennaStatus(status) => Some(status),
+                _ => None,
+            })
+        }
+
+        fn channel_status(self) -> Only<Self, ChannelStatus> {
+            self.filter_map(|s| match s {
+                GpsSentence::ChannelStatus(status) => Some(status),
+                _ => None,
+            })
+        }
+    }
+
+    impl<I: Iterator<Item = GpsSentence>> SentenceIter for I {}
+}
+
 #[cfg(test)]
+#[allow(clippy::bool_assert_comparison)]
 mod gps_test {
     use super::gps;
 
@@ -282,19 +763,163 @@ mod gps_test {
             gps::is_valid_checksum("$GPGSA,A,3,29,02,26,25,31,14,,,,,,,1.42,1.17,0.80*A7\r\n"),
             false
         );
+        assert!(!gps::is_valid_checksum(""));
+        assert!(!gps::is_valid_checksum("*2F"));
+        assert!(!gps::is_valid_checksum("$PMTK220,1é0*2F"));
     }
 }
 
 
+#[cfg(test)]
+mod port_test {
+    use std::fs::{remove_file, File};
+    use std::io::Write;
+
+    use crate::error::GpsError;
+    use crate::nmea::talker::Talker;
+
+    use super::gps::{Gps, GpsSentence};
+
+    const GGA: &str = "$GNGGA,131613.000,5132.7314,N,00005.9099,W,1,9,1.17,42.4,M,47.0,M,,*60\r\n";
+
+    #[test]
+    fn read_from_bytes() {
+        let mut gps = Gps::from_bytes(GGA.as_bytes());
+        match gps.update() {
+            GpsSentence::GGA(sentence) => assert_eq!(sentence.satellites_used, 9),
+            other => panic!("Expected GGA, got {:?}", other),
+        }
+        assert!(matches!(gps.read_line(), Err(GpsError::Timeout)));
+    }
+
+    #[test]
+    fn read_from_file() {
+        let mut f = File::create("port_test_file").unwrap();
+        f.write_all(GGA.as_bytes()).unwrap();
+        let mut gps = Gps::from_file("port_test_file").unwrap();
+        let line = gps.read_line();
+        let _ = remove_file("port_test_file");
+        assert_eq!(line.unwrap(), GGA.to_string());
+    }
+
+    #[test]
+    fn gsv_groups_between_sentences() {
+        let bytes = [
+            "$GPGSV,2,1,05,12,12,100,30,04,11,331,,16,06,282,,05,05,074,22*72\r\n",
+            "$GLGSV,1,1,02,65,40,050,35,66,70,120,40*63\r\n",
+            GGA,
+            "$GPGSV,2,2,05,32,01,215,*4A\r\n",
+        ].concat();
+        let mut gps = Gps::from_bytes(bytes.as_bytes());
+        match gps.update() {
+            GpsSentence::GSV(view) => assert_eq!((view.talker, view.satellites.len()), (Talker::Glonass, 2)),
+            other => panic!("Expected GLONASS GSV, got {:?}", other),
+        }
+        assert!(matches!(gps.update(), GpsSentence::GGA(_)));
+        match gps.update() {
+            GpsSentence::GSV(view) => assert_eq!((view.talker, view.in_view, view.satellites.len()), (Talker::Gps, 5, 5)),
+            other => panic!("Expected GPS GSV, got {:?}", other),
+        }
+        assert_eq!(gps.update(), GpsSentence::NoConnection);
+    }
+
+    #[test]
+    fn commands_written_to_port() {
+        let mut gps = Gps::from_bytes(b"$PMTK001,220,3*30\r\n");
+        gps.send_command("PMTK220,1000");
+        assert_eq!(gps.port.output, b"$PMTK220,1000*1F\r\n".to_vec());
+    }
+}
+
+#[cfg(test)]
+mod error_test {
+    use std::io::{Error, ErrorKind, Read, Result, Write};
+
+    use crate::error::GpsError;
+    use crate::nmea::parse_nmea::{NmeaParseError, ParseErrorReason};
+
+    use super::gps::{Gps, GpsSentence, open_port};
+
+    /// Port that acts like an unplugged usb adapter.
+    struct UnpluggedPort;
+
+    impl Read for UnpluggedPort {
+        fn read(&mut self, _buf: &mut [u8]) -> Result<usize> {
+            Err(Error::new(ErrorKind::BrokenPipe, "unplugged"))
+        }
+    }
+
+    impl Write for UnpluggedPort {
+        fn write(&mut self, _buf: &[u8]) -> Result<usize> {
+            Err(Error::new(ErrorKind::BrokenPipe, "unplugged"))
+        }
+
+        fn flush(&mut self) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn bad_baud_rate() {
+        assert!(matches!(Gps::new("/dev/serial0", "96OO"), Err(GpsError::BaudRate(_))));
+    }
+
+    #[test]
+    fn port_not_found() {
+        assert!(matches!(open_port("/dev/no_such_gps", 9600), Err(GpsError::PortOpen(_, _))));
+    }
+
+    #[test]
+    fn unplugged_port() {
+        let mut gps = Gps::from_port(UnpluggedPort);
+        assert!(matches!(gps.read_line(), Err(GpsError::Io(_))));
+        assert_eq!(gps.update(), GpsSentence::NoConnection);
+    }
+
+    #[test]
+    fn try_update_errors() {
+        let mut gps = Gps::from_port(UnpluggedPort);
+        assert!(matches!(gps.try_update(), Err(GpsError::Io(_))));
+        let mut gps = Gps::from_bytes(b"$GPGSA,A,3,29,02,26,25,31,14,,,,,,,1.42,1.17,0.80*07\r\n");
+        assert!(matches!(gps.try_update(), Ok(GpsSentence::GSA(_))));
+        assert!(matches!(gps.try_update(), Err(GpsError::Timeout)));
+    }
+
+    #[test]
+    fn try_update_bad_lines_are_sentences() {
+        let mut gps = Gps::from_bytes(b"$GPGGA,bad*00\r\n$GPGGA,131613.000,5132.7314,N,00005.9099,W,1*5F\r\n");
+        assert!(matches!(gps.try_update(), Ok(GpsSentence::InvalidSentence)));
+        assert!(matches!(gps.try_update(), Ok(GpsSentence::ParseError(_))));
+    }
+
+    #[test]
+    fn truncated_sentence() {
+        // Valid checksum, but the GGA is cut off after the fix quality.
+        let mut gps = Gps::from_bytes(b"$GPGGA,131613.000,5132.7314,N,00005.9099,W,1*5F\r\n");
+        assert_eq!(
+            gps.update(),
+            GpsSentence::ParseError(NmeaParseError::new("GGA", 7, ParseErrorReason::Missing))
+        );
+    }
+
+    #[test]
+    fn invalid_bytes() {
+        let mut gps = Gps::from_bytes(&[36, 255, 254, 10]);
+        assert!(matches!(gps.read_line(), Err(GpsError::InvalidBytes(_))));
+    }
+}
+
 #[cfg(test)]
 mod test_read_write {
     use std::fs::remove_file;
 
     use crate::GpsSentence;
     use crate::nmea::gga::{GgaData, SatFix};
+    use crate::nmea::talker::Talker;
+    use crate::time::utc::UtcTime;
 
     const SENTENCE: GpsSentence = GpsSentence::GGA(GgaData {
-        utc: 100.0,
+        utc: Some(UtcTime { hour: 0, minute: 1, second: 0, millisecond: 0 }),
         lat: Some(51.55465),
         long: Some(-0.05632),
         sat_fix: SatFix::DgpsFix,
@@ -303,12 +928,13 @@ mod test_read_write {
         msl_alt: Some(42.53),
         geoidal_sep: Some(47.0),
         age_diff_corr: None,
+        talker: Talker::Gps,
     });
 
     #[test]
     fn read_write_single() {
-        SENTENCE.append_to("single_test");
-        let read = GpsSentence::read_from("single_test");
+        SENTENCE.append_to("single_test").unwrap();
+        let read = GpsSentence::read_from("single_test").unwrap();
         let _ = remove_file("single_test");
         assert_eq!(read, vec![SENTENCE]);
     }
@@ -317,9 +943,9 @@ mod test_read_write {
     fn read_write_vec() {
         let v: Vec<GpsSentence> = vec![SENTENCE];
         for s in v.iter() {
-            s.clone().append_to("vec_test");
+            s.append_to("vec_test").unwrap();
         }
-        let read: Vec<GpsSentence> = GpsSentence::read_from("vec_test");
+        let read: Vec<GpsSentence> = GpsSentence::read_from("vec_test").unwrap();
         let _ = remove_file("vec_test");
         assert_eq!(v, read);
     }
@@ -328,12 +954,109 @@ mod test_read_write {
     fn read_and_write_loop() {
         let mut check_vec = Vec::new();
         for _ in 0..3 {
-            SENTENCE.append_to("loop_test");
+            SENTENCE.append_to("loop_test").unwrap();
             check_vec.push(SENTENCE)
         }
 
-        let read: Vec<GpsSentence> = GpsSentence::read_from("loop_test");
+        let read: Vec<GpsSentence> = GpsSentence::read_from("loop_test").unwrap();
         let _ = remove_file("loop_test");
         assert_eq!(read, check_vec);
     }
 }
+
+
+#[cfg(test)]
+mod sentences_test {
+    use crate::nmea::talker::Talker;
+
+    use super::gps::{Gps, GpsSentence};
+    use super::sentences::{SentenceIter, StopPolicy};
+
+    const BYTES: &[u8] = b"$GNGGA,131613.000,5132.7314,N,00005.9099,W,1,9,1.17,42.4,M,47.0,M,,*60\r\n\
+$GPGSA,A,3,29,02,26,25,31,14,,,,,,,1.42,1.17,0.80*07\r\n\
+$GPGGA,bad*00\r\n\
+$GNRMC,131613.000,A,5132.7314,N,00005.9099,W,0.11,0.00,250520,,,A*6D\r\n\
+$GNGGA,131614.000,5132.7314,N,00005.9099,W,1,8,1.17,42.4,M,47.0,M,,*66\r\n";
+
+    #[test]
+    fn stop_after_no_connection() {
+        let mut gps = Gps::from_bytes(BYTES);
+        let sentences: Vec<GpsSentence> = gps.sentences().stop_after(2).collect();
+        assert_eq!(sentences.len(), 7);
+        assert_eq!(sentences[2], GpsSentence::InvalidSentence);
+        assert_eq!(sentences[5..], [GpsSentence::NoConnection, GpsSentence::NoConnection]);
+    }
+
+    #[test]
+    fn never_stop() {
+        let mut gps = Gps::from_bytes(BYTES);
+        let sentences = gps.sentences().policy(StopPolicy::Never).take(20).count();
+        assert_eq!(sentences, 20);
+    }
+
+    #[test]
+    fn valid_only() {
+        let mut gps = Gps::from_bytes(BYTES);
+        assert_eq!(gps.sentences().stop_after(1).valid().count(), 4);
+    }
+
+    #[test]
+    fn typed() {
+        let mut gps = Gps::from_bytes(BYTES);
+        let satellites: Vec<i32> = gps.sentences().stop_after(1).gga().map(|gga| gga.satellites_used).collect();
+        assert_eq!(satellites, vec![9, 8]);
+
+        let mut gps = Gps::from_bytes(BYTES);
+        assert_eq!(gps.sentences().stop_after(1).rmc().count(), 1);
+        let mut gps = Gps::from_bytes(BYTES);
+        assert_eq!(gps.sentences().stop_after(1).gsa().count(), 1);
+    }
+
+    #[test]
+    fn gsa_for_each_constellation() {
+        let bytes = b"$GPGSA,A,3,29,02,26,25,31,14,,,,,,,1.42,1.17,0.80*07\r\n\
+$GLGSA,A,3,65,66,74,75,,,,,,,,,1.42,1.17,0.80*14\r\n\
+$PMTK010,002*2D\r\n";
+        let mut gps = Gps::from_bytes(bytes);
+        let talkers: Vec<Option<Talker>> = gps.sentences().stop_after(1).valid().map(|s| s.talker()).collect();
+        assert_eq!(talkers, vec![Some(Talker::Gps), Some(Talker::Glonass), None]);
+
+        let mut gps = Gps::from_bytes(bytes);
+        let glonass: Vec<GpsSentence> = gps.sentences().stop_after(1).valid().talkers(&[Talker::Glonass]).collect();
+        assert_eq!(glonass.len(), 2);
+        match &glonass[0] {
+            GpsSentence::GSA(gsa) => assert_eq!(gsa.sat1, Some(65)),
+            other => panic!("Expected GSA, got {:?}", other),
+        }
+        assert!(matches!(glonass[1], GpsSentence::SystemMessage(_)));
+    }
+
+    #[test]
+    fn integrity_sentences() {
+        let bytes = b"$GNGNS,131613.000,5132.7314,N,00005.9099,W,AA,14,1.17,42.4,47.0,,*76\r\n\
+$GPGST,172814.0,0.006,0.023,0.020,273.6,0.023,0.020,0.031*6A\r\n\
+$GPGBS,235458.00,1.4,1.3,3.1,03,,-21.4,3.8,1,0*5A\r\n\
+$GPGRS,220320.0,0,-0.8,-0.2,-0.1,-0.2,0.8,0.6,,,,,,*79\r\n\
+$GPDTM,999,,0.08,N,0.07,E,-47.7,W84*1B\r\n";
+        let mut gps = Gps::from_bytes(bytes);
+        assert_eq!(gps.sentences().stop_after(1).valid().count(), 5);
+        let mut gps = Gps::from_bytes(bytes);
+        assert_eq!(gps.sentences().stop_after(1).gns().next().map(|gns| gns.satellites_used), Some(14));
+        let mut gps = Gps::from_bytes(bytes);
+        assert_eq!(gps.sentences().stop_after(1).gst().next().and_then(|gst| gst.alt_error), Some(0.031));
+        let mut gps = Gps::from_bytes(bytes);
+        assert_eq!(gps.sentences().stop_after(1).gbs().next().and_then(|gbs| gbs.failed_satellite), Some(3));
+        let mut gps = Gps::from_bytes(bytes);
+        assert_eq!(gps.sentences().stop_after(1).grs().next().map(|grs| grs.residuals.len()), Some(12));
+        let mut gps = Gps::from_bytes(bytes);
+        assert_eq!(gps.sentences().stop_after(1).dtm().next().and_then(|dtm| dtm.alt_offset), Some(-47.7));
+    }
+
+    #[test]
+    fn zda() {
+        let mut gps = Gps::from_bytes(b"$GPZDA,172809.456,12,07,1996,00,00*57\r\n");
+        let zda = gps.sentences().stop_after(1).zda().next().unwrap();
+        assert_eq!(zda.datetime().map(|datetime| datetime.to_string()), Some("1996-07-12T17:28:09.456Z".to_string()));
+        assert_eq!(zda.local_offset_minutes(), Some(0));
+    }
+}
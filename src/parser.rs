@@ -0,0 +1,261 @@
+//! # Sentence parsers
+//! Each sentence read from the gps is given to the parser registered for its address field, so
+//! sentences this crate does not know, such as proprietary ones, can be parsed without forking.
+
+pub mod registry {
+    //! The SentenceParser trait and the Registry of parsers, keyed by address field.
+    use std::collections::HashMap;
+
+    use crate::nmea::dtm::parse_dtm;
+    use crate::nmea::gbs::parse_gbs;
+    use crate::nmea::gga::parse_gga;
+    use crate::nmea::gll::parse_gll;
+    use crate::nmea::gns::parse_gns;
+    use crate::nmea::grs::parse_grs;
+    use crate::nmea::gsa::parse_gsa;
+    use crate::nmea::gst::parse_gst;
+    use crate::nmea::parse_nmea::NmeaParseError;
+    use crate::nmea::rmc::parse_rmc;
+    use crate::nmea::vtg::parse_vtg;
+    use crate::nmea::zda::parse_zda;
+    use crate::open_gps::gps::GpsSentence;
+    use crate::proprietary::gtop::parse_pgtop;
+    use crate::proprietary::mtk::{parse_pmtk010, parse_pmtk011, parse_pmtkchn};
+
+    /// Parses one sentence.
+    ///
+    /// fields are the sentence split on ',' without the checksum, so `fields[0]` is the address such as
+    /// $GPGGA or $PGTOP. The checksum has already been checked.
+    ///
+    /// Any `Fn(&[&str]) -> Result<GpsSentence, NmeaParseError>` is a SentenceParser.
+    pub trait SentenceParser: Send + Sync {
+        fn parse(&self, fields: &[&str]) -> Result<GpsSentence, NmeaParseError>;
+    }
+
+    impl<F> SentenceParser for F
+        where F: Fn(&[&str]) -> Result<GpsSentence, NmeaParseError> + Send + Sync {
+        fn parse(&self, fields: &[&str]) -> Result<GpsSentence, NmeaParseError> {
+            self(fields)
+        }
+    }
+
+    type Builtin = fn(&[&str]) -> Result<GpsSentence, NmeaParseError>;
+
+    /// The parsers for the sentences known by this crate, keyed by sentence type, or by full address
+    /// for proprietary sentences.
+    const BUILTIN: [(&str, Builtin); 15] = [
+        ("GGA", |fields| parse_gga(fields.to_vec()).map(GpsSentence::GGA)),
+        ("VTG", |fields| parse_vtg(fields.to_vec()).map(GpsSentence::VTG)),
+        ("GSA", |fields| parse_gsa(fields.to_vec()).map(GpsSentence::GSA)),
+        ("GLL", |fields| parse_gll(fields.to_vec()).map(GpsSentence::GLL)),
+        ("RMC", |fields| parse_rmc(fields.to_vec()).map(GpsSentence::RMC)),
+        ("ZDA", |fields| parse_zda(fields.to_vec()).map(GpsSentence::ZDA)),
+        ("GNS", |fields| parse_gns(fields.to_vec()).map(GpsSentence::GNS)),
+        ("GST", |fields| parse_gst(fields.to_vec()).map(GpsSentence::GST)),
+        ("GBS", |fields| parse_gbs(fields.to_vec()).map(GpsSentence::GBS)),
+        ("GRS", |fields| parse_grs(fields.to_vec()).map(GpsSentence::GRS)),
+        ("DTM", |fields| parse_dtm(fields.to_vec()).map(GpsSentence::DTM)),
+        ("PMTK010", |fields| parse_pmtk010(fields.to_vec()).map(GpsSentence::SystemMessage)),
+        ("PMTK011", |fields| parse_pmtk011(fields.to_vec()).map(GpsSentence::TextMessage)),
+        ("PMTKCHN", |fields| parse_pmtkchn(fields.to_vec()).map(GpsSentence::ChannelStatus)),
+        ("PGTOP", |fields| parse_pgtop(fields.to_vec()).map(GpsSentence::AntennaStatus)),
+    ];
+
+    /// Splits an address into talker and sentence type.
+    ///
+    /// - GPGGA -> ("GP", "GGA")
+    /// - PMTK001 -> ("P", "MTK001"): proprietary sentences start with P.
+    ///
+    /// None if the address is not ascii letters and digits, or is too short.
+    pub fn split_address(address: &str) -> Option<(&str, &str)> {
+        if !address.bytes().all(|b| b.is_ascii_alphanumeric()) {
+            return None;
+        }
+        let split = if address.starts_with('P') { 1 } else { 2 };
+        if address.len() <= split {
+            return None;
+        }
+        Some(address.split_at(split))
+    }
+
+    /// # Registry
+    /// The parsers used by gps.update(), keyed by address field.
+    ///
+    /// A parser is found by the full address first (GPGGA, PGTOP, PMTK010), then by the sentence
+    /// type for non-proprietary sentences (GGA for any talker). Sentences with no parser are given as
+    /// GpsSentence::Other.
+    ///
+    /// GSV has no parser here, as one GSV is given over several sentences: the gps assembles each
+    /// group into a SkyView. A parser registered for GSV, or a full address such as GPGSV, is used
+    /// instead.
+    pub struct Registry {
+        parsers: HashMap<String, Box<dyn SentenceParser>>,
+    }
+
+    impl Default for Registry {
+        fn default() -> Registry {
+            Registry::new()
+        }
+    }
+
+    impl Registry {
+        /// Registry with the parsers for all the sentences this crate knows.
+        pub fn new() -> Registry {
+            let mut registry = Registry::empty();
+            for (address, parser) in BUILTIN.iter() {
+                registry.register(address, *parser);
+            }
+            registry
+        }
+
+        /// Registry with no parsers: every sentence is given as GpsSentence::Other.
+        pub fn empty() -> Registry {
+            Registry { parsers: HashMap::new() }
+        }
+
+        /// Use the parser for sentences with the given address, without the $. Replaces any parser
+        /// already registered for that address.
+        pub fn register<P: SentenceParser + 'static>(&mut self, address: &str, parser: P) {
+            self.parsers.insert(address.to_string(), Box::new(parser));
+        }
+
+        /// Removes the parser for the address. Those sentences are then given as GpsSentence::Other.
+        pub fn remove(&mut self, address: &str) {
+            self.parsers.remove(address);
+        }
+
+        /// The parser used for the address, without the $.
+        pub fn get(&self, address: &str) -> Option<&dyn SentenceParser> {
+            let (talker, kind) = split_address(address)?;
+            let parser = match self.parsers.get(address) {
+                Some(parser) => parser,
+                None if talker != "P" => self.parsers.get(kind)?,
+                None => return None,
+            };
+            Some(parser.as_ref())
+        }
+
+        /// Parses a sentence split on ',' with the checksum removed.
+        ///
+        /// Gives InvalidSentence if the address is not valid, ParseError if the parser fails and
+        /// Other if there is no parser for the address.
+        pub fn parse(&self, fields: &[&str]) -> GpsSentence {
+            let address = fields.first()
+                .and_then(|field| field.strip_prefix('$').or_else(|| field.strip_prefix('!')))
+                .unwrap_or("");
+            let (talker, kind) = match split_address(address) {
+                Some(split) => split,
+                None => return GpsSentence::InvalidSentence,
+            };
+            match self.get(address) {
+                Some(parser) => parser.parse(fields).unwrap_or_else(GpsSentence::ParseError),
+                None => GpsSentence::Other {
+                    talker: talker.to_string(),
+                    kind: kind.to_string(),
+                    fields: fields[1..].iter().map(|field| field.to_string()).collect(),
+                },
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod registry_test {
+    use crate::nmea::parse_nmea::{NmeaParseError, ParseErrorReason};
+    use crate::open_gps::gps::{Gps, GpsSentence};
+
+    use super::registry::{split_address, Registry};
+
+    const GGA: &str = "$GNGGA,131613.000,5132.7314,N,00005.9099,W,1,9,1.17,42.4,M,47.0,M,,*60\r\n";
+
+    #[test]
+    fn addresses() {
+        assert_eq!(split_address("GPGGA"), Some(("GP", "GGA")));
+        assert_eq!(split_address("PMTK001"), Some(("P", "MTK001")));
+        assert_eq!(split_address("PGTOP"), Some(("P", "GTOP")));
+        assert_eq!(split_address("GP"), None);
+        assert_eq!(split_address("P"), None);
+        assert_eq!(split_address("GP GGA"), None);
+    }
+
+    #[test]
+    fn unknown_is_other() {
+        let mut gps = Gps::from_bytes(b"$PSRF150,1*3E\r\n$PMTK869,2,1*36\r\n");
+        assert_eq!(gps.update(), GpsSentence::Other {
+            talker: "P".to_string(),
+            kind: "SRF150".to_string(),
+            fields: vec!["1".to_string()],
+        });
+        match gps.update() {
+            GpsSentence::Other { talker, kind, fields } => {
+                assert_eq!((talker.as_str(), kind.as_str()), ("P", "MTK869"));
+                assert_eq!(fields, vec!["2".to_string(), "1".to_string()]);
+            }
+            other => panic!("Expected Other, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn register_proprietary() {
+        let mut gps = Gps::from_bytes(b"$PGTOP,11,3*6F\r\n$PGTOP,11,x*24\r\n");
+        gps.register("PGTOP", |fields: &[&str]| match fields.get(2) {
+            Some(&"3") => Ok(GpsSentence::InvalidBytes),
+            _ => Err(NmeaParseError::new("PGTOP", 2, ParseErrorReason::Missing)),
+        });
+        assert_eq!(gps.update(), GpsSentence::InvalidBytes);
+        assert_eq!(gps.update(), GpsSentence::ParseError(NmeaParseError::new("PGTOP", 2, ParseErrorReason::Missing)));
+    }
+
+    #[test]
+    fn full_address_before_type() {
+        let mut registry = Registry::new();
+        registry.register("GNGGA", |_fields: &[&str]| Ok(GpsSentence::NoConnection));
+        assert!(registry.get("GNGGA").is_some());
+        let gpgga = ["$GPGGA", "131613.000", "5132.7314", "N", "00005.9099", "W", "1", "9", "1.17", "42.4", "M",
+            "47.0", "M", ""];
+        assert!(matches!(registry.parse(&gpgga), GpsSentence::GGA(_)));
+        let mut gps = Gps::from_bytes(GGA.as_bytes());
+        gps.register("GNGGA", |_fields: &[&str]| Ok(GpsSentence::NoConnection));
+        assert_eq!(gps.update(), GpsSentence::NoConnection);
+    }
+
+    #[test]
+    fn register_gsv() {
+        let gsv = "$GPGSV,1,1,01,12,12,100,30*4A\r\n";
+        let mut gps = Gps::from_bytes(gsv.as_bytes());
+        assert!(matches!(gps.update(), GpsSentence::GSV(_)));
+        let mut gps = Gps::from_bytes(gsv.as_bytes());
+        gps.register("GSV", |_fields: &[&str]| Ok(GpsSentence::NoConnection));
+        assert_eq!(gps.update(), GpsSentence::NoConnection);
+        let mut gps = Gps::from_bytes(gsv.as_bytes());
+        gps.register("GPGSV", |_fields: &[&str]| Ok(GpsSentence::InvalidBytes));
+        assert_eq!(gps.update(), GpsSentence::InvalidBytes);
+    }
+
+    #[test]
+    fn proprietary_gsv_is_other() {
+        let mut gps = Gps::from_bytes(b"$PAGSV,1,1*53\r\n");
+        assert_eq!(gps.update(), GpsSentence::Other {
+            talker: "P".to_string(),
+            kind: "AGSV".to_string(),
+            fields: vec!["1".to_string(), "1".to_string()],
+        });
+    }
+
+    #[test]
+    fn remove_builtin() {
+        let mut registry = Registry::new();
+        registry.remove("GGA");
+        assert!(registry.get("GPGGA").is_none());
+        assert!(matches!(registry.parse(&["$GPGGA", "131613.000"]), GpsSentence::Other { .. }));
+        assert!(matches!(Registry::empty().parse(&["$GPRMC"]), GpsSentence::Other { .. }));
+    }
+
+    #[test]
+    fn bad_address() {
+        let registry = Registry::new();
+        assert_eq!(registry.parse(&["$G"]), GpsSentence::InvalidSentence);
+        assert_eq!(registry.parse(&["$GP-GA", "1"]), GpsSentence::InvalidSentence);
+        assert_eq!(registry.parse(&[]), GpsSentence::InvalidSentence);
+    }
+}
@@ -18,16 +18,29 @@
 //! ## PMTK return formats
 //! Depending on the command given, the return values change.
 //!
+//! gps.execute(&PmtkCommand) sends any command and gives the PmtkResponse that answers it, see the
+//! [command module](command/index.html). The pmtk_ methods use it.
+//!
 
 pub mod send_pmtk {
     //! Contains all the pmtk commands that can be sent.
+    use std::io::{Read, Write};
     use std::str;
+    use std::thread::sleep;
+    use std::time::{Duration, Instant};
 
-    use serialport::{self, ClearBuffer};
+    use serialport::ClearBuffer;
 
-    use super::super::open_gps::gps::{Gps,GpsSentence, is_valid_checksum, open_port, PortConnection};
+    use crate::error::GpsError;
+    use crate::framing::check::checksum;
+    use crate::locus::logger::{parse_pmtklox, LocusDump, LocusStatus, LoxLine};
+    use crate::nmea::parse_nmea::parse_sentence;
+    use crate::proprietary::mtk::SystemMessage;
 
-    #[derive(Debug, PartialEq)]
+    use super::command::{parse_pmtk_response, PmtkCommand, PmtkReply, PmtkResponse, RetryPolicy};
+    use super::super::open_gps::gps::{Gps,GpsSentence, is_valid_checksum, open_port};
+
+    #[derive(Debug, PartialEq, Clone, Copy)]
     /// # PMTK001 return values
     ///
     /// - Invalid (No such command)
@@ -48,7 +61,8 @@ pub mod send_pmtk {
         NoPacket,
     }
 
-    #[derive(Debug, PartialEq)]
+    #[derive(Debug, PartialEq, Clone, Copy)]
+    #[allow(clippy::upper_case_acronyms)]
     /// Dgps (Differential GPS) mode is the usage of ground stations to aid in the accuracy of position.
     /// - NoDGPS: Default
     /// - RTCM
@@ -60,7 +74,7 @@ pub mod send_pmtk {
         Unknown,
     }
 
-    #[derive(Debug, PartialEq)]
+    #[derive(Debug, PartialEq, Clone, Copy)]
     /// SBAS (Satellite-based augmentation systems) uses ground stations broadcasting
     /// satellite messages to aid in navigation and accuracy.
     pub enum Sbas {
@@ -69,14 +83,14 @@ pub mod send_pmtk {
         Unknown,
     }
 
-    #[derive(Debug, PartialEq)]
+    #[derive(Debug, PartialEq, Clone, Copy)]
     pub enum SbasMode {
         Testing,
         Integrity,
         Unknown,
     }
 
-    #[derive(Debug, PartialEq)]
+    #[derive(Debug, PartialEq, Clone, Copy)]
     pub struct NmeaOutput {
         pub gll: i8,
         pub rmc: i8,
@@ -84,33 +98,57 @@ pub mod send_pmtk {
         pub gga: i8,
         pub gsa: i8,
         pub gsv: i8,
+        pub zda: i8,
         pub pmtkchn_interval: i8,
     }
 
-    #[derive(Debug, PartialEq)]
+    /// EPO data status, see pmtk_607_q_epo_info. Times of week are up to 604,800 seconds.
+    #[derive(Debug, PartialEq, Clone, Copy)]
     pub struct EpoData {
-        pub set: i8,
-        pub fwn_ftow_week_number: i8,
-        pub fwn_ftow_tow: i8,
-        pub lwn_ltow_week_number: i8,
-        pub lwn_ltow_tow: i8,
-        pub fcwn_fctow_week_number: i8,
-        pub fcwn_fctow_tow: i8,
-        pub lcwn_lctow_week_number: i8,
-        pub lcwn_lctow_tow: i8,
+        pub set: i32,
+        pub fwn_ftow_week_number: i32,
+        pub fwn_ftow_tow: i32,
+        pub lwn_ltow_week_number: i32,
+        pub lwn_ltow_tow: i32,
+        pub fcwn_fctow_week_number: i32,
+        pub fcwn_fctow_tow: i32,
+        pub lcwn_lctow_week_number: i32,
+        pub lcwn_lctow_tow: i32,
     }
 
     /// Adds a $ and a checksum to a given string.
     pub fn add_checksum(sentence: String) -> String {
-        let mut checksum = 0;
-        for char in sentence.as_bytes() {
-            checksum ^= *char;
+        // Always 2 hex digits: a checksum under 0x10 is given as 0F, not F.
+        format!("${}*{:02X}\r\n", sentence, checksum(sentence.as_bytes())).to_ascii_uppercase()
+    }
+
+    /// Gives the command number and ack if the line is a valid PMTK001 sentence, None for any other
+    /// line.
+    pub(crate) fn parse_pmtk_001(line: &str) -> Option<(u16, Pmtk001Ack)> {
+        // Format: $PMTK001,cmd,flag*checksum
+        if !line.starts_with("$PMTK001") || !is_valid_checksum(line) {
+            return None;
         }
-        let checksum = format!("{:X}", checksum); //Format as hexidecimal.
-        let checksumed_sentence = format!("${}*{}\r\n", sentence, checksum)
-            .as_str()
-            .to_ascii_uppercase();
-        return checksumed_sentence;
+        // Remove checksum.
+        let line = line.trim().split('*').next().unwrap_or("");
+        let args: Vec<&str> = line.split(',').collect();
+        // args: $PMTK001, cmd, flag,
+        let command = args.get(1)?.parse().ok()?;
+        Some((command, match args.get(2) {
+            Some(&"0") => Pmtk001Ack::Invalid,
+            Some(&"1") => Pmtk001Ack::Unsupported,
+            Some(&"2") => Pmtk001Ack::Failed,
+            Some(&"3") => Pmtk001Ack::Success,
+            _ => Pmtk001Ack::NoPacket,
+        }))
+    }
+
+    /// Gives the line without the checksum if it is a valid PMTK sentence, None for any other line.
+    pub(crate) fn parse_pmtk_500(line: &str) -> Option<String> {
+        if !line.starts_with("$PMTK") || !is_valid_checksum(line) {
+            return None;
+        }
+        line.trim().split('*').next().map(|line| line.to_string())
     }
 
     /// Success (new baud rate) or fail.
@@ -127,10 +165,15 @@ pub mod send_pmtk {
     ///
     /// Returns BaudRateResults enum: Success(baud rate), Fail.
     ///
+    /// Gives GpsError::BaudRate if the baud rate is not a number, or GpsError::PortOpen if the port
+    /// cannot be opened.
+    ///
     /// Use a battery to maintain settings as this method takes a while to run and is error prone.
-    pub fn set_baud_rate(baud_rate: &str, port_name: &str) -> BaudRateResults {
+    pub fn set_baud_rate(baud_rate: &str, port_name: &str) -> Result<BaudRateResults, GpsError> {
         // stty -F /dev/serial0 9600 clocal cread cs8 -cstopb -parenb
 
+        let _: u32 = baud_rate.parse().map_err(|_e| GpsError::BaudRate(baud_rate.to_string()))?;
+
         // Get current baud rate
         let possible_baud_rates: [u32; 7] = [4800, 9600, 14400, 19200, 38400, 57600, 115200];
 
@@ -138,8 +181,8 @@ pub mod send_pmtk {
         // For some reason there are invalid bytes in front of what should be the correct baud rate.
         // So read 200 bytes, and ditch the first 100.
         for rate in possible_baud_rates.iter() {
-            let port = open_port(port_name, *rate);
-            let mut gps = Gps { port };
+            let port = open_port(port_name, *rate)?;
+            let mut gps = Gps::from_port(port);
             // Try reading 5 lines.
             for _ in 0..5 {
                 let line = gps.update();
@@ -152,73 +195,135 @@ pub mod send_pmtk {
                         let cmd = add_checksum(format!("PMTK251,{}", baud_rate));
                         let cmd = cmd.as_bytes();
                         let _ = gps.port.clear(ClearBuffer::Output);
-                        let _ = gps.port.write(cmd);
-                        return BaudRateResults::Success(*rate);
+                        let _ = gps.port.write_all(cmd);
+                        return Ok(BaudRateResults::Success(*rate));
                     }
                 }
             }
         }
-        return BaudRateResults::Fail;
+        Ok(BaudRateResults::Fail)
+    }
+
+    /// Whether the reply is a startup message.
+    fn started(reply: Result<PmtkReply, GpsError>) -> bool {
+        matches!(reply.map(|reply| reply.response),
+                 Ok(PmtkResponse::System(SystemMessage::Startup)) | Ok(PmtkResponse::Text(_)))
+    }
+
+    /// The response given by the query, None if there was no reply or it was an ack.
+    fn response(reply: Result<PmtkReply, GpsError>) -> Option<PmtkResponse> {
+        reply.ok().map(|reply| reply.response)
+    }
+
+    /// The ack of the reply, NoPacket if there was none.
+    fn ack(reply: Result<PmtkReply, GpsError>) -> Pmtk001Ack {
+        reply.map_or(Pmtk001Ack::NoPacket, |reply| reply.response.ack())
     }
 
     /// This implies all the traits to do with sending commands to the gps.
-    impl Gps {
-        #[allow(unused_must_use)] // self.port.write is not used
+    impl<T: Read + Write> Gps<T> {
         /// Send the PMTK command.
         pub fn send_command(&mut self, cmd: &str) {
             //! Input: no $ and no *checksum.
             let cmd = add_checksum(cmd.to_string());
             let byte_cmd = cmd.as_bytes();
-            self.port.clear(serialport::ClearBuffer::Output);
-            self.port.write(byte_cmd);
+            let _ = self.port.write_all(byte_cmd);
+            let _ = self.port.flush();
         }
 
-        /// Check for a PMTK001 return.
-        pub fn pmtk_001(&mut self, search_depth: i32) -> Pmtk001Ack {
-            //! Format: $pmtk{cmd},{flag},{value}*{checksum}
-            for _i in 0..search_depth {
-                // Check 10 lines before giving up.
-                let line = self.read_line();
-                match line {
-                    PortConnection::Valid(line) => {
-                        if is_valid_checksum(line.as_str()) {
-                            if &line[0..8] == "$PMTK001" {
-                                let line = line.trim();
-                                // Remove checksum.
-                                let line: Vec<&str> = line.split("*").collect();
-                                let line: &str = line.get(0).unwrap();
-
-                                let args: Vec<&str> = line.split(",").collect();
-                                // args: $PMTK001, cmd, flag,
-                                // let cmd: &str = args.get(1).expect("pmtk001 format not correct");
-                                let flag: &str = args.get(2).expect("pmtk001 format not correct");
-                                // let value: &str = args.get(3).unwrap_or(&"");
-
-                                return if flag == "0" {
-                                    Pmtk001Ack::Invalid
-                                } else if flag == "1" {
-                                    Pmtk001Ack::Unsupported
-                                } else if flag == "2" {
-                                    Pmtk001Ack::Failed
-                                } else if flag == "3" {
-                                    Pmtk001Ack::Success
-                                } else {
-                                    Pmtk001Ack::NoPacket
-                                };
-                            } else {
-                                continue;
-                            }
+        /// How long execute() and the pmtk_ methods wait for a reply, and how many times they send a
+        /// command that gets no reply or a Failed ack. See [RetryPolicy](../command/struct.RetryPolicy.html).
+        pub fn set_retry(&mut self, retry: RetryPolicy) {
+            self.retry = retry;
+        }
+
+        /// Sends the command and gives its reply: the $PMTK001 ack with the same command number, the
+        /// $PMTK5xx or $PMTK7xx reply to a query, or the startup message after a restart. Other lines,
+        /// such as acks for earlier commands, are passed over.
+        ///
+        /// The command is sent again, after the retry backoff, if there is no reply in time or the
+        /// ack is Failed. The reply gives how many attempts were made.
+        ///
+        /// Gives GpsError::Timeout if no attempt was replied to, or GpsError::InvalidCommand for a
+        /// command with an Unknown mode.
+        /// ```no_run
+        /// use adafruit_gps::{Gps, PmtkCommand, PmtkResponse};
+        ///
+        /// let mut gps = Gps::new("/dev/serial0", "9600").unwrap();
+        /// if let Ok(reply) = gps.execute(&PmtkCommand::QueryDatum) {
+        ///     if let PmtkResponse::Datum(datum) = reply.response {
+        ///         println!("Datum {} after {} attempts", datum, reply.attempts);
+        ///     }
+        /// }
+        /// ```
+        pub fn execute(&mut self, command: &PmtkCommand) -> Result<PmtkReply, GpsError> {
+            let sentence = command.to_nmea()?;
+            let retry = self.retry;
+            let mut attempts = 0;
+            loop {
+                attempts += 1;
+                self.port.write_all(sentence.as_bytes())?;
+                self.port.flush()?;
+                let reply = self.reply_to(command, command.timeout(retry.timeout));
+                let again = match &reply {
+                    Ok(response) => response.ack() == Pmtk001Ack::Failed,
+                    Err(GpsError::Timeout) => true,
+                    Err(_e) => false,
+                };
+                if !again || attempts >= retry.attempts {
+                    return reply.map(|response| PmtkReply { response, attempts });
+                }
+                sleep(retry.backoff(attempts));
+            }
+        }
+
+        /// Reads lines until the reply to the command, or the timeout. Gives GpsError::Timeout at the
+        /// timeout, or at the end of a file.
+        fn reply_to(&mut self, command: &PmtkCommand, timeout: Duration) -> Result<PmtkResponse, GpsError> {
+            let deadline = Instant::now() + timeout;
+            while let Some(remaining) = deadline.checked_duration_since(Instant::now()) {
+                let line = match self.read_line_within(remaining) {
+                    Ok(line) => line,
+                    Err(GpsError::InvalidBytes(_bytes)) => continue,
+                    Err(GpsError::Timeout) if !self.framer.ended() => continue,
+                    Err(e) => return Err(e),
+                };
+                let response = match parse_sentence(&line) {
+                    Some(args) if args[0].starts_with("$PMTK") => parse_pmtk_response(args),
+                    _ => continue,
+                };
+                if let Ok(response) = response {
+                    if response.answers(command) {
+                        if let (PmtkCommand::SetNmeaOutput(output), Pmtk001Ack::Success) = (command, response.ack()) {
+                            // next_fix() no longer waits for sentences that are not given every fix.
+                            self.fixes.expect(output);
                         }
+                        return Ok(response);
                     }
-                    PortConnection::NoConnection => {
-                        return Pmtk001Ack::NoPacket;
-                    }
-                    PortConnection::InvalidBytes(_) => {
-                        return Pmtk001Ack::NoPacket;
-                    }
-                };
+                }
+            }
+            Err(GpsError::Timeout)
+        }
+
+        /// Check for the PMTK001 return for the command number, such as 220 for PMTK220.
+        pub fn pmtk_001(&mut self, command: u16, timeout: Duration) -> Pmtk001Ack {
+            //! Format: $PMTK001,{cmd},{flag}*{checksum}
+            //!
+            //! Acks for other commands are passed over. NoPacket if there is no ack for the command
+            //! before the timeout, the end of a file is reached or the port can not be read.
+            let deadline = Instant::now() + timeout;
+            while let Some(remaining) = deadline.checked_duration_since(Instant::now()) {
+                match self.read_line_within(remaining) {
+                    Ok(line) => match parse_pmtk_001(&line) {
+                        Some((number, ack)) if number == command => return ack,
+                        _ => {}
+                    },
+                    Err(GpsError::InvalidBytes(_bytes)) => {}
+                    Err(GpsError::Timeout) if !self.framer.ended() => {}
+                    Err(_e) => return Pmtk001Ack::NoPacket,
+                }
             }
-            return Pmtk001Ack::NoPacket;
+            Pmtk001Ack::NoPacket
         }
 
         /// Check for PMTK500 style return.
@@ -226,44 +331,28 @@ pub mod send_pmtk {
             //! Return the string without checksum.
             for _i in 0..10 {
                 // Check 10 lines before giving up.
-                let line = self.read_line();
-                match line {
-                    PortConnection::Valid(line) => {
-                        if (&line[0..5] == "$PMTK") && (is_valid_checksum(&line)) {
-                            let line = line.trim();
-                            // Remove checksum.
-                            let line: Vec<&str> = line.split("*").collect();
-                            let line: &str = line.get(0).unwrap();
-                            return Some(line.to_string());
+                match self.read_line() {
+                    Ok(line) => {
+                        if let Some(line) = parse_pmtk_500(&line) {
+                            return Some(line);
                         }
                     }
-                    PortConnection::NoConnection => {
-                        return None;
-                    }
-                    PortConnection::InvalidBytes(_) => {
+                    Err(_e) => {
                         return None;
                     }
                 }
             }
-            return None;
+            None
         }
 
-        /// Checks if the GPS rebooted.
+        /// Checks if the GPS rebooted: a $PMTK011 text message or a $PMTK010 startup message within
+        /// 10 sentences.
         pub fn pmtk_startup(&mut self) -> bool {
             for _i in 0..10 {
-                let line = self.read_line();
-                match line {
-                    PortConnection::Valid(line) => {
-                        if (&line[0..8] == "$PMTK011") && (is_valid_checksum(&line)) {
-                            return true;
-                        }
-                    }
-                    PortConnection::NoConnection => {
-                        return false;
-                    }
-                    PortConnection::InvalidBytes(_) => {
-                        return false;
-                    }
+                match self.update() {
+                    GpsSentence::TextMessage(_) | GpsSentence::SystemMessage(SystemMessage::Startup) => return true,
+                    GpsSentence::NoConnection => return false,
+                    _ => {}
                 }
             }
             false
@@ -271,71 +360,43 @@ pub mod send_pmtk {
 
         /// Restart with all data intact.
         pub fn pmtk_101_cmd_hot_start(&mut self) -> bool {
-            self.send_command("PMTK101");
-            self.pmtk_startup()
+            started(self.execute(&PmtkCommand::HotStart))
         }
 
         /// Hot Restart without using Ephemeris data.
         pub fn pmtk_102_cmd_warm_start(&mut self) -> bool {
-            self.send_command("PMTK102");
-            self.pmtk_startup()
+            started(self.execute(&PmtkCommand::WarmStart))
         }
 
         /// Restart with current settings, but no navigation data.
         pub fn pmtk_103_cmd_cold_start(&mut self) -> bool {
-            self.send_command("PMTK103");
-            self.pmtk_startup()
+            started(self.execute(&PmtkCommand::ColdStart))
         }
 
         /// Full cold start resets all setting to default.
         pub fn pmtk_104_cmd_full_cold_start(&mut self) -> bool {
-            self.send_command("PMTK104");
-            self.pmtk_startup()
+            started(self.execute(&PmtkCommand::FullColdStart))
         }
 
         /// Set the update rate, as miliseconds from 100 (100Hz) to 10_000 (0.1Hz). 1000 is default.
         pub fn pmtk_220_set_nmea_updaterate(&mut self, update_rate: &str) -> Pmtk001Ack {
-            self.send_command(format!("PMTK220,{}", update_rate).as_str());
-            self.pmtk_001(10)
+            match update_rate.parse() {
+                Ok(update_rate) => ack(self.execute(&PmtkCommand::SetNmeaUpdateRate(update_rate))),
+                Err(_e) => Pmtk001Ack::Invalid,
+            }
         }
 
         /// Set Differental Gps mode
         pub fn pmtk_301_api_set_dgps_mode(&mut self, dgps_mode: DgpsMode) -> Pmtk001Ack {
-            match dgps_mode {
-                DgpsMode::NoDgps => self.send_command("PMTK301,0"),
-                DgpsMode::RTCM => self.send_command("PMTK301,1"),
-                DgpsMode::WAAS => self.send_command("PMTK301,2"),
-                DgpsMode::Unknown => (),
-            }
-
-            self.pmtk_001(10)
+            ack(self.execute(&PmtkCommand::SetDgpsMode(dgps_mode)))
         }
 
         /// Check what the current Differential Gps mode is.
         pub fn pmtk_401_api_q_dgps_mode(&mut self) -> DgpsMode {
-            self.send_command("PMTK401");
-
-            // Should be just one arg.
-            return match self.pmtk_500() {
-                Some(args) => {
-                    if args.len() != 10 {
-                        // $PM TK5 01, {0,1,2}
-                        return DgpsMode::Unknown;
-                    }
-                    let mode: String = args.chars().nth_back(0).unwrap().to_string();
-                    let mode: &str = mode.as_str();
-                    if mode == "0" {
-                        return DgpsMode::NoDgps;
-                    } else if mode == "1" {
-                        DgpsMode::RTCM
-                    } else if mode == "2" {
-                        DgpsMode::WAAS
-                    } else {
-                        DgpsMode::Unknown
-                    }
-                }
-                None => DgpsMode::Unknown,
-            };
+            match response(self.execute(&PmtkCommand::QueryDgpsMode)) {
+                Some(PmtkResponse::DgpsMode(mode)) => mode,
+                _ => DgpsMode::Unknown,
+            }
         }
 
         /// Set SBAS (Satellite-based augmentation systems) enabled or disabled.
@@ -343,34 +404,15 @@ pub mod send_pmtk {
             //! Enable = 1 -> Default.
             //!
             //! Disabled = 0
-            match sbas {
-                Sbas::Enabled => self.send_command("PMTK313,1"),
-                Sbas::Disabled => self.send_command("PMTK313,0"),
-                Sbas::Unknown => (),
-            }
-            self.pmtk_001(10)
+            ack(self.execute(&PmtkCommand::SetSbasEnabled(sbas)))
         }
 
         /// Check if SBAS is enabled
         pub fn pmtk_413_api_q_sbas_enabled(&mut self) -> Sbas {
-            self.send_command("PMTK413");
-            return match self.pmtk_500() {
-                Some(args) => {
-                    if args.len() != 10 {
-                        return Sbas::Unknown;
-                    }
-                    let mode = args.chars().nth_back(0).unwrap().to_string();
-                    let mode = mode.as_str();
-                    if mode == "0" {
-                        Sbas::Disabled
-                    } else if mode == "1" {
-                        Sbas::Enabled
-                    } else {
-                        Sbas::Unknown
-                    }
-                }
-                None => Sbas::Unknown,
-            };
+            match response(self.execute(&PmtkCommand::QuerySbasEnabled)) {
+                Some(PmtkResponse::SbasEnabled(sbas)) => sbas,
+                _ => Sbas::Unknown,
+            }
         }
 
         /// Set what NMEA sentences are to be outputted as frequency.
@@ -379,64 +421,38 @@ pub mod send_pmtk {
         /// - 0 -> Never
         /// - 1 -> Every output
         /// - 2 -> Every second output
-        /// ...
+        /// - ...
         /// - 5 -> Every 5th output
+        ///
         pub fn pmtk_314_api_set_nmea_output(&mut self, output: NmeaOutput) -> Pmtk001Ack {
             //! 19 fields can be parsed to this one.
             //!
-            //! $PMTK314,{GPGLL},{GPRMC},{GPTVG},{GPGGA},{GPGAS},{GPGSV},{R}..6-17,{PMTKCHN interval}
+            //! $PMTK314,{GPGLL},{GPRMC},{GPTVG},{GPGGA},{GPGAS},{GPGSV},{R}..6-16,{GPZDA},{PMTKCHN interval}
             //!
             //! For each field, frequency setting is given: 0-5, 0-> Disabled,
             //! 1-> Output once everty one position fix, 2-> every second... every 5th.
             //!
             //! Default is PMTK314,-1* (Default: 0,1,1,1,1,5,0..0)
-
-            self.send_command(
-                format!(
-                    "PMTK314,{},{},{},{},{},{},0,0,0,0,0,0,0,{}",
-                    output.gll, output.rmc, output.vtg, output.gga, output.gsa, output.gsv, output.pmtkchn_interval
-                )
-                    .as_str(),
-            );
-            self.pmtk_001(10)
+            ack(self.execute(&PmtkCommand::SetNmeaOutput(output)))
         }
 
         /// Gets current NMEA output frequency.
         pub fn pmtk_414_api_q_nmea_output(&mut self) -> NmeaOutput {
             //! Return 514: PMTK514, the nmea outputs that are valid (see pmtk_314_api_set_nmea_output
             //! for the fields).
-            self.send_command("PMTK414");
-            return match self.pmtk_500() {
-                Some(args) => {
-                    let args: Vec<&str> = args.split(",").collect();
-                    let gll: &str = args.get(1).unwrap_or(&"-1");
-                    let rmc: &str = args.get(2).unwrap_or(&"-1");
-                    let vtg: &str = args.get(3).unwrap_or(&"-1");
-                    let gga: &str = args.get(4).unwrap_or(&"-1");
-                    let gsa: &str = args.get(5).unwrap_or(&"-1");
-                    let gsv: &str = args.get(6).unwrap_or(&"-1");
-                    let pmtkchn_interval: &str = args.get(18).unwrap_or(&"-1");
-
-                    NmeaOutput {
-                        gll: gll.parse::<i8>().unwrap(),
-                        rmc: rmc.parse::<i8>().unwrap(),
-                        vtg: vtg.parse::<i8>().unwrap(),
-                        gga: gga.parse::<i8>().unwrap(),
-                        gsa: gsa.parse::<i8>().unwrap(),
-                        gsv: gsv.parse::<i8>().unwrap(),
-                        pmtkchn_interval: pmtkchn_interval.parse::<i8>().unwrap(),
-                    }
-                }
-                None => NmeaOutput {
+            match response(self.execute(&PmtkCommand::QueryNmeaOutput)) {
+                Some(PmtkResponse::NmeaOutput(output)) => output,
+                _ => NmeaOutput {
                     gll: -1,
                     rmc: -1,
                     vtg: -1,
                     gga: -1,
                     gsa: -1,
                     gsv: -1,
+                    zda: -1,
                     pmtkchn_interval: -1,
                 },
-            };
+            }
         }
 
         /// Set SBAS mode
@@ -444,14 +460,8 @@ pub mod send_pmtk {
             //! Set sbas mode. 0=testing mode and 1=integrity mode.
             //! Integrity mode is default.
             //!
-            //! Get's reboot code.
-            //!
-            match sbas_mode {
-                SbasMode::Integrity => self.send_command("PMTK391,1"),
-                SbasMode::Testing => self.send_command("PMTK391,0"),
-                SbasMode::Unknown => (),
-            }
-            self.pmtk_startup()
+            //! True if the gps acked it with Success.
+            ack(self.execute(&PmtkCommand::SetSbasMode(sbas_mode))) == Pmtk001Ack::Success
         }
 
         /// Check SBAS mode
@@ -459,21 +469,10 @@ pub mod send_pmtk {
             //! 519 response, PMTK519,{0,1} for {testing mode, integrity mode}, set by 319.
             //! false: testing mode, true: integrity mode.
             //!
-            self.send_command("PMTK419");
-            return match self.pmtk_500() {
-                Some(args) => {
-                    let arg = args.chars().nth_back(0).unwrap().to_string();
-                    let arg = arg.as_str();
-                    if arg == "0" {
-                        SbasMode::Testing
-                    } else if arg == "1" {
-                        SbasMode::Integrity
-                    } else {
-                        SbasMode::Unknown
-                    }
-                }
-                None => SbasMode::Unknown,
-            };
+            match response(self.execute(&PmtkCommand::QuerySbasMode)) {
+                Some(PmtkResponse::SbasMode(mode)) => mode,
+                _ => SbasMode::Unknown,
+            }
         }
 
         /// Gives GPS firmware release info.
@@ -481,11 +480,10 @@ pub mod send_pmtk {
             //! Return example: $PMTK705,AXN_5.1.7_3333_19020118,0027,PA1010D,1.0*76
             //!
             //! Return blank string if no info found.
-            self.send_command("PMTK605");
-            return match self.pmtk_500() {
-                Some(args) => args[9..args.len()].to_string(),
-                None => "".to_string(),
-            };
+            match response(self.execute(&PmtkCommand::QueryRelease)) {
+                Some(PmtkResponse::Release(release)) => release,
+                _ => "".to_string(),
+            }
         }
 
         /// Get EPO data: Extended Prediction Orbit tries to predict where satellites will be in the future.
@@ -504,29 +502,26 @@ pub mod send_pmtk {
             //! - 6 FCWN & FCTOW : TOW of the first set of EPO data that are currently used respectively
             //! - 7 LCWN & LCTOW : GPS week number
             //! - 8 LCWN & LCTOW : TOW of the last set of EPO data that are currently used respectively
-
-            let args = self
-                .pmtk_500()
-                .unwrap_or("PMTK,-1,-1,-1,-1,-1,-1,-1,-1,-1".to_string());
-            let args: Vec<&str> = args.split(",").collect();
-            return EpoData {
-                set: args.get(1).unwrap_or(&"-1").parse::<i8>().unwrap(),
-                fwn_ftow_week_number: args.get(2).unwrap_or(&"-1").parse::<i8>().unwrap(),
-                fwn_ftow_tow: args.get(3).unwrap_or(&"-1").parse::<i8>().unwrap(),
-                lwn_ltow_week_number: args.get(4).unwrap_or(&"-1").parse::<i8>().unwrap(),
-                lwn_ltow_tow: args.get(5).unwrap_or(&"-1").parse::<i8>().unwrap(),
-                fcwn_fctow_week_number: args.get(6).unwrap_or(&"-1").parse::<i8>().unwrap(),
-                fcwn_fctow_tow: args.get(7).unwrap_or(&"-1").parse::<i8>().unwrap(),
-                lcwn_lctow_week_number: args.get(8).unwrap_or(&"-1").parse::<i8>().unwrap(),
-                lcwn_lctow_tow: args.get(9).unwrap_or(&"-1").parse::<i8>().unwrap(),
-            };
+            match response(self.execute(&PmtkCommand::QueryEpoInfo)) {
+                Some(PmtkResponse::EpoInfo(epo)) => epo,
+                _ => EpoData {
+                    set: -1,
+                    fwn_ftow_week_number: -1,
+                    fwn_ftow_tow: -1,
+                    lwn_ltow_week_number: -1,
+                    lwn_ltow_tow: -1,
+                    fcwn_fctow_week_number: -1,
+                    fcwn_fctow_tow: -1,
+                    lcwn_lctow_week_number: -1,
+                    lcwn_lctow_tow: -1,
+                },
+            }
         }
 
         /// Clear EPO data.
         pub fn pmtk_127_cmd_clear_epo(&mut self) -> Pmtk001Ack {
             //! Multiple $CLR,EPO,{000a8000}*5E lines, ending with a 001 response.
-            self.send_command("PMTK127");
-            self.pmtk_001(50) // 50 should be plenty. Probably.
+            ack(self.execute(&PmtkCommand::ClearEpo))
         }
 
         /// For MT3318 and MT3329 chips.
@@ -536,8 +531,7 @@ pub mod send_pmtk {
         /// Speed thresholds: 0/ 0.2/ 0.4/ 0.6/ 0.8/ 1.0/1.5/2.0 (m/s)
         pub fn pmtk_397_set_nav_speed_threshold(&mut self, nav_threshold: f32) -> Pmtk001Ack {
             //! For MT3318 and MT3329 chips.
-            self.send_command(format!("PMTK397,{:.1}", nav_threshold).as_str());
-            self.pmtk_001(10)
+            ack(self.execute(&PmtkCommand::SetNavSpeedThresholdMt3318(nav_threshold)))
         }
 
         /// For MT3339 chips.
@@ -547,28 +541,21 @@ pub mod send_pmtk {
         /// Speed thresholds: 0/ 0.2/ 0.4/ 0.6/ 0.8/ 1.0/1.5/2.0 (m/s)
         pub fn pmtk_386_set_nav_speed_threshold(&mut self, nav_threshold: f32) -> Pmtk001Ack {
             //! For MT3339 chips.
-            self.send_command(format!("PMTK397,{:.1}", nav_threshold).as_str());
-            self.pmtk_001(10)
+            ack(self.execute(&PmtkCommand::SetNavSpeedThreshold(nav_threshold)))
         }
 
         /// Gets current nav speed threshold.
         pub fn pmtk_447_q_nav_threshold(&mut self) -> f32 {
             //! $PMTK527,{0.40}*04
-            self.send_command("PMTK447");
-            return match self.pmtk_500() {
-                Some(args) => {
-                    let args: Vec<&str> = args.split(",").collect();
-                    let nav_threshold: f32 = args.get(1).unwrap().parse::<f32>().unwrap();
-                    nav_threshold
-                }
-                None => return -1.0,
-            };
+            match response(self.execute(&PmtkCommand::QueryNavThreshold)) {
+                Some(PmtkResponse::NavThreshold(nav_threshold)) => nav_threshold,
+                _ => -1.0,
+            }
         }
 
         /// Puts gps on standby mode for power saving. Send any command to wake it up again.
         pub fn pmtk_161_cmd_standby_mode(&mut self) -> Pmtk001Ack {
-            self.send_command("PMTK161,0");
-            self.pmtk_001(10)
+            ack(self.execute(&PmtkCommand::Standby))
         }
 
         /// Used with pmtk_225_cmd_periodic_mode to set periodic mode.
@@ -593,10 +580,7 @@ pub mod send_pmtk {
             //! Extention gap: Default 60000, range 0-3_600_000
             //!
             //! Standard 001 response.
-            self.send_command(
-                format!("PMTK223,{},{},{},{}", sv, snr, ext_threshold, ext_gap).as_str(),
-            );
-            self.pmtk_001(10)
+            ack(self.execute(&PmtkCommand::SetAlDeeCfg { sv, snr, ext_threshold, ext_gap }))
         }
 
         /// Sets periodic mode settings
@@ -643,21 +627,20 @@ pub mod send_pmtk {
             //! Note：
             //! - 1.The second run time should larger than first run time when non-zero value.
             //! - 2.The purpose of second run time and sleep time can let module to catch more satellite
-            //!     ephemeris data in cold boot condition. The value of them can be null. Then it will
-            //!     use the first run time and sleep time for ephemeris data receiving.
+            //!   ephemeris data in cold boot condition. The value of them can be null. Then it will
+            //!   use the first run time and sleep time for ephemeris data receiving.
             //! - 3.AlwaysLocateTM is an intelligent controller of MT3339 power saving mode. Depending on
-            //!     the environment and motion conditions, MT3339 can adaptive adjust the on/off time
-            //!     to achieve balance of positioning accuracy and power consumption.
+            //!   the environment and motion conditions, MT3339 can adaptive adjust the on/off time
+            //!   to achieve balance of positioning accuracy and power consumption.
             //! - 4.This command needs to work normal with some hardware circuits.
             //!
-            self.send_command(
-                format!(
-                    "PMTK223,{},{},{},{},{}",
-                    run_type, run_time, sleep_time, second_run_time, second_sleep_time
-                )
-                    .as_str(),
-            );
-            self.pmtk_001(10)
+            ack(self.execute(&PmtkCommand::PeriodicMode {
+                run_type,
+                run_time,
+                sleep_time,
+                second_run_time,
+                second_sleep_time,
+            }))
         }
 
         /// Active Interference Calcellation to counter jamming an enterfearance.
@@ -665,12 +648,7 @@ pub mod send_pmtk {
         /// True: enable, false: disabled.
         pub fn pmtk_286_cmd_aic_mode(&mut self, aic: bool) -> Pmtk001Ack {
             //! true is enable, false is disable.
-            if aic {
-                self.send_command("PMTK286,1")
-            } else {
-                self.send_command("PMTK286,0")
-            }
-            self.pmtk_001(10)
+            ack(self.execute(&PmtkCommand::AicMode(aic)))
         }
 
         /// Set EASY status. True: enable, False: disable.
@@ -691,37 +669,79 @@ pub mod send_pmtk {
             //! - pmtk,0 -> gives $PMTK869,2,1,3*29
             //! - pmtk,1,0 -> Gives 001 reply.
             //! - pmtk,2,{0,1} -> Gives 001 reply.
-            if enable_easy {
-                self.send_command("PMTK869,1,1")
-            } else {
-                self.send_command("PMTK869,1,0")
-            }
-            self.pmtk_001(10)
+            ack(self.execute(&PmtkCommand::SetEasy(enable_easy)))
         }
 
         /// Get current EASY status
         pub fn pmtk_869_cmd_easy_query(&mut self) -> bool {
             //! Query the EASY command status. Return true or false, true is enabled, false it disabled.
-            self.send_command("PMTK869,0");
-            return match self.pmtk_500() {
-                Some(args) => {
-                    let args: Vec<&str> = args.split(",").collect();
-                    if args.get(2).unwrap() == &"0" {
-                        false
-                    } else {
-                        true
+            !matches!(response(self.execute(&PmtkCommand::QueryEasy)), Some(PmtkResponse::Easy { enabled: false, .. }))
+        }
+
+        /// Query the LOCUS logger status.
+        pub fn pmtk_183_locus_query(&mut self) -> Option<LocusStatus> {
+            //! Gives $PMTKLOG,Serial,Type,Mode,Content,Interval,Distance,Speed,Status,Number,Percent
+            //!
+            //! None if there is no reply.
+            match response(self.execute(&PmtkCommand::LocusQuery)) {
+                Some(PmtkResponse::LocusStatus(status)) => Some(status),
+                _ => None,
+            }
+        }
+
+        /// Erase all the data logged by LOCUS.
+        pub fn pmtk_184_locus_erase(&mut self) -> Pmtk001Ack {
+            //! Logging carries on after the erase if it was on.
+            ack(self.execute(&PmtkCommand::LocusErase))
+        }
+
+        /// Start LOCUS logging, or stop it for false.
+        pub fn pmtk_185_locus_logging(&mut self, start: bool) -> Pmtk001Ack {
+            ack(self.execute(&PmtkCommand::LocusLogging(start)))
+        }
+
+        /// Dump the LOCUS flash.
+        pub fn pmtk_622_locus_dump(&mut self) -> Result<LocusDump, GpsError> {
+            //! Reads the $PMTKLOX lines until the end of the dump. Lines with a bad checksum are left
+            //! out, see LocusDump.missing().
+            //!
+            //! A dump is a few hundred lines, so it is not retried. GpsError::Timeout if the gps stops
+            //! giving dump lines for the retry timeout.
+            let sentence = PmtkCommand::LocusDump.to_nmea()?;
+            self.port.write_all(sentence.as_bytes())?;
+            self.port.flush()?;
+            let timeout = self.retry.timeout;
+            let mut dump = LocusDump::default();
+            let mut last = Instant::now();
+            while let Some(remaining) = (last + timeout).checked_duration_since(Instant::now()) {
+                let line = match self.read_line_within(remaining) {
+                    Ok(line) => line,
+                    Err(GpsError::InvalidBytes(_bytes)) => continue,
+                    Err(GpsError::Timeout) if !self.framer.ended() => continue,
+                    Err(e) => return Err(e),
+                };
+                let lox = match parse_sentence(&line) {
+                    Some(args) if args[0] == "$PMTKLOX" => parse_pmtklox(args),
+                    _ => continue,
+                };
+                last = Instant::now();
+                match lox {
+                    Ok(LoxLine::Start { lines }) if dump.lines.len() < lines as usize => {
+                        dump.lines.resize(lines as usize, None)
                     }
+                    Ok(LoxLine::Data { line, bytes }) => dump.insert(line, bytes),
+                    Ok(LoxLine::End) => return Ok(dump),
+                    _ => {}
                 }
-                None => true,
-            };
+            }
+            Err(GpsError::Timeout)
         }
 
         /// Configure LOCUS interval, seconds.
-        pub fn pmtk_187_locus_config(&mut self, locus_interval: i8) -> Pmtk001Ack {
+        pub fn pmtk_187_locus_config(&mut self, locus_interval: u16) -> Pmtk001Ack {
             //! Locus mode (1 for interval mode) is always on.
             //! Interval, in seconds, is how often to log a data.
-            self.send_command(format!("PMTK187,1,{}", locus_interval).as_str());
-            self.pmtk_001(10)
+            ack(self.execute(&PmtkCommand::LocusConfig { interval: locus_interval }))
         }
 
         /// Get DATUM, 0 = WGS84 (default).
@@ -735,8 +755,7 @@ pub mod send_pmtk {
             //! ‘2’ = TOKYO-A
             //!
             //! A full list is on the GTOP Datum list, but I can't find it.
-            self.send_command(format!("PMTK330,{}", datum).as_str());
-            self.pmtk_001(10)
+            ack(self.execute(&PmtkCommand::SetDatum(datum)))
         }
 
         /// Query current DATUM
@@ -745,15 +764,10 @@ pub mod send_pmtk {
             //! See pmtk_330_api_set_datum for more details on datum.
             //!
             //! 0 is return value if there is an error.
-            self.send_command("PMTK430");
-            return match self.pmtk_500() {
-                Some(args) => {
-                    let args: Vec<&str> = args.split(",").collect();
-                    let datum = args.get(1).unwrap_or(&"0").parse::<u16>().unwrap();
-                    datum
-                }
-                None => 0,
-            };
+            match response(self.execute(&PmtkCommand::QueryDatum)) {
+                Some(PmtkResponse::Datum(datum)) => datum,
+                _ => 0,
+            }
         }
 
         /// The receiver support new NMEA format for QZSS. The command allow user enable or disable QZSS
@@ -762,12 +776,7 @@ pub mod send_pmtk {
             //! Sets the output to be the QZSS NMEA format.
             //!
             //! True is enable, false is disable. Default is disable.
-            if enable_qzss {
-                self.send_command("PMTK351,1")
-            } else {
-                self.send_command("PMTK351,0")
-            }
-            self.pmtk_001(10)
+            ack(self.execute(&PmtkCommand::SetQzssNmea(enable_qzss)))
         }
 
         /// Since QZSS is regional positioning service. The command allow user enable or disable QZSS function.
@@ -778,17 +787,448 @@ pub mod send_pmtk {
             //! Default is enable QZSS function
             //!
             //! Enable is true, disable is false. Default is enable.
+            ack(self.execute(&PmtkCommand::SetQzss(enable)))
+        }
+
+        /// Turn the $PGTOP antenna status report on or off. GlobalTop modules only, such as the
+        /// Adafruit Ultimate GPS.
+        pub fn pgcmd_33_antenna_status(&mut self, enable: bool) {
+            //! There is no PMTK001 reply. When on, gps.update() gives GpsSentence::AntennaStatus with
+            //! each fix.
+            //!
+            //! Off by default.
             if enable {
-                self.send_command("PMTK352,0")
+                self.send_command("PGCMD,33,1")
             } else {
-                self.send_command("PMTK352,1")
+                self.send_command("PGCMD,33,0")
             }
-            self.pmtk_001(10)
         }
     }
 }
 
 
+pub mod command {
+    //! PmtkCommand gives each command in its wire format, PmtkResponse parses the replies.
+    //!
+    //! gps.execute(&command) sends a command and gives the reply that answers it:
+    //! - Set and cmd commands -> PmtkResponse::Ack, the $PMTK001 for that command number.
+    //! - Query commands -> The $PMTK5xx or $PMTK7xx reply, such as PmtkResponse::Datum for PMTK430.
+    //!   A $PMTK001 for the query, such as an Unsupported ack, also answers it.
+    //! - Restarts -> PmtkResponse::System(SystemMessage::Startup) or PmtkResponse::Text.
+    use std::time::Duration;
+
+    use crate::error::GpsError;
+    use crate::locus::logger::{parse_pmtklog, parse_pmtklox, LocusStatus, LoxLine};
+    use crate::nmea::parse_nmea::*;
+    use crate::proprietary::mtk::{parse_pmtk010, parse_pmtk011, SystemMessage, TextMessage};
+
+    use super::send_pmtk::{add_checksum, DgpsMode, EpoData, NmeaOutput, Pmtk001Ack, Sbas, SbasMode};
+
+    /// # PMTK command
+    /// Each command with its PMTK number. See the pmtk_ methods on Gps for what each one does.
+    /// - HotStart -> 101
+    /// - WarmStart -> 102
+    /// - ColdStart -> 103
+    /// - FullColdStart -> 104
+    /// - ClearEpo -> 127
+    /// - Standby -> 161
+    /// - LocusQuery -> 183
+    /// - LocusErase -> 184
+    /// - LocusLogging -> 185, true starts logging and false stops it.
+    /// - LocusConfig -> 187, interval in seconds.
+    /// - SetNmeaUpdateRate -> 220, milliseconds between fixes.
+    /// - SetAlDeeCfg -> 223
+    /// - PeriodicMode -> 225
+    /// - SetOutputFormat -> 253, binary true switches to MTK binary packets and false to NMEA. baud 0
+    ///   keeps the baud rate. Once in binary mode the gps gives no NMEA ack, see upload_epo.
+    /// - AicMode -> 286
+    /// - SetDgpsMode -> 301
+    /// - SetSbasEnabled -> 313
+    /// - SetNmeaOutput -> 314
+    /// - SetSbasMode -> 319
+    /// - SetDatum -> 330
+    /// - SetQzssNmea -> 351
+    /// - SetQzss -> 352, true enables QZSS.
+    /// - SetNavSpeedThreshold -> 386, for MT3339 chips.
+    /// - SetNavSpeedThresholdMt3318 -> 397, for MT3318 and MT3329 chips.
+    /// - QueryDgpsMode -> 401
+    /// - QuerySbasEnabled -> 413
+    /// - QueryNmeaOutput -> 414
+    /// - QuerySbasMode -> 419
+    /// - QueryDatum -> 430
+    /// - QueryNavThreshold -> 447
+    /// - QueryRelease -> 605
+    /// - QueryEpoInfo -> 607
+    /// - LocusDump -> 622
+    /// - SetEasy -> 869,1
+    /// - QueryEasy -> 869,0
+    #[derive(Debug, PartialEq, Clone)]
+    pub enum PmtkCommand {
+        HotStart,
+        WarmStart,
+        ColdStart,
+        FullColdStart,
+        ClearEpo,
+        Standby,
+        LocusQuery,
+        LocusErase,
+        LocusLogging(bool),
+        LocusConfig { interval: u16 },
+        SetNmeaUpdateRate(u16),
+        SetAlDeeCfg { sv: i8, snr: i8, ext_threshold: i32, ext_gap: i32 },
+        PeriodicMode { run_type: u8, run_time: u32, sleep_time: u32, second_run_time: u32, second_sleep_time: u32 },
+        SetOutputFormat { binary: bool, baud: u32 },
+        AicMode(bool),
+        SetDgpsMode(DgpsMode),
+        SetSbasEnabled(Sbas),
+        SetNmeaOutput(NmeaOutput),
+        SetSbasMode(SbasMode),
+        SetDatum(u16),
+        SetQzssNmea(bool),
+        SetQzss(bool),
+        SetNavSpeedThreshold(f32),
+        SetNavSpeedThresholdMt3318(f32),
+        QueryDgpsMode,
+        QuerySbasEnabled,
+        QueryNmeaOutput,
+        QuerySbasMode,
+        QueryDatum,
+        QueryNavThreshold,
+        QueryRelease,
+        QueryEpoInfo,
+        LocusDump,
+        SetEasy(bool),
+        QueryEasy,
+    }
+
+    fn flag(value: bool) -> u8 {
+        value as u8
+    }
+
+    impl PmtkCommand {
+        /// The PMTK number, which is the cmd field of its $PMTK001 ack.
+        pub fn number(&self) -> u16 {
+            match self {
+                PmtkCommand::HotStart => 101,
+                PmtkCommand::WarmStart => 102,
+                PmtkCommand::ColdStart => 103,
+                PmtkCommand::FullColdStart => 104,
+                PmtkCommand::ClearEpo => 127,
+                PmtkCommand::Standby => 161,
+                PmtkCommand::LocusQuery => 183,
+                PmtkCommand::LocusErase => 184,
+                PmtkCommand::LocusLogging(_) => 185,
+                PmtkCommand::LocusConfig { .. } => 187,
+                PmtkCommand::SetNmeaUpdateRate(_) => 220,
+                PmtkCommand::SetAlDeeCfg { .. } => 223,
+                PmtkCommand::PeriodicMode { .. } => 225,
+                PmtkCommand::SetOutputFormat { .. } => 253,
+                PmtkCommand::AicMode(_) => 286,
+                PmtkCommand::SetDgpsMode(_) => 301,
+                PmtkCommand::SetSbasEnabled(_) => 313,
+                PmtkCommand::SetNmeaOutput(_) => 314,
+                PmtkCommand::SetSbasMode(_) => 319,
+                PmtkCommand::SetDatum(_) => 330,
+                PmtkCommand::SetQzssNmea(_) => 351,
+                PmtkCommand::SetQzss(_) => 352,
+                PmtkCommand::SetNavSpeedThreshold(_) => 386,
+                PmtkCommand::SetNavSpeedThresholdMt3318(_) => 397,
+                PmtkCommand::QueryDgpsMode => 401,
+                PmtkCommand::QuerySbasEnabled => 413,
+                PmtkCommand::QueryNmeaOutput => 414,
+                PmtkCommand::QuerySbasMode => 419,
+                PmtkCommand::QueryDatum => 430,
+                PmtkCommand::QueryNavThreshold => 447,
+                PmtkCommand::QueryRelease => 605,
+                PmtkCommand::QueryEpoInfo => 607,
+                PmtkCommand::LocusDump => 622,
+                PmtkCommand::SetEasy(_) | PmtkCommand::QueryEasy => 869,
+            }
+        }
+
+        /// The command without the $ and checksum, such as PMTK220,1000.
+        ///
+        /// Gives GpsError::InvalidCommand for a mode that can not be sent, such as DgpsMode::Unknown.
+        pub fn to_sentence(&self) -> Result<String, GpsError> {
+            let number = self.number();
+            let args = match self {
+                PmtkCommand::HotStart | PmtkCommand::WarmStart | PmtkCommand::ColdStart
+                | PmtkCommand::FullColdStart | PmtkCommand::ClearEpo | PmtkCommand::QueryDgpsMode
+                | PmtkCommand::QuerySbasEnabled | PmtkCommand::QueryNmeaOutput | PmtkCommand::QuerySbasMode
+                | PmtkCommand::QueryDatum | PmtkCommand::QueryNavThreshold | PmtkCommand::QueryRelease
+                | PmtkCommand::QueryEpoInfo | PmtkCommand::LocusQuery => String::new(),
+                PmtkCommand::Standby => ",0".to_string(),
+                PmtkCommand::LocusErase => ",1".to_string(),
+                // The field is stop logging, so 0 starts it.
+                PmtkCommand::LocusLogging(start) => format!(",{}", flag(!*start)),
+                PmtkCommand::LocusConfig { interval } => format!(",1,{}", interval),
+                PmtkCommand::SetNmeaUpdateRate(rate) => format!(",{}", rate),
+                PmtkCommand::SetAlDeeCfg { sv, snr, ext_threshold, ext_gap } => {
+                    format!(",{},{},{},{}", sv, snr, ext_threshold, ext_gap)
+                }
+                PmtkCommand::PeriodicMode { run_type, run_time, sleep_time, second_run_time, second_sleep_time } => {
+                    format!(",{},{},{},{},{}", run_type, run_time, sleep_time, second_run_time, second_sleep_time)
+                }
+                PmtkCommand::SetOutputFormat { binary, baud } => format!(",{},{}", flag(*binary), baud),
+                PmtkCommand::AicMode(enable) => format!(",{}", flag(*enable)),
+                PmtkCommand::SetDgpsMode(mode) => match mode {
+                    DgpsMode::NoDgps => ",0".to_string(),
+                    DgpsMode::RTCM => ",1".to_string(),
+                    DgpsMode::WAAS => ",2".to_string(),
+                    DgpsMode::Unknown => return Err(GpsError::InvalidCommand(format!("{:?}", self))),
+                },
+                PmtkCommand::SetSbasEnabled(sbas) => match sbas {
+                    Sbas::Disabled => ",0".to_string(),
+                    Sbas::Enabled => ",1".to_string(),
+                    Sbas::Unknown => return Err(GpsError::InvalidCommand(format!("{:?}", self))),
+                },
+                PmtkCommand::SetNmeaOutput(output) => format!(
+                    ",{},{},{},{},{},{},0,0,0,0,0,0,0,0,0,0,0,{},{}",
+                    output.gll, output.rmc, output.vtg, output.gga, output.gsa, output.gsv, output.zda,
+                    output.pmtkchn_interval
+                ),
+                PmtkCommand::SetSbasMode(mode) => match mode {
+                    SbasMode::Testing => ",0".to_string(),
+                    SbasMode::Integrity => ",1".to_string(),
+                    SbasMode::Unknown => return Err(GpsError::InvalidCommand(format!("{:?}", self))),
+                },
+                PmtkCommand::SetDatum(datum) => format!(",{}", datum),
+                PmtkCommand::SetQzssNmea(enable) => format!(",{}", flag(*enable)),
+                // The field is stop QZSS, so 0 enables it.
+                PmtkCommand::SetQzss(enable) => format!(",{}", flag(!*enable)),
+                PmtkCommand::SetNavSpeedThreshold(threshold)
+                | PmtkCommand::SetNavSpeedThresholdMt3318(threshold) => format!(",{:.1}", threshold),
+                PmtkCommand::SetEasy(enable) => format!(",1,{}", flag(*enable)),
+                PmtkCommand::QueryEasy => ",0".to_string(),
+                // 1 dumps the part of the flash in use, 0 would dump all of it.
+                PmtkCommand::LocusDump => ",1".to_string(),
+            };
+            Ok(format!("PMTK{:03}{}", number, args))
+        }
+
+        /// The command as sent to the gps, with the $, checksum and \r\n.
+        pub fn to_nmea(&self) -> Result<String, GpsError> {
+            Ok(add_checksum(self.to_sentence()?))
+        }
+
+        /// Whether the gps restarts, giving $PMTK010 and $PMTK011, instead of an ack.
+        pub fn is_restart(&self) -> bool {
+            matches!(self, PmtkCommand::HotStart | PmtkCommand::WarmStart | PmtkCommand::ColdStart
+                | PmtkCommand::FullColdStart)
+        }
+
+        /// How long to wait for the reply, given the retry timeout. Clearing EPO gives a $CLR,EPO
+        /// line for each block cleared before its ack, and erasing the LOCUS flash is as slow, so
+        /// they are given 5 times as long.
+        pub(crate) fn timeout(&self, timeout: Duration) -> Duration {
+            match self {
+                PmtkCommand::ClearEpo | PmtkCommand::LocusErase => timeout * 5,
+                _ => timeout,
+            }
+        }
+    }
+
+    /// # Retry policy
+    /// - timeout -> How long to wait for the reply to each attempt. Default 2 seconds.
+    /// - attempts -> How many times to send a command that gets no reply or a Failed ack. Default 3.
+    /// - backoff -> How long to wait before the second attempt, doubled for each attempt after.
+    ///   Default 100 milliseconds.
+    #[derive(Debug, PartialEq, Clone, Copy)]
+    pub struct RetryPolicy {
+        pub timeout: Duration,
+        pub attempts: u32,
+        pub backoff: Duration,
+    }
+
+    impl Default for RetryPolicy {
+        fn default() -> RetryPolicy {
+            RetryPolicy { timeout: Duration::from_secs(2), attempts: 3, backoff: Duration::from_millis(100) }
+        }
+    }
+
+    impl RetryPolicy {
+        /// Send each command once.
+        pub fn once(timeout: Duration) -> RetryPolicy {
+            RetryPolicy { timeout, attempts: 1, backoff: Duration::from_secs(0) }
+        }
+
+        /// How long to wait after the given attempt, from 1.
+        pub fn backoff(&self, attempt: u32) -> Duration {
+            self.backoff.checked_mul(2u32.saturating_pow(attempt.saturating_sub(1))).unwrap_or(Duration::MAX)
+        }
+    }
+
+    /// # PMTK reply
+    /// - response -> The [PmtkResponse](enum.PmtkResponse.html) that answered the command.
+    /// - attempts -> How many times the command was sent, from 1.
+    #[derive(Debug, PartialEq, Clone)]
+    pub struct PmtkReply {
+        pub response: PmtkResponse,
+        pub attempts: u32,
+    }
+
+    /// # PMTK response
+    /// - Ack -> $PMTK001: the command number and [Pmtk001Ack](../send_pmtk/enum.Pmtk001Ack.html).
+    /// - System -> $PMTK010 [SystemMessage](../../mtk/enum.SystemMessage.html)
+    /// - Text -> $PMTK011 [TextMessage](../../mtk/struct.TextMessage.html)
+    /// - DgpsMode -> $PMTK501, reply to QueryDgpsMode.
+    /// - SbasEnabled -> $PMTK513, reply to QuerySbasEnabled.
+    /// - NmeaOutput -> $PMTK514, reply to QueryNmeaOutput. Fields not given are -1.
+    /// - SbasMode -> $PMTK519, reply to QuerySbasMode.
+    /// - NavThreshold -> $PMTK527, reply to QueryNavThreshold, m/s.
+    /// - Datum -> $PMTK530, reply to QueryDatum.
+    /// - Release -> $PMTK705, reply to QueryRelease: the firmware release, such as
+    ///   AXN_5.1.7_3333_19020118,0027,PA1010D,1.0
+    /// - EpoInfo -> $PMTK707, reply to QueryEpoInfo.
+    /// - Easy -> $PMTK869,2, reply to QueryEasy: whether EASY is on and how many days it has extended
+    ///   the ephemeris by.
+    /// - LocusStatus -> $PMTKLOG, reply to LocusQuery.
+    ///   [LocusStatus](../../locus/logger/struct.LocusStatus.html)
+    /// - Lox -> $PMTKLOX, one line of a LOCUS dump. [LoxLine](../../locus/logger/enum.LoxLine.html)
+    /// - Other -> Any other $PMTK sentence. kind is after the PMTK, such as 999.
+    #[derive(Debug, PartialEq, Clone)]
+    pub enum PmtkResponse {
+        Ack { command: u16, ack: Pmtk001Ack },
+        System(SystemMessage),
+        Text(TextMessage),
+        DgpsMode(DgpsMode),
+        SbasEnabled(Sbas),
+        NmeaOutput(NmeaOutput),
+        SbasMode(SbasMode),
+        NavThreshold(f32),
+        Datum(u16),
+        Release(String),
+        EpoInfo(EpoData),
+        Easy { enabled: bool, extension_days: Option<u8> },
+        LocusStatus(LocusStatus),
+        Lox(LoxLine),
+        Other { kind: String, fields: Vec<String> },
+    }
+
+    impl PmtkResponse {
+        /// Whether this is the reply to the command.
+        pub fn answers(&self, command: &PmtkCommand) -> bool {
+            match (self, command) {
+                (PmtkResponse::Ack { command: number, .. }, _) => *number == command.number(),
+                (PmtkResponse::System(SystemMessage::Startup), _) | (PmtkResponse::Text(_), _) => {
+                    command.is_restart()
+                }
+                (PmtkResponse::DgpsMode(_), PmtkCommand::QueryDgpsMode)
+                | (PmtkResponse::SbasEnabled(_), PmtkCommand::QuerySbasEnabled)
+                | (PmtkResponse::NmeaOutput(_), PmtkCommand::QueryNmeaOutput)
+                | (PmtkResponse::SbasMode(_), PmtkCommand::QuerySbasMode)
+                | (PmtkResponse::NavThreshold(_), PmtkCommand::QueryNavThreshold)
+                | (PmtkResponse::Datum(_), PmtkCommand::QueryDatum)
+                | (PmtkResponse::Release(_), PmtkCommand::QueryRelease)
+                | (PmtkResponse::EpoInfo(_), PmtkCommand::QueryEpoInfo)
+                | (PmtkResponse::Easy { .. }, PmtkCommand::QueryEasy)
+                | (PmtkResponse::LocusStatus(_), PmtkCommand::LocusQuery) => true,
+                _ => false,
+            }
+        }
+
+        /// The ack flag, NoPacket for replies that are not a $PMTK001.
+        pub fn ack(&self) -> Pmtk001Ack {
+            match self {
+                PmtkResponse::Ack { ack, .. } => *ack,
+                _ => Pmtk001Ack::NoPacket,
+            }
+        }
+    }
+
+    /// Parses a field that must be there and not empty.
+    fn parse_value<T: std::str::FromStr>(args: &[&str], index: usize, sentence: &str) -> Result<T, NmeaParseError> {
+        parse_field(args, index, sentence)?
+            .ok_or_else(|| NmeaParseError::new(sentence, index, ParseErrorReason::Missing))
+    }
+
+    /// Parses a 0 or 1 field into one of two values.
+    fn parse_switch<T>(args: &[&str], index: usize, sentence: &str, off: T, on: T) -> Result<T, NmeaParseError> {
+        match get_field(args, index, sentence)? {
+            "0" => Ok(off),
+            "1" => Ok(on),
+            field => Err(NmeaParseError::new(sentence, index, ParseErrorReason::Invalid(field.to_string()))),
+        }
+    }
+
+    pub fn parse_pmtk_response(args: Vec<&str>) -> Result<PmtkResponse, NmeaParseError> {
+        //! Format: $PMTK{number},{fields}*checksum
+        let address = get_field(&args, 0, "PMTK")?;
+        let kind = match address.strip_prefix("$PMTK") {
+            Some(kind) => kind,
+            None => return Err(NmeaParseError::new("PMTK", 0, ParseErrorReason::WrongSentence(address.to_string()))),
+        };
+        let sentence = &address[1..];
+        Ok(match kind {
+            "001" => {
+                // $PMTK001,cmd,flag
+                let ack = match get_field(&args, 2, sentence)? {
+                    "0" => Pmtk001Ack::Invalid,
+                    "1" => Pmtk001Ack::Unsupported,
+                    "2" => Pmtk001Ack::Failed,
+                    "3" => Pmtk001Ack::Success,
+                    flag => return Err(NmeaParseError::new(sentence, 2, ParseErrorReason::Invalid(flag.to_string()))),
+                };
+                PmtkResponse::Ack { command: parse_value(&args, 1, sentence)?, ack }
+            }
+            "010" => PmtkResponse::System(parse_pmtk010(args)?),
+            "011" => PmtkResponse::Text(parse_pmtk011(args)?),
+            "501" => PmtkResponse::DgpsMode(match get_field(&args, 1, sentence)? {
+                "0" => DgpsMode::NoDgps,
+                "1" => DgpsMode::RTCM,
+                "2" => DgpsMode::WAAS,
+                _ => DgpsMode::Unknown,
+            }),
+            "513" => PmtkResponse::SbasEnabled(parse_switch(&args, 1, sentence, Sbas::Disabled, Sbas::Enabled)?),
+            "514" => {
+                // $PMTK514,{GLL},{RMC},{VTG},{GGA},{GSA},{GSV},0..,{ZDA},{PMTKCHN interval}
+                let field = |index| -> Result<i8, NmeaParseError> {
+                    Ok(parse_optional_field(&args, index, sentence)?.unwrap_or(-1))
+                };
+                PmtkResponse::NmeaOutput(NmeaOutput {
+                    gll: field(1)?,
+                    rmc: field(2)?,
+                    vtg: field(3)?,
+                    gga: field(4)?,
+                    gsa: field(5)?,
+                    gsv: field(6)?,
+                    zda: field(18)?,
+                    pmtkchn_interval: field(19)?,
+                })
+            }
+            "519" => PmtkResponse::SbasMode(parse_switch(&args, 1, sentence, SbasMode::Testing, SbasMode::Integrity)?),
+            "527" => PmtkResponse::NavThreshold(parse_value(&args, 1, sentence)?),
+            "530" => PmtkResponse::Datum(parse_value(&args, 1, sentence)?),
+            "705" => {
+                get_field(&args, 1, sentence)?;
+                PmtkResponse::Release(args[1..].join(","))
+            }
+            "707" => PmtkResponse::EpoInfo(EpoData {
+                set: parse_value(&args, 1, sentence)?,
+                fwn_ftow_week_number: parse_value(&args, 2, sentence)?,
+                fwn_ftow_tow: parse_value(&args, 3, sentence)?,
+                lwn_ltow_week_number: parse_value(&args, 4, sentence)?,
+                lwn_ltow_tow: parse_value(&args, 5, sentence)?,
+                fcwn_fctow_week_number: parse_value(&args, 6, sentence)?,
+                fcwn_fctow_tow: parse_value(&args, 7, sentence)?,
+                lcwn_lctow_week_number: parse_value(&args, 8, sentence)?,
+                lcwn_lctow_tow: parse_value(&args, 9, sentence)?,
+            }),
+            // $PMTK869,2,{enabled},{extension days}
+            "869" if args.get(1) == Some(&"2") => PmtkResponse::Easy {
+                enabled: parse_switch(&args, 2, sentence, false, true)?,
+                extension_days: parse_optional_field(&args, 3, sentence)?,
+            },
+            "LOG" => PmtkResponse::LocusStatus(parse_pmtklog(args)?),
+            "LOX" => PmtkResponse::Lox(parse_pmtklox(args)?),
+            _ => PmtkResponse::Other {
+                kind: kind.to_string(),
+                fields: args[1..].iter().map(|field| field.to_string()).collect(),
+            },
+        })
+    }
+}
+
 #[cfg(test)]
 mod checksum_test {
     use crate::pmtk::send_pmtk::add_checksum;
@@ -802,11 +1242,401 @@ mod checksum_test {
             "$GNGGA,165419.000,5132.7378,N,00005.9192,W,1,7,1.93,34.4,M,47.0,M,,*6A\r\n"
                 .to_string()
         );
-        assert_eq!(add_checksum("PMTK103".to_string()), "$PMTK103*30\r\n")
+        assert_eq!(add_checksum("PMTK103".to_string()), "$PMTK103*30\r\n");
+        assert_eq!(add_checksum("GPVTG,10.00,T,,M,0.03,N,0.06,K,A".to_string()), "$GPVTG,10.00,T,,M,0.03,N,0.06,K,A*09\r\n");
+    }
+}
+
+#[cfg(test)]
+mod memory_port_test {
+    use crate::open_gps::gps::Gps;
+
+    use super::send_pmtk::{NmeaOutput, Pmtk001Ack};
+
+    #[test]
+    fn ack_from_bytes() {
+        let mut gps = Gps::from_bytes(b"$GPGSA,A,3,29,02,26,25,31,14,,,,,,,1.42,1.17,0.80*07\r\n$PMTK001,220,3*30\r\n");
+        assert_eq!(gps.pmtk_220_set_nmea_updaterate("1000"), Pmtk001Ack::Success);
+        assert_eq!(gps.port.output, b"$PMTK220,1000*1F\r\n".to_vec());
+    }
+
+    #[test]
+    fn nmea_output_has_19_fields() {
+        let mut gps = Gps::from_bytes(b"$PMTK001,314,3*36\r\n");
+        let output = NmeaOutput { gll: 0, rmc: 1, vtg: 1, gga: 1, gsa: 0, gsv: 0, zda: 1, pmtkchn_interval: 0 };
+        assert_eq!(gps.pmtk_314_api_set_nmea_output(output), Pmtk001Ack::Success);
+        assert_eq!(gps.port.output, b"$PMTK314,0,1,1,1,0,0,0,0,0,0,0,0,0,0,0,0,0,1,0*28\r\n".to_vec());
+    }
+
+    #[test]
+    fn startup_after_restart() {
+        let mut gps = Gps::from_bytes(b"$PMTK011,MTKGPS*08\r\n");
+        assert!(gps.pmtk_101_cmd_hot_start());
+        let mut gps = Gps::from_bytes(b"$PMTK010,003*2C\r\n$PMTK010,001*2E\r\n");
+        assert!(gps.pmtk_101_cmd_hot_start());
+        let mut gps = Gps::from_bytes(b"$PMTK010,003*2C\r\n");
+        assert!(!gps.pmtk_101_cmd_hot_start());
+    }
+
+    #[test]
+    fn antenna_status_command() {
+        let mut gps = Gps::from_bytes(b"");
+        gps.pgcmd_33_antenna_status(true);
+        gps.pgcmd_33_antenna_status(false);
+        assert_eq!(gps.port.output, b"$PGCMD,33,1*6C\r\n$PGCMD,33,0*6D\r\n".to_vec());
+    }
+
+    #[test]
+    fn no_ack_at_end_of_bytes() {
+        let mut gps = Gps::from_bytes(b"");
+        assert_eq!(gps.pmtk_220_set_nmea_updaterate("1000"), Pmtk001Ack::NoPacket);
+    }
+}
+
+#[cfg(test)]
+mod command_test {
+    use crate::error::GpsError;
+    use crate::locus::logger::{LocusState, LocusStatus, LocusType, LoxLine, LOX_LINE_SIZE};
+    use crate::open_gps::gps::Gps;
+    use crate::proprietary::mtk::{SystemMessage, TextMessage};
+
+    use super::command::{PmtkCommand, PmtkReply, PmtkResponse};
+    use super::send_pmtk::{DgpsMode, EpoData, NmeaOutput, Pmtk001Ack, Sbas, SbasMode};
+
+    fn ack(command: u16, ack: Pmtk001Ack) -> PmtkResponse {
+        PmtkResponse::Ack { command, ack }
+    }
+
+    const OUTPUT: NmeaOutput = NmeaOutput { gll: 0, rmc: 1, vtg: 1, gga: 1, gsa: 1, gsv: 5, zda: 0, pmtkchn_interval: 0 };
+
+    /// Command, what is sent, what the gps replies and the response given.
+    fn commands() -> Vec<(PmtkCommand, &'static str, &'static str, PmtkResponse)> {
+        vec![
+            (PmtkCommand::HotStart, "$PMTK101*32", "$PMTK011,MTKGPS*08",
+             PmtkResponse::Text(TextMessage { text: "MTKGPS".to_string() })),
+            (PmtkCommand::WarmStart, "$PMTK102*31", "$PMTK010,001*2E", PmtkResponse::System(SystemMessage::Startup)),
+            (PmtkCommand::ColdStart, "$PMTK103*30", "$PMTK010,001*2E", PmtkResponse::System(SystemMessage::Startup)),
+            (PmtkCommand::FullColdStart, "$PMTK104*37", "$PMTK010,001*2E", PmtkResponse::System(SystemMessage::Startup)),
+            (PmtkCommand::ClearEpo, "$PMTK127*36", "$PMTK001,127,3*34", ack(127, Pmtk001Ack::Success)),
+            (PmtkCommand::Standby, "$PMTK161,0*28", "$PMTK001,161,3*36", ack(161, Pmtk001Ack::Success)),
+            (PmtkCommand::LocusQuery, "$PMTK183*38", "$PMTKLOG,456,0,11,31,2,0,0,0,3769,46*48",
+             PmtkResponse::LocusStatus(LocusStatus {
+                serial: 456,
+                log_type: LocusType::Overlap,
+                mode: 11,
+                content: 31,
+                interval: 2,
+                distance: 0,
+                speed: 0,
+                status: LocusState::Logging,
+                records: 3769,
+                percent: 46,
+            })),
+            (PmtkCommand::LocusErase, "$PMTK184,1*22", "$PMTK001,184,3*3D", ack(184, Pmtk001Ack::Success)),
+            (PmtkCommand::LocusLogging(true), "$PMTK185,0*22", "$PMTK001,185,3*3C", ack(185, Pmtk001Ack::Success)),
+            (PmtkCommand::LocusLogging(false), "$PMTK185,1*23", "$PMTK001,185,3*3C", ack(185, Pmtk001Ack::Success)),
+            (PmtkCommand::LocusConfig { interval: 15 }, "$PMTK187,1,15*09", "$PMTK001,187,3*3E",
+             ack(187, Pmtk001Ack::Success)),
+            (PmtkCommand::SetNmeaUpdateRate(1000), "$PMTK220,1000*1F", "$PMTK001,220,3*30",
+             ack(220, Pmtk001Ack::Success)),
+            (PmtkCommand::SetAlDeeCfg { sv: 1, snr: 30, ext_threshold: 180_000, ext_gap: 60_000 },
+             "$PMTK223,1,30,180000,60000*3C", "$PMTK001,223,3*33", ack(223, Pmtk001Ack::Success)),
+            (PmtkCommand::PeriodicMode {
+                run_type: 2,
+                run_time: 3000,
+                sleep_time: 12_000,
+                second_run_time: 18_000,
+                second_sleep_time: 72_000,
+            }, "$PMTK225,2,3000,12000,18000,72000*15", "$PMTK001,225,3*35", ack(225, Pmtk001Ack::Success)),
+            (PmtkCommand::SetOutputFormat { binary: true, baud: 0 }, "$PMTK253,1,0*37", "$PMTK001,253,3*34",
+             ack(253, Pmtk001Ack::Success)),
+            (PmtkCommand::AicMode(true), "$PMTK286,1*23", "$PMTK001,286,3*3C", ack(286, Pmtk001Ack::Success)),
+            (PmtkCommand::SetDgpsMode(DgpsMode::WAAS), "$PMTK301,2*2E", "$PMTK001,301,3*32",
+             ack(301, Pmtk001Ack::Success)),
+            (PmtkCommand::SetSbasEnabled(Sbas::Enabled), "$PMTK313,1*2E", "$PMTK001,313,3*31",
+             ack(313, Pmtk001Ack::Success)),
+            (PmtkCommand::SetNmeaOutput(OUTPUT), "$PMTK314,0,1,1,1,1,5,0,0,0,0,0,0,0,0,0,0,0,0,0*2D",
+             "$PMTK001,314,3*36", ack(314, Pmtk001Ack::Success)),
+            (PmtkCommand::SetSbasMode(SbasMode::Integrity), "$PMTK319,1*24", "$PMTK001,319,3*3B",
+             ack(319, Pmtk001Ack::Success)),
+            (PmtkCommand::SetDatum(0), "$PMTK330,0*2E", "$PMTK001,330,3*30", ack(330, Pmtk001Ack::Success)),
+            (PmtkCommand::SetQzssNmea(false), "$PMTK351,0*29", "$PMTK001,351,3*37", ack(351, Pmtk001Ack::Success)),
+            (PmtkCommand::SetQzss(false), "$PMTK352,1*2B", "$PMTK001,352,3*34", ack(352, Pmtk001Ack::Success)),
+            (PmtkCommand::SetNavSpeedThreshold(0.4), "$PMTK386,0.4*39", "$PMTK001,386,3*3D",
+             ack(386, Pmtk001Ack::Success)),
+            (PmtkCommand::SetNavSpeedThresholdMt3318(0.2), "$PMTK397,0.2*3F", "$PMTK001,397,1*3F",
+             ack(397, Pmtk001Ack::Unsupported)),
+            (PmtkCommand::QueryDgpsMode, "$PMTK401*37", "$PMTK501,2*28", PmtkResponse::DgpsMode(DgpsMode::WAAS)),
+            (PmtkCommand::QuerySbasEnabled, "$PMTK413*34", "$PMTK513,1*28", PmtkResponse::SbasEnabled(Sbas::Enabled)),
+            (PmtkCommand::QueryNmeaOutput, "$PMTK414*33", "$PMTK514,0,1,1,1,1,5,0,0,0,0,0,0,0,0,0,0,0,0,0*2B",
+             PmtkResponse::NmeaOutput(OUTPUT)),
+            (PmtkCommand::QuerySbasMode, "$PMTK419*3E", "$PMTK519,1*22", PmtkResponse::SbasMode(SbasMode::Integrity)),
+            (PmtkCommand::QueryDatum, "$PMTK430*35", "$PMTK530,0*28", PmtkResponse::Datum(0)),
+            (PmtkCommand::QueryNavThreshold, "$PMTK447*35", "$PMTK527,0.40*04", PmtkResponse::NavThreshold(0.4)),
+            (PmtkCommand::QueryRelease, "$PMTK605*31", "$PMTK705,AXN_5.1.7_3333_19020118,0027,PA1010D,1.0*76",
+             PmtkResponse::Release("AXN_5.1.7_3333_19020118,0027,PA1010D,1.0".to_string())),
+            (PmtkCommand::QueryEpoInfo, "$PMTK607*33",
+             "$PMTK707,56,2119,345600,2126,388800,2119,345600,2119,367200*1A", PmtkResponse::EpoInfo(EpoData {
+                set: 56,
+                fwn_ftow_week_number: 2119,
+                fwn_ftow_tow: 345_600,
+                lwn_ltow_week_number: 2126,
+                lwn_ltow_tow: 388_800,
+                fcwn_fctow_week_number: 2119,
+                fcwn_fctow_tow: 345_600,
+                lcwn_lctow_week_number: 2119,
+                lcwn_lctow_tow: 367_200,
+            })),
+            (PmtkCommand::LocusDump, "$PMTK622,1*29", "$PMTK001,622,3*36", ack(622, Pmtk001Ack::Success)),
+            (PmtkCommand::SetEasy(true), "$PMTK869,1,1*35", "$PMTK001,869,3*37", ack(869, Pmtk001Ack::Success)),
+            (PmtkCommand::QueryEasy, "$PMTK869,0*29", "$PMTK869,2,1,3*29",
+             PmtkResponse::Easy { enabled: true, extension_days: Some(3) }),
+        ]
+    }
+
+    #[test]
+    fn every_command() {
+        for (command, sent, reply, response) in commands() {
+            let mut gps = Gps::from_bytes(format!("{}\r\n", reply).as_bytes());
+            assert_eq!(gps.execute(&command).unwrap(), PmtkReply { response, attempts: 1 }, "{:?}", command);
+            assert_eq!(gps.port.output, format!("{}\r\n", sent).into_bytes(), "{:?}", command);
+        }
+    }
+
+    #[test]
+    fn ack_for_other_command_passed_over() {
+        let mut gps = Gps::from_bytes(b"$PMTK001,220,2*31\r\n$PMTK999,1*26\r\n$PMTK001,313,3*31\r\n");
+        assert_eq!(gps.execute(&PmtkCommand::SetSbasEnabled(Sbas::Enabled)).unwrap().response, ack(313, Pmtk001Ack::Success));
+        let mut gps = Gps::from_bytes(b"$PMTK001,101,3*30\r\n$PMTK001,605,1*31\r\n");
+        assert_eq!(gps.execute(&PmtkCommand::QueryRelease).unwrap().response, ack(605, Pmtk001Ack::Unsupported));
+        let mut gps = Gps::from_bytes(b"$PMTK001,220,2*31\r\n");
+        assert!(matches!(gps.execute(&PmtkCommand::SetSbasEnabled(Sbas::Enabled)), Err(GpsError::Timeout)));
+    }
+
+    #[test]
+    fn unknown_mode_not_sent() {
+        let mut gps = Gps::from_bytes(b"$PMTK001,301,3*32\r\n");
+        assert!(matches!(gps.execute(&PmtkCommand::SetDgpsMode(DgpsMode::Unknown)), Err(GpsError::InvalidCommand(_))));
+        assert_eq!(gps.port.output, Vec::<u8>::new());
+    }
+
+    #[test]
+    fn wrong_numbers_fixed() {
+        let mut gps = Gps::from_bytes(b"$PMTK001,386,3*3D\r\n$PMTK001,225,3*35\r\n$PMTK001,319,3*3B\r\n");
+        assert_eq!(gps.pmtk_386_set_nav_speed_threshold(0.4), Pmtk001Ack::Success);
+        assert_eq!(gps.pmtk_225_cmd_periodic_mode(2, 3000, 12_000, 18_000, 72_000), Pmtk001Ack::Success);
+        assert!(gps.pmtk_319_api_set_sbas_mode(SbasMode::Integrity));
+        assert_eq!(gps.port.output, b"$PMTK386,0.4*39\r\n$PMTK225,2,3000,12000,18000,72000*15\r\n$PMTK319,1*24\r\n".to_vec());
+    }
+
+    #[test]
+    fn locus_dump() {
+        let mut gps = Gps::from_bytes(concat!(
+            "$PMTKLOX,0,3*5A\r\n",
+            "$PMTKLOX,1,0,0100010B,7F000000*5B\r\n",
+            "$GPGSA,A,3,29,02,26,25,31,14,,,,,,,1.42,1.17,0.80*07\r\n",
+            "$PMTKLOX,1,1,FFFFFFFF*00\r\n",
+            "$PMTKLOX,1,2,FFFFFFFF*76\r\n",
+            "$PMTKLOX,2*47\r\n",
+            "$PMTK001,622,3*36\r\n",
+        ).as_bytes());
+        let dump = gps.pmtk_622_locus_dump().unwrap();
+        assert_eq!(gps.port.output, b"$PMTK622,1*29\r\n".to_vec());
+        assert_eq!(dump.lines, vec![Some(vec![0x01, 0x00, 0x01, 0x0B, 0x7F, 0x00, 0x00, 0x00]), None,
+                                    Some(vec![0xFF; 4])]);
+        assert_eq!(dump.missing(), vec![1]);
+        assert_eq!(dump.data().len(), 8 + LOX_LINE_SIZE + 4);
+
+        let mut gps = Gps::from_bytes(b"$PMTKLOX,0,3*5A\r\n$PMTKLOX,1,0,0100010B,7F000000*5B\r\n");
+        assert!(matches!(gps.pmtk_622_locus_dump(), Err(GpsError::Timeout)));
+        assert_eq!(super::command::parse_pmtk_response(vec!["$PMTKLOX", "2"]).unwrap(), PmtkResponse::Lox(LoxLine::End));
+    }
+
+    #[test]
+    fn other_response() {
+        let mut gps = Gps::from_bytes(b"$PMTK999,1*26\r\n");
+        assert!(matches!(gps.execute(&PmtkCommand::QueryDatum), Err(GpsError::Timeout)));
+        let args = vec!["$PMTK999", "1"];
+        assert_eq!(super::command::parse_pmtk_response(args).unwrap(),
+                   PmtkResponse::Other { kind: "999".to_string(), fields: vec!["1".to_string()] });
+    }
+}
+
+#[cfg(test)]
+mod retry_test {
+    use std::io::{self, Read, Write};
+    use std::thread::sleep;
+    use std::time::{Duration, Instant};
+
+    use crate::error::GpsError;
+    use crate::open_gps::gps::Gps;
+
+    use super::command::{PmtkCommand, PmtkReply, PmtkResponse, RetryPolicy};
+    use super::send_pmtk::Pmtk001Ack;
+
+    const SET_RATE: &[u8] = b"$PMTK220,1000*1F\r\n";
+
+    /// Port that gives the same sentence forever, like a gps that never acks.
+    struct Repeating;
+
+    impl Read for Repeating {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let line = b"$GPGSA,A,3,29,02,26,25,31,14,,,,,,,1.42,1.17,0.80*07\r\n";
+            let size = buf.len().min(line.len());
+            buf[..size].copy_from_slice(&line[..size]);
+            Ok(size)
+        }
+    }
+
+    impl Write for Repeating {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// Port that gives nothing, like a serial port whose reads time out.
+    struct Silent;
+
+    impl Read for Silent {
+        fn read(&mut self, _buf: &mut [u8]) -> io::Result<usize> {
+            sleep(Duration::from_millis(10));
+            Err(io::Error::from(io::ErrorKind::TimedOut))
+        }
+    }
+
+    impl Write for Silent {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// Port that gives nothing until the given time, then gives the line, like a gps that is slow
+    /// to reply.
+    struct Late {
+        at: Instant,
+        line: &'static [u8],
+    }
+
+    impl Read for Late {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            if Instant::now() < self.at || self.line.is_empty() {
+                sleep(Duration::from_millis(10));
+                return Err(io::Error::from(io::ErrorKind::TimedOut));
+            }
+            let size = buf.len().min(self.line.len());
+            buf[..size].copy_from_slice(&self.line[..size]);
+            self.line = &self.line[size..];
+            Ok(size)
+        }
+    }
+
+    impl Write for Late {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn retry(attempts: u32, backoff: u64) -> RetryPolicy {
+        RetryPolicy { timeout: Duration::from_secs(1), attempts, backoff: Duration::from_millis(backoff) }
+    }
+
+    #[test]
+    fn backoff_doubles() {
+        let retry = RetryPolicy::default();
+        assert_eq!(retry.backoff(1), Duration::from_millis(100));
+        assert_eq!(retry.backoff(3), Duration::from_millis(400));
+        assert!(retry.backoff(100) > Duration::from_secs(60));
+    }
+
+    #[test]
+    fn ack_matched_to_command() {
+        let mut gps = Gps::from_bytes(b"$PMTK001,313,3*31\r\n$PMTK001,220,3*30\r\n");
+        assert_eq!(gps.pmtk_001(220, Duration::from_secs(1)), Pmtk001Ack::Success);
+        let mut gps = Gps::from_bytes(b"$PMTK001,313,3*31\r\n");
+        assert_eq!(gps.pmtk_001(220, Duration::from_secs(1)), Pmtk001Ack::NoPacket);
+    }
+
+    #[test]
+    fn retry_failed() {
+        let mut gps = Gps::from_bytes(b"$PMTK001,220,2*31\r\n$PMTK001,220,3*30\r\n");
+        gps.set_retry(retry(3, 10));
+        assert_eq!(gps.execute(&PmtkCommand::SetNmeaUpdateRate(1000)).unwrap(), PmtkReply {
+            response: PmtkResponse::Ack { command: 220, ack: Pmtk001Ack::Success },
+            attempts: 2,
+        });
+        assert_eq!(gps.port.output, [SET_RATE, SET_RATE].concat());
+
+        let mut gps = Gps::from_bytes(b"$PMTK001,220,2*31\r\n$PMTK001,220,3*30\r\n");
+        gps.set_retry(retry(1, 10));
+        assert_eq!(gps.pmtk_220_set_nmea_updaterate("1000"), Pmtk001Ack::Failed);
+    }
+
+    #[test]
+    fn retry_no_packet() {
+        let mut gps = Gps::from_bytes(b"");
+        gps.set_retry(retry(3, 20));
+        let start = Instant::now();
+        assert!(matches!(gps.execute(&PmtkCommand::SetNmeaUpdateRate(1000)), Err(GpsError::Timeout)));
+        assert!(start.elapsed() >= Duration::from_millis(60));
+        assert_eq!(gps.port.output, [SET_RATE, SET_RATE, SET_RATE].concat());
+    }
+
+    #[test]
+    fn timeout_in_time() {
+        let mut gps = Gps::from_port(Repeating);
+        gps.set_retry(RetryPolicy::once(Duration::from_millis(200)));
+        let start = Instant::now();
+        assert!(matches!(gps.execute(&PmtkCommand::QueryDatum), Err(GpsError::Timeout)));
+        assert_eq!(gps.pmtk_001(220, Duration::from_millis(200)), Pmtk001Ack::NoPacket);
+        let elapsed = start.elapsed();
+        assert!(elapsed >= Duration::from_millis(400) && elapsed < Duration::from_secs(2), "{:?}", elapsed);
+    }
+
+    #[test]
+    fn timeout_past_read_timeout() {
+        // The read_line timeout is 1 second, the reply is waited for longer.
+        let mut gps = Gps::from_port(Silent);
+        gps.set_retry(RetryPolicy::once(Duration::from_millis(1500)));
+        let start = Instant::now();
+        assert!(matches!(gps.execute(&PmtkCommand::QueryDatum), Err(GpsError::Timeout)));
+        let elapsed = start.elapsed();
+        assert!(elapsed >= Duration::from_millis(1500) && elapsed < Duration::from_secs(2), "{:?}", elapsed);
+
+        let start = Instant::now();
+        assert_eq!(gps.pmtk_001(220, Duration::from_millis(1500)), Pmtk001Ack::NoPacket);
+        let elapsed = start.elapsed();
+        assert!(elapsed >= Duration::from_millis(1500) && elapsed < Duration::from_secs(2), "{:?}", elapsed);
+    }
+
+    #[test]
+    fn locus_erase_waits_longer() {
+        // The ack comes after the retry timeout and the 1 second read_line timeout, but before the
+        // 5 times longer erase timeout.
+        let late = || Late { at: Instant::now() + Duration::from_millis(1200), line: b"$PMTK001,184,3*3D\r\n" };
+        let mut gps = Gps::from_port(late());
+        gps.set_retry(RetryPolicy::once(Duration::from_millis(500)));
+        assert_eq!(gps.pmtk_184_locus_erase(), Pmtk001Ack::Success);
+
+        let mut gps = Gps::from_port(late());
+        gps.set_retry(RetryPolicy::once(Duration::from_millis(500)));
+        assert_eq!(gps.pmtk_185_locus_logging(true), Pmtk001Ack::NoPacket);
     }
 }
 
 #[cfg(test)]
+#[allow(clippy::bool_assert_comparison, clippy::needless_return)]
 mod pmtktests {
     use std::thread::sleep;
     use std::time::Duration;
@@ -819,8 +1649,8 @@ mod pmtktests {
     fn port_setup() -> Gps {
         let _ = set_baud_rate("9600", "/dev/serial0");
         sleep(Duration::from_secs(1));
-        let port = open_port("/dev/serial0", 9600);
-        let mut gps = Gps { port };
+        let port = open_port("/dev/serial0", 9600).unwrap();
+        let mut gps = Gps::from_port(port);
         gps.pmtk_220_set_nmea_updaterate("1000");
         return gps;
     }
@@ -907,6 +1737,7 @@ mod pmtktests {
                 gga: 1,
                 gsa: 1,
                 gsv: 5,
+                zda: 0,
                 pmtkchn_interval: 0,
             }
         );
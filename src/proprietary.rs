@@ -0,0 +1,306 @@
+//! # Proprietary sentences
+//! Sentences starting with $P that are given by the gps chip rather than defined by NMEA, such as
+//! the $PMTK system messages. They are parsed by the Registry like any other sentence.
+
+pub mod mtk {
+    //! Parse the PMTK010 and PMTK011 sentences given by the MTK chip at startup and when it changes
+    //! power mode, and the PMTKCHN channel status.
+    //!
+    //! After a restart the gps gives:
+    //! - $PMTK011,MTKGPS*08 -> TextMessage
+    //! - $PMTK010,001*2E -> SystemMessage::Startup
+    //!
+    //! PMTKCHN is given at the interval set by pmtkchn_interval in
+    //! [NmeaOutput](../../pmtk/send_pmtk/struct.NmeaOutput.html).
+
+    use serde::{Deserialize, Serialize};
+
+    use crate::nmea::parse_nmea::*;
+
+    /// # PMTK010 system message
+    /// - Unknown -> 000
+    /// - Startup -> 001: The gps has started, such as after a restart command.
+    /// - EpoNotification -> 002: The host should send EPO data to aid the fix.
+    /// - Normal -> 003: Transition to normal mode was successful.
+    /// - WakeUp -> 004: The gps is waking up from standby or periodic mode.
+    /// - Other -> Any other value, as given.
+    #[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+    pub enum SystemMessage {
+        Unknown,
+        Startup,
+        EpoNotification,
+        Normal,
+        WakeUp,
+        Other(u16),
+    }
+
+    impl From<u16> for SystemMessage {
+        fn from(value: u16) -> SystemMessage {
+            match value {
+                0 => SystemMessage::Unknown,
+                1 => SystemMessage::Startup,
+                2 => SystemMessage::EpoNotification,
+                3 => SystemMessage::Normal,
+                4 => SystemMessage::WakeUp,
+                other => SystemMessage::Other(other),
+            }
+        }
+    }
+
+    /// # PMTK011 text message
+    /// - text -> The text given, MTKGPS for the MTK chips.
+    #[derive(Debug, PartialEq, Eq, Clone, Default, Serialize, Deserialize)]
+    pub struct TextMessage {
+        pub text: String,
+    }
+
+    /// # Channel tracking status
+    /// - Idle -> 0: The channel is not used.
+    /// - Searching -> 1: Looking for the satellite.
+    /// - Tracking -> 2: The satellite is being tracked.
+    /// - Other -> Any other value, as given.
+    #[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+    pub enum TrackingStatus {
+        Idle,
+        Searching,
+        Tracking,
+        Other(u8),
+    }
+
+    impl From<u8> for TrackingStatus {
+        fn from(value: u8) -> TrackingStatus {
+            match value {
+                0 => TrackingStatus::Idle,
+                1 => TrackingStatus::Searching,
+                2 => TrackingStatus::Tracking,
+                other => TrackingStatus::Other(other),
+            }
+        }
+    }
+
+    /// One receiver channel.
+    /// - id -> The satellite id the channel is given.
+    /// - snr -> Signal to Noise ratio, 0-99.
+    /// - status -> [TrackingStatus](enum.TrackingStatus.html)
+    #[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+    pub struct Channel {
+        pub id: u16,
+        pub snr: u8,
+        pub status: TrackingStatus,
+    }
+
+    /// # PMTKCHN channel status
+    /// - channels -> One for each channel in order, 32 for the MTK3339. None for an empty field.
+    #[derive(Debug, PartialEq, Eq, Clone, Default, Serialize, Deserialize)]
+    pub struct ChannelStatus {
+        pub channels: Vec<Option<Channel>>,
+    }
+
+    impl ChannelStatus {
+        /// The channels that are tracking a satellite.
+        pub fn tracking(&self) -> impl Iterator<Item = &Channel> {
+            self.channels.iter().flatten().filter(|channel| channel.status == TrackingStatus::Tracking)
+        }
+    }
+
+    pub fn parse_pmtk010(args: Vec<&str>) -> Result<SystemMessage, NmeaParseError> {
+        //! Format: $PMTK010,Message*checksum
+        //!
+        //! Message is 3 digits, 000 to 004.
+        check_address(&args, "PMTK010")?;
+        match parse_field::<u16>(&args, 1, "PMTK010")? {
+            Some(value) => Ok(SystemMessage::from(value)),
+            None => Err(NmeaParseError::new("PMTK010", 1, ParseErrorReason::Missing)),
+        }
+    }
+
+    pub fn parse_pmtk011(args: Vec<&str>) -> Result<TextMessage, NmeaParseError> {
+        //! Format: $PMTK011,Text*checksum
+        //!
+        //! Any ',' in the text is kept.
+        check_address(&args, "PMTK011")?;
+        get_field(&args, 1, "PMTK011")?;
+        Ok(TextMessage { text: args[1..].join(",") })
+    }
+
+    pub fn parse_pmtkchn(args: Vec<&str>) -> Result<ChannelStatus, NmeaParseError> {
+        //! Format: $PMTKCHN,Channel 1,Channel 2...,Channel 32*checksum
+        //!
+        //! Each channel is IISSt: satellite id, SNR (2 digits) and tracking status (1 digit).
+        //! Ids over 99 are given with 3 digits.
+        check_address(&args, "PMTKCHN")?;
+        get_field(&args, 1, "PMTKCHN")?;
+        let channels = args.iter().enumerate().skip(1)
+            .map(|(index, field)| parse_channel(field, index))
+            .collect::<Result<Vec<Option<Channel>>, NmeaParseError>>()?;
+        Ok(ChannelStatus { channels })
+    }
+
+    fn parse_channel(field: &str, index: usize) -> Result<Option<Channel>, NmeaParseError> {
+        if field.is_empty() {
+            return Ok(None);
+        }
+        let invalid = || NmeaParseError::new("PMTKCHN", index, ParseErrorReason::Invalid(field.to_string()));
+        if field.len() < 5 || !field.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(invalid());
+        }
+        let split = field.len() - 3;
+        Ok(Some(Channel {
+            id: field[..split].parse().map_err(|_e| invalid())?,
+            snr: field[split..split + 2].parse().map_err(|_e| invalid())?,
+            status: TrackingStatus::from(field[split + 2..].parse::<u8>().map_err(|_e| invalid())?),
+        }))
+    }
+}
+
+pub mod gtop {
+    //! Parse the PGTOP antenna status given by GlobalTop modules, such as the Adafruit Ultimate GPS.
+    //!
+    //! The report is turned on with gps.pgcmd_33_antenna_status(true) and given once a fix:
+    //! - $PGTOP,11,1*6D -> Shorted
+    //! - $PGTOP,11,2*6E -> Internal
+    //! - $PGTOP,11,3*6F -> External
+
+    use serde::{Deserialize, Serialize};
+
+    use crate::nmea::parse_nmea::*;
+
+    /// # PGTOP,11 antenna status
+    /// - Shorted -> 1: The external active antenna is shorted.
+    /// - Internal -> 2: Using the internal patch antenna.
+    /// - External -> 3: Using the external active antenna.
+    /// - Other -> Any other value, as given.
+    #[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+    pub enum AntennaStatus {
+        Shorted,
+        Internal,
+        External,
+        Other(u8),
+    }
+
+    impl AntennaStatus {
+        /// True when the antenna needs looking at: shorted or an unknown status.
+        pub fn is_fault(&self) -> bool {
+            matches!(self, AntennaStatus::Shorted | AntennaStatus::Other(_))
+        }
+    }
+
+    impl From<u8> for AntennaStatus {
+        fn from(value: u8) -> AntennaStatus {
+            match value {
+                1 => AntennaStatus::Shorted,
+                2 => AntennaStatus::Internal,
+                3 => AntennaStatus::External,
+                other => AntennaStatus::Other(other),
+            }
+        }
+    }
+
+    pub fn parse_pgtop(args: Vec<&str>) -> Result<AntennaStatus, NmeaParseError> {
+        //! Format: $PGTOP,11,Status*checksum
+        //!
+        //! 11 is the function id of the antenna status.
+        check_address(&args, "PGTOP")?;
+        if get_field(&args, 1, "PGTOP")? != "11" {
+            return Err(NmeaParseError::new("PGTOP", 1, ParseErrorReason::Invalid(args[1].to_string())));
+        }
+        match parse_field::<u8>(&args, 2, "PGTOP")? {
+            Some(value) => Ok(AntennaStatus::from(value)),
+            None => Err(NmeaParseError::new("PGTOP", 2, ParseErrorReason::Missing)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod proprietary_test {
+    mod mtk {
+        use crate::nmea::parse_nmea::{NmeaParseError, ParseErrorReason};
+        use crate::open_gps::gps::{Gps, GpsSentence};
+        use crate::open_gps::sentences::SentenceIter;
+        use crate::proprietary::mtk::{self, Channel, SystemMessage, TextMessage, TrackingStatus};
+
+        #[test]
+        fn system_messages() {
+            assert_eq!(mtk::parse_pmtk010(vec!["$PMTK010", "001"]), Ok(SystemMessage::Startup));
+            assert_eq!(mtk::parse_pmtk010(vec!["$PMTK010", "002"]), Ok(SystemMessage::EpoNotification));
+            assert_eq!(mtk::parse_pmtk010(vec!["$PMTK010", "004"]), Ok(SystemMessage::WakeUp));
+            assert_eq!(mtk::parse_pmtk010(vec!["$PMTK010", "009"]), Ok(SystemMessage::Other(9)));
+            assert_eq!(
+                mtk::parse_pmtk010(vec!["$PMTK010", ""]),
+                Err(NmeaParseError::new("PMTK010", 1, ParseErrorReason::Missing))
+            );
+            assert_eq!(
+                mtk::parse_pmtk010(vec!["$PMTK011", "001"]),
+                Err(NmeaParseError::new("PMTK010", 0, ParseErrorReason::WrongSentence("$PMTK011".to_string())))
+            );
+        }
+
+        #[test]
+        fn text_message() {
+            assert_eq!(mtk::parse_pmtk011(vec!["$PMTK011", "MTKGPS"]), Ok(TextMessage { text: "MTKGPS".to_string() }));
+            assert_eq!(mtk::parse_pmtk011(vec!["$PMTK011", "A", "B"]).unwrap().text, "A,B");
+        }
+
+        #[test]
+        fn channel_status() {
+            let status = mtk::parse_pmtkchn(vec!["$PMTKCHN", "08302", "13271", "", "19300", "138000"]).unwrap();
+            assert_eq!(status.channels.len(), 5);
+            assert_eq!(status.channels[0], Some(Channel { id: 8, snr: 30, status: TrackingStatus::Tracking }));
+            assert_eq!(status.channels[1].map(|channel| channel.status), Some(TrackingStatus::Searching));
+            assert_eq!(status.channels[2], None);
+            assert_eq!(status.channels[4], Some(Channel { id: 138, snr: 0, status: TrackingStatus::Idle }));
+            assert_eq!(status.tracking().count(), 1);
+            assert_eq!(
+                mtk::parse_pmtkchn(vec!["$PMTKCHN", "08302", "1x271"]),
+                Err(NmeaParseError::new("PMTKCHN", 2, ParseErrorReason::Invalid("1x271".to_string())))
+            );
+            assert_eq!(
+                mtk::parse_pmtkchn(vec!["$PMTKCHN", "302"]),
+                Err(NmeaParseError::new("PMTKCHN", 1, ParseErrorReason::Invalid("302".to_string())))
+            );
+        }
+
+        #[test]
+        fn channel_status_from_update() {
+            let bytes = b"$PMTKCHN,08302,13272,19312,27282,07251,10201,00000,00000,00000,00000,00000,00000,00000,00000,00000,00000,00000,00000,00000,00000,00000,00000,00000,00000,00000,00000,00000,00000,00000,00000,00000,00000*4D\r\n";
+            let mut gps = Gps::from_bytes(bytes);
+            let status = gps.sentences().stop_after(1).channel_status().next().unwrap();
+            assert_eq!(status.channels.len(), 32);
+            assert_eq!(status.tracking().map(|channel| channel.id).collect::<Vec<u16>>(), vec![8, 13, 19, 27]);
+        }
+
+        #[test]
+        fn restart_in_band() {
+            let mut gps = Gps::from_bytes(b"$PMTK011,MTKGPS*08\r\n$PMTK010,001*2E\r\n");
+            assert_eq!(gps.update(), GpsSentence::TextMessage(TextMessage { text: "MTKGPS".to_string() }));
+            assert_eq!(gps.update(), GpsSentence::SystemMessage(SystemMessage::Startup));
+        }
+    }
+    mod gtop {
+        use crate::nmea::parse_nmea::{NmeaParseError, ParseErrorReason};
+        use crate::open_gps::gps::{Gps, GpsSentence};
+        use crate::proprietary::gtop::{self, AntennaStatus};
+
+        #[test]
+        fn antenna_status() {
+            assert_eq!(gtop::parse_pgtop(vec!["$PGTOP", "11", "3"]), Ok(AntennaStatus::External));
+            assert_eq!(gtop::parse_pgtop(vec!["$PGTOP", "11", "2"]), Ok(AntennaStatus::Internal));
+            assert!(gtop::parse_pgtop(vec!["$PGTOP", "11", "1"]).unwrap().is_fault());
+            assert!(!AntennaStatus::External.is_fault());
+            assert_eq!(
+                gtop::parse_pgtop(vec!["$PGTOP", "12", "3"]),
+                Err(NmeaParseError::new("PGTOP", 1, ParseErrorReason::Invalid("12".to_string())))
+            );
+            assert_eq!(
+                gtop::parse_pgtop(vec!["$PGTOP", "11"]),
+                Err(NmeaParseError::new("PGTOP", 2, ParseErrorReason::Missing))
+            );
+        }
+
+        #[test]
+        fn antenna_from_update() {
+            let mut gps = Gps::from_bytes(b"$PGTOP,11,1*6D\r\n");
+            assert_eq!(gps.update(), GpsSentence::AntennaStatus(AntennaStatus::Shorted));
+        }
+    }
+}
@@ -0,0 +1,763 @@
+//! # Sentence log
+//! GpsSentence::append_to() and GpsSentence::read_from() save sentences to a log file of
+//! length-prefixed records, so any struct can be read back, whatever bytes its fields encode to.
+//!
+//! The file starts with a header:
+//! - magic -> 8 bytes, `\x89AGPS\r\n\x1a`. The \r\n and \x1a show up a file mangled by text mode.
+//! - version -> u16, [LOG_VERSION](constant.LOG_VERSION.html).
+//!
+//! Then each record, little endian:
+//! - start -> 2 bytes, `\xa5\x5a`. Used to find the next record after a corrupt one.
+//! - length -> u32, the length of the sentence.
+//! - received -> i64, Unix milliseconds when the sentence was received, 0 if not known.
+//! - sentence -> the GpsSentence, bincode encoded.
+//! - crc -> u32, CRC-32 of the length, received and sentence.
+//!
+//! Files from before the header, written by version 0.4.1 as bincode with a \n after each sentence,
+//! can be read with read_legacy() and rewritten with migrate_legacy(). They are decoded with the
+//! structs of 0.4.1, see [legacy](legacy/index.html).
+
+pub mod log {
+    //! LogWriter writes records, read_log() reads them back and reports any that are corrupt.
+    use std::error::Error;
+    use std::fmt;
+    use std::fs::{File, OpenOptions};
+    use std::io::{self, Read, Write};
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    use bincode::Options;
+    use serde::de::DeserializeOwned;
+
+    use crate::open_gps::gps::GpsSentence;
+    use crate::time::utc::UtcDateTime;
+
+    use super::legacy;
+
+    pub const MAGIC: [u8; 8] = *b"\x89AGPS\r\n\x1a";
+    /// Raised whenever GpsSentence, or any struct in it, changes how it is encoded, so an old file
+    /// gives LogError::Version instead of being decoded wrong.
+    pub const LOG_VERSION: u16 = 1;
+    const HEADER_LEN: usize = 10;
+    const RECORD_START: [u8; 2] = [0xa5, 0x5a];
+    /// Start, length and received.
+    const RECORD_HEAD_LEN: usize = 14;
+    const CRC_LEN: usize = 4;
+    /// Longest sentence that will be decoded. No GpsSentence comes near this.
+    const MAX_SENTENCE_LEN: u32 = 1 << 20;
+
+    /// # LogError
+    /// - Io -> Opening, reading or writing the file failed.
+    /// - NotALog -> The file does not start with the magic header. Files written by versions before
+    ///   the header can be read with read_legacy().
+    /// - Version -> The file was written with a different LOG_VERSION.
+    /// - Encode -> The sentence could not be bincode encoded.
+    /// - Unreadable -> Not one record could be read from the file, errors gives where bytes were
+    ///   skipped.
+    #[derive(Debug)]
+    pub enum LogError {
+        Io(io::Error),
+        NotALog,
+        Version(u16),
+        Encode(bincode::Error),
+        Unreadable(Vec<RecordError>),
+    }
+
+    impl fmt::Display for LogError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                LogError::Io(e) => write!(f, "Log io error: {}", e),
+                LogError::NotALog => write!(f, "Not a sentence log, the magic header is missing"),
+                LogError::Version(version) => write!(f, "Log version {} can not be read, expected {}",
+                                                     version, LOG_VERSION),
+                LogError::Encode(e) => write!(f, "Sentence could not be encoded: {}", e),
+                LogError::Unreadable(errors) => write!(f, "No sentence could be read, {} records skipped",
+                                                       errors.len()),
+            }
+        }
+    }
+
+    impl Error for LogError {
+        fn source(&self) -> Option<&(dyn Error + 'static)> {
+            match self {
+                LogError::Io(e) => Some(e),
+                LogError::Encode(e) => Some(e),
+                _ => None,
+            }
+        }
+    }
+
+    impl From<io::Error> for LogError {
+        fn from(e: io::Error) -> LogError {
+            LogError::Io(e)
+        }
+    }
+
+    /// # Record
+    /// - received -> When the sentence was received, None for records migrated from legacy files.
+    /// - sentence -> The sentence.
+    #[derive(Debug, PartialEq, Clone)]
+    pub struct Record {
+        pub received: Option<UtcDateTime>,
+        pub sentence: GpsSentence,
+    }
+
+    /// # RecordError
+    /// Bytes skipped when reading a log. offset is from the start of the file.
+    /// - Corrupt -> The record at offset failed its CRC, or is not a record. skipped bytes were passed
+    ///   over to the next good record.
+    /// - Truncated -> The record at offset runs past the end of the file, probably the last write was
+    ///   cut off.
+    /// - Undecodable -> The record at offset has a good CRC but is not a GpsSentence.
+    #[derive(Debug, PartialEq, Clone, Copy)]
+    pub enum RecordError {
+        Corrupt { offset: u64, skipped: u64 },
+        Truncated { offset: u64 },
+        Undecodable { offset: u64 },
+    }
+
+    impl fmt::Display for RecordError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                RecordError::Corrupt { offset, skipped } => write!(f, "Corrupt record at byte {}, skipped {} bytes",
+                                                                   offset, skipped),
+                RecordError::Truncated { offset } => write!(f, "Truncated record at byte {}", offset),
+                RecordError::Undecodable { offset } => write!(f, "Record at byte {} is not a sentence", offset),
+            }
+        }
+    }
+
+    /// # Log contents
+    /// - records -> Every record that could be read, in file order.
+    /// - errors -> Where bytes were skipped, in file order.
+    #[derive(Debug, PartialEq, Clone, Default)]
+    pub struct LogContents {
+        pub records: Vec<Record>,
+        pub errors: Vec<RecordError>,
+    }
+
+    impl LogContents {
+        /// The sentences without their receive times.
+        pub fn sentences(self) -> Vec<GpsSentence> {
+            self.records.into_iter().map(|record| record.sentence).collect()
+        }
+    }
+
+    /// # LogWriter
+    /// Writes sentences as records to a log file, or any other writer.
+    pub struct LogWriter<W: Write> {
+        writer: W,
+    }
+
+    impl<W: Write> LogWriter<W> {
+        /// Starts a new log, writing the header.
+        pub fn new(mut writer: W) -> Result<LogWriter<W>, LogError> {
+            writer.write_all(&header())?;
+            Ok(LogWriter { writer })
+        }
+
+        /// Writes the sentence, received now.
+        pub fn write(&mut self, sentence: &GpsSentence) -> Result<(), LogError> {
+            let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+            self.write_at(sentence, Some(UtcDateTime::from_unix_millis(now.as_millis() as i64)))
+        }
+
+        /// Writes the sentence with the time it was received.
+        pub fn write_at(&mut self, sentence: &GpsSentence, received: Option<UtcDateTime>) -> Result<(), LogError> {
+            let encoded = bincode::serialize(sentence).map_err(LogError::Encode)?;
+            let mut record = Vec::with_capacity(RECORD_HEAD_LEN + encoded.len() + CRC_LEN);
+            record.extend_from_slice(&RECORD_START);
+            record.extend_from_slice(&(encoded.len() as u32).to_le_bytes());
+            record.extend_from_slice(&received.map_or(0, |time| time.unix_millis()).to_le_bytes());
+            record.extend_from_slice(&encoded);
+            let crc = crc32(&record[RECORD_START.len()..]);
+            record.extend_from_slice(&crc.to_le_bytes());
+            // One write, so a record is only cut off if the write itself is.
+            self.writer.write_all(&record)?;
+            self.writer.flush()?;
+            Ok(())
+        }
+
+        pub fn into_inner(self) -> W {
+            self.writer
+        }
+    }
+
+    impl LogWriter<File> {
+        /// Appends to the log file, making it with a header if it does not exist or is empty.
+        ///
+        /// Gives LogError::NotALog or LogError::Version for a file that is not in this format, rather
+        /// than adding records to it.
+        pub fn open(path: &str) -> Result<LogWriter<File>, LogError> {
+            let mut file = OpenOptions::new().read(true).append(true).create(true).open(path)?;
+            if file.metadata()?.len() == 0 {
+                return LogWriter::new(file);
+            }
+            let mut header = [0; HEADER_LEN];
+            file.read_exact(&mut header).map_err(|_| LogError::NotALog)?;
+            check_header(&header)?;
+            Ok(LogWriter { writer: file })
+        }
+    }
+
+    fn header() -> [u8; HEADER_LEN] {
+        let mut header = [0; HEADER_LEN];
+        header[..MAGIC.len()].copy_from_slice(&MAGIC);
+        header[MAGIC.len()..].copy_from_slice(&LOG_VERSION.to_le_bytes());
+        header
+    }
+
+    fn check_header(bytes: &[u8]) -> Result<(), LogError> {
+        if bytes.len() < HEADER_LEN || bytes[..MAGIC.len()] != MAGIC {
+            return Err(LogError::NotALog);
+        }
+        match u16::from_le_bytes([bytes[8], bytes[9]]) {
+            LOG_VERSION => Ok(()),
+            version => Err(LogError::Version(version)),
+        }
+    }
+
+    /// Reads every record in the log file.
+    pub fn read_log(path: &str) -> Result<LogContents, LogError> {
+        let mut buffer = Vec::new();
+        File::open(path)?.read_to_end(&mut buffer)?;
+        parse_log(&buffer)
+    }
+
+    /// Reads every record in a log, skipping corrupt records and giving where they were in errors.
+    pub fn parse_log(bytes: &[u8]) -> Result<LogContents, LogError> {
+        check_header(bytes)?;
+        let mut contents = LogContents::default();
+        let mut offset = HEADER_LEN;
+        while offset < bytes.len() {
+            match read_record(bytes, offset) {
+                Ok(length) => {
+                    let encoded = &bytes[offset + RECORD_HEAD_LEN..offset + length - CRC_LEN];
+                    match decode(encoded) {
+                        Some((sentence, used)) if used == encoded.len() => {
+                            let millis = i64::from_le_bytes(field(bytes, offset + 6));
+                            let received = match millis {
+                                0 => None,
+                                millis => Some(UtcDateTime::from_unix_millis(millis)),
+                            };
+                            contents.records.push(Record { received, sentence });
+                        }
+                        _ => contents.errors.push(RecordError::Undecodable { offset: offset as u64 }),
+                    }
+                    offset += length;
+                }
+                Err(error) => {
+                    let next = (offset + 1..bytes.len()).find(|next| read_record(bytes, *next).is_ok());
+                    contents.errors.push(match (error, next) {
+                        // Only the last record can be cut off, otherwise its length is corrupt.
+                        (RecordError::Truncated { .. }, None) => RecordError::Truncated { offset: offset as u64 },
+                        (_, next) => RecordError::Corrupt {
+                            offset: offset as u64,
+                            skipped: (next.unwrap_or(bytes.len()) - offset) as u64,
+                        },
+                    });
+                    offset = next.unwrap_or(bytes.len());
+                }
+            }
+        }
+        Ok(contents)
+    }
+
+    /// The length of the good record at offset.
+    fn read_record(bytes: &[u8], offset: usize) -> Result<usize, RecordError> {
+        let corrupt = RecordError::Corrupt { offset: offset as u64, skipped: 0 };
+        let rest = &bytes[offset..];
+        if !rest.starts_with(&RECORD_START) && !RECORD_START.starts_with(rest) {
+            return Err(corrupt);
+        }
+        if rest.len() < RECORD_HEAD_LEN {
+            return Err(RecordError::Truncated { offset: offset as u64 });
+        }
+        let sentence_len = u32::from_le_bytes(field(bytes, offset + 2));
+        if sentence_len > MAX_SENTENCE_LEN {
+            return Err(corrupt);
+        }
+        let length = RECORD_HEAD_LEN + sentence_len as usize + CRC_LEN;
+        if rest.len() < length {
+            return Err(RecordError::Truncated { offset: offset as u64 });
+        }
+        let crc = u32::from_le_bytes(field(bytes, offset + length - CRC_LEN));
+        if crc32(&rest[RECORD_START.len()..length - CRC_LEN]) != crc {
+            return Err(corrupt);
+        }
+        Ok(length)
+    }
+
+    fn field<const N: usize>(bytes: &[u8], offset: usize) -> [u8; N] {
+        let mut field = [0; N];
+        field.copy_from_slice(&bytes[offset..offset + N]);
+        field
+    }
+
+    /// Decodes one sentence from the start of bytes, giving it and the number of bytes used.
+    fn decode<T: DeserializeOwned>(bytes: &[u8]) -> Option<(T, usize)> {
+        let mut reader = bytes;
+        let sentence = bincode::DefaultOptions::new()
+            .with_fixint_encoding()
+            .allow_trailing_bytes()
+            .with_limit(MAX_SENTENCE_LEN as u64)
+            .deserialize_from(&mut reader)
+            .ok()?;
+        Some((sentence, bytes.len() - reader.len()))
+    }
+
+    /// Reads a file written by version 0.4.1, before the header: bincode with a \n after each
+    /// sentence.
+    ///
+    /// Sentences are decoded one after the other with the 0.4.1 structs, rather than split on \n, so
+    /// sentences with a 10 byte in them are kept. Anything that can not be decoded is skipped to the
+    /// next \n. Records have no receive time, and the sentences have Talker::Unknown.
+    pub fn read_legacy(bytes: &[u8]) -> LogContents {
+        let mut contents = LogContents::default();
+        let mut offset = 0;
+        while offset < bytes.len() {
+            match decode::<legacy::GpsSentence>(&bytes[offset..]) {
+                Some((sentence, used)) if bytes.get(offset + used) == Some(&b'\n') => {
+                    contents.records.push(Record { received: None, sentence: sentence.into() });
+                    offset += used + 1;
+                }
+                _ => {
+                    let next = bytes[offset..].iter().position(|b| *b == b'\n')
+                        .map_or(bytes.len(), |newline| offset + newline + 1);
+                    contents.errors.push(RecordError::Corrupt {
+                        offset: offset as u64,
+                        skipped: (next - offset) as u64,
+                    });
+                    offset = next;
+                }
+            }
+        }
+        contents
+    }
+
+    /// Rewrites a file written by versions before the header as a log at new_path, giving what was
+    /// read from it so skipped bytes can be checked. A file that is already a log is copied without
+    /// its corrupt records.
+    pub fn migrate_legacy(old_path: &str, new_path: &str) -> Result<LogContents, LogError> {
+        let mut buffer = Vec::new();
+        File::open(old_path)?.read_to_end(&mut buffer)?;
+        let contents = match parse_log(&buffer) {
+            Err(LogError::NotALog) => read_legacy(&buffer),
+            contents => contents?,
+        };
+        let mut writer = LogWriter::new(File::create(new_path)?)?;
+        for record in contents.records.iter() {
+            writer.write_at(&record.sentence, record.received)?;
+        }
+        Ok(contents)
+    }
+
+    /// CRC-32 as used by zip and PNG.
+    pub fn crc32(bytes: &[u8]) -> u32 {
+        let mut crc = !0u32;
+        for byte in bytes {
+            crc ^= *byte as u32;
+            for _ in 0..8 {
+                crc = if crc & 1 == 1 { (crc >> 1) ^ 0xedb8_8320 } else { crc >> 1 };
+            }
+        }
+        !crc
+    }
+}
+
+pub mod legacy {
+    //! The structs as saved by append_to() in version 0.4.1, before the log header.
+    //!
+    //! bincode saves no field names or types, only the enum variant index and the field values in
+    //! order. The structs have changed since (UtcTime, talkers, new sentences), so the old bytes are
+    //! decoded with these frozen copies and converted. Do not change them.
+    use serde::Deserialize;
+
+    use crate::nmea::gsv::SkyView;
+    use crate::nmea::talker::Talker;
+    use crate::nmea::{gga, gll, gsa, gsv, rmc, vtg};
+    use crate::open_gps::gps;
+    use crate::time::utc::{UtcDate, UtcTime};
+
+    #[derive(Debug, Deserialize)]
+    #[allow(clippy::upper_case_acronyms)]
+    pub enum GpsSentence {
+        GGA(GgaData),
+        VTG(VtgData),
+        GSA(GsaData),
+        GSV(Vec<Satellites>),
+        GLL(GllData),
+        RMC(RmcData),
+        NoConnection,
+        InvalidBytes,
+        InvalidSentence,
+    }
+
+    #[derive(Debug, Deserialize)]
+    #[allow(clippy::enum_variant_names)]
+    pub enum SatFix {
+        NoFix,
+        GpsFix,
+        DgpsFix,
+    }
+
+    /// - utc -> hhmmss.sss, 0.0 when the gps gave no time.
+    #[derive(Debug, Deserialize)]
+    pub struct GgaData {
+        pub utc: f64,
+        pub lat: Option<f32>,
+        pub long: Option<f32>,
+        pub sat_fix: SatFix,
+        pub satellites_used: i32,
+        pub hdop: Option<f32>,
+        pub msl_alt: Option<f32>,
+        pub geoidal_sep: Option<f32>,
+        pub age_diff_corr: Option<f32>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    pub enum GsaMode {
+        Manual,
+        Automatic,
+    }
+
+    #[derive(Debug, Deserialize)]
+    pub enum DimensionFix {
+        NotAvailable,
+        Dimension2d,
+        Dimension3d,
+    }
+
+    #[derive(Debug, Deserialize)]
+    pub struct GsaData {
+        pub mode: GsaMode,
+        pub dimension_fix: DimensionFix,
+        /// sat1 to sat12.
+        pub sats: [Option<i32>; 12],
+        pub pdop: Option<f32>,
+        pub hdop: Option<f32>,
+        pub vdop: Option<f32>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    pub struct Satellites {
+        pub id: Option<i32>,
+        pub elevation: Option<f32>,
+        pub azimuth: Option<f32>,
+        pub snr: Option<f32>,
+    }
+
+    /// - date -> ddmmyy, empty when the gps gave no date.
+    #[derive(Debug, Deserialize)]
+    pub struct RmcData {
+        pub utc: f64,
+        pub fix_status: bool,
+        pub latitude: Option<f32>,
+        pub longitude: Option<f32>,
+        pub speed: Option<f32>,
+        pub course: Option<f32>,
+        pub date: String,
+        pub mag_var: Option<f32>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    pub enum VtgMode {
+        Autonomous,
+        Differential,
+        Estimated,
+        Unknown,
+    }
+
+    #[derive(Debug, Deserialize)]
+    pub struct VtgData {
+        pub true_course: Option<f32>,
+        pub magnetic_course: Option<f32>,
+        pub speed_knots: Option<f32>,
+        pub speed_kph: Option<f32>,
+        pub mode: VtgMode,
+    }
+
+    #[derive(Debug, Deserialize)]
+    pub struct GllData {
+        pub latitude: Option<f32>,
+        pub longitude: Option<f32>,
+        pub utc: Option<f64>,
+        pub is_valid: bool,
+    }
+
+    /// hhmmss.sss as a UtcTime. 0.4.1 gave 0.0 for a GGA or RMC without a time, which can not be
+    /// told from midnight, so it is read as 00:00:00.
+    fn utc(time: f64) -> Option<UtcTime> {
+        UtcTime::parse(&format!("{:010.3}", time))
+    }
+
+    /// The talker was not saved, so every sentence has Talker::Unknown.
+    impl From<GpsSentence> for gps::GpsSentence {
+        fn from(sentence: GpsSentence) -> gps::GpsSentence {
+            match sentence {
+                GpsSentence::GGA(data) => gps::GpsSentence::GGA(gga::GgaData {
+                    utc: utc(data.utc),
+                    lat: data.lat,
+                    long: data.long,
+                    sat_fix: match data.sat_fix {
+                        SatFix::NoFix => gga::SatFix::NoFix,
+                        SatFix::GpsFix => gga::SatFix::GpsFix,
+                        SatFix::DgpsFix => gga::SatFix::DgpsFix,
+                    },
+                    satellites_used: data.satellites_used,
+                    hdop: data.hdop,
+                    msl_alt: data.msl_alt,
+                    geoidal_sep: data.geoidal_sep,
+                    age_diff_corr: data.age_diff_corr,
+                    talker: Talker::Unknown,
+                }),
+                GpsSentence::VTG(data) => gps::GpsSentence::VTG(vtg::VtgData {
+                    true_course: data.true_course,
+                    magnetic_course: data.magnetic_course,
+                    speed_knots: data.speed_knots,
+                    speed_kph: data.speed_kph,
+                    mode: match data.mode {
+                        VtgMode::Autonomous => vtg::Mode::Autonomous,
+                        VtgMode::Differential => vtg::Mode::Differential,
+                        VtgMode::Estimated => vtg::Mode::Estimated,
+                        VtgMode::Unknown => vtg::Mode::Unknown,
+                    },
+                    talker: Talker::Unknown,
+                }),
+                GpsSentence::GSA(data) => {
+                    let [sat1, sat2, sat3, sat4, sat5, sat6, sat7, sat8, sat9, sat10, sat11, sat12] = data.sats;
+                    gps::GpsSentence::GSA(gsa::GsaData {
+                        mode: match data.mode {
+                            GsaMode::Manual => gsa::Mode::Manual,
+                            GsaMode::Automatic => gsa::Mode::Automatic,
+                        },
+                        dimension_fix: match data.dimension_fix {
+                            DimensionFix::NotAvailable => gsa::DimensionFix::NotAvailable,
+                            DimensionFix::Dimension2d => gsa::DimensionFix::Dimension2d,
+                            DimensionFix::Dimension3d => gsa::DimensionFix::Dimension3d,
+                        },
+                        sat1, sat2, sat3, sat4, sat5, sat6, sat7, sat8, sat9, sat10, sat11, sat12,
+                        pdop: data.pdop,
+                        hdop: data.hdop,
+                        vdop: data.vdop,
+                        talker: Talker::Unknown,
+                    })
+                }
+                GpsSentence::GSV(satellites) => gps::GpsSentence::GSV(SkyView {
+                    talker: Talker::Unknown,
+                    in_view: satellites.len() as u8,
+                    satellites: satellites.into_iter().map(|satellite| gsv::Satellites {
+                        id: satellite.id,
+                        elevation: satellite.elevation,
+                        azimuth: satellite.azimuth,
+                        snr: satellite.snr,
+                        talker: Talker::Unknown,
+                    }).collect(),
+                }),
+                GpsSentence::GLL(data) => gps::GpsSentence::GLL(gll::GllData {
+                    latitude: data.latitude,
+                    longitude: data.longitude,
+                    utc: data.utc.and_then(utc),
+                    is_valid: data.is_valid,
+                    talker: Talker::Unknown,
+                }),
+                GpsSentence::RMC(data) => gps::GpsSentence::RMC(rmc::RmcData {
+                    utc: utc(data.utc),
+                    fix_status: data.fix_status,
+                    latitude: data.latitude,
+                    longitude: data.longitude,
+                    speed: data.speed,
+                    course: data.course,
+                    date: UtcDate::parse(&data.date),
+                    mag_var: data.mag_var,
+                    talker: Talker::Unknown,
+                }),
+                GpsSentence::NoConnection => gps::GpsSentence::NoConnection,
+                GpsSentence::InvalidBytes => gps::GpsSentence::InvalidBytes,
+                GpsSentence::InvalidSentence => gps::GpsSentence::InvalidSentence,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod log_test {
+    use std::fs::{remove_file, write};
+
+    use crate::nmea::gga::{GgaData, SatFix};
+    use crate::nmea::talker::Talker;
+    use crate::open_gps::gps::GpsSentence;
+    use crate::time::utc::{UtcDateTime, UtcTime};
+
+    use super::log::{crc32, migrate_legacy, parse_log, read_legacy, read_log, LogError, LogWriter, Record,
+                     RecordError, MAGIC};
+
+    /// The latitude encodes with a 10 byte, so was dropped by the old reader that split on \n.
+    const SENTENCE: GpsSentence = GpsSentence::GGA(GgaData {
+        utc: Some(UtcTime { hour: 13, minute: 16, second: 13, millisecond: 0 }),
+        lat: Some(48.51),
+        long: Some(-0.09849),
+        sat_fix: SatFix::GpsFix,
+        satellites_used: 9,
+        hdop: Some(1.17),
+        msl_alt: Some(42.4),
+        geoidal_sep: Some(47.0),
+        age_diff_corr: None,
+        talker: Talker::Multi,
+    });
+
+    fn received() -> Option<UtcDateTime> {
+        Some(UtcDateTime::from_unix_millis(1_590_412_573_250))
+    }
+
+    /// The header, then the records for SENTENCE, NoConnection and SENTENCE.
+    fn log() -> (Vec<u8>, Vec<Vec<u8>>) {
+        let header = LogWriter::new(Vec::new()).unwrap().into_inner();
+        let records = [SENTENCE, GpsSentence::NoConnection, SENTENCE].iter().map(|sentence| {
+            let mut writer = LogWriter::new(Vec::new()).unwrap();
+            writer.write_at(sentence, received()).unwrap();
+            writer.into_inner()[header.len()..].to_vec()
+        }).collect();
+        (header, records)
+    }
+
+    fn records(sentences: &[GpsSentence], received: Option<UtcDateTime>) -> Vec<Record> {
+        sentences.iter().map(|sentence| Record { received, sentence: sentence.clone() }).collect()
+    }
+
+    #[test]
+    fn crc() {
+        assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+        assert_eq!(crc32(b""), 0);
+    }
+
+    #[test]
+    fn read_records() {
+        assert!(bincode::serialize(&SENTENCE).unwrap().contains(&b'\n'));
+        let (header, records) = log();
+        assert_eq!(&header[..8], &MAGIC);
+        let contents = parse_log(&[header, records.concat()].concat()).unwrap();
+        assert_eq!(contents.errors, vec![]);
+        assert_eq!(contents.records, self::records(&[SENTENCE, GpsSentence::NoConnection, SENTENCE], received()));
+    }
+
+    #[test]
+    fn skip_corrupt_record() {
+        let (header, mut records) = log();
+        let offset = (header.len() + records[0].len()) as u64;
+        records[1][20] ^= 0xff;
+        let contents = parse_log(&[header, records.concat()].concat()).unwrap();
+        assert_eq!(contents.records, self::records(&[SENTENCE, SENTENCE], received()));
+        assert_eq!(contents.errors, vec![RecordError::Corrupt { offset, skipped: records[1].len() as u64 }]);
+
+        // Garbage between records, and a length that runs past the end of the file.
+        let (header, mut records) = log();
+        records[0][3] = 0xff;
+        let bytes = [header.clone(), b"\xa5\x5a junk".to_vec(), records.concat()].concat();
+        let contents = parse_log(&bytes).unwrap();
+        assert_eq!(contents.records, self::records(&[GpsSentence::NoConnection, SENTENCE], received()));
+        assert_eq!(contents.errors, vec![RecordError::Corrupt {
+            offset: header.len() as u64,
+            skipped: (7 + records[0].len()) as u64,
+        }]);
+    }
+
+    #[test]
+    fn truncated_last_record() {
+        let (header, records) = log();
+        let bytes = [header.clone(), records.concat()].concat();
+        let contents = parse_log(&bytes[..bytes.len() - 3]).unwrap();
+        assert_eq!(contents.records, self::records(&[SENTENCE, GpsSentence::NoConnection], received()));
+        let offset = (bytes.len() - records[2].len()) as u64;
+        assert_eq!(contents.errors, vec![RecordError::Truncated { offset }]);
+    }
+
+    #[test]
+    fn bad_header() {
+        assert!(matches!(parse_log(b"$GNGGA,131613.000"), Err(LogError::NotALog)));
+        assert!(matches!(parse_log(&MAGIC), Err(LogError::NotALog)));
+        assert!(matches!(parse_log(&[&MAGIC[..], &[2, 0]].concat()), Err(LogError::Version(2))));
+    }
+
+    /// Written by GpsSentence::append_to in version 0.4.1: GGA, NoConnection, InvalidSentence, GSA,
+    /// GSV, RMC, VTG, GLL and InvalidBytes, parsed from the sentences of one epoch.
+    const LEGACY: &[u8] = include_bytes!("../tests/data/legacy-0.4.1.gps");
+
+    #[test]
+    fn legacy() {
+        let contents = read_legacy(LEGACY);
+        assert_eq!(contents.errors, vec![]);
+        assert!(contents.records.iter().all(|record| record.received.is_none()));
+        let sentences = contents.clone().sentences();
+        assert_eq!(sentences.len(), 9);
+        assert_eq!(sentences[0], GpsSentence::GGA(GgaData {
+            utc: Some(UtcTime { hour: 13, minute: 16, second: 13, millisecond: 0 }),
+            lat: Some(51.545525),
+            long: Some(-0.098498),
+            sat_fix: SatFix::GpsFix,
+            satellites_used: 9,
+            hdop: Some(1.17),
+            msl_alt: Some(42.4),
+            geoidal_sep: Some(47.0),
+            age_diff_corr: None,
+            talker: Talker::Unknown,
+        }));
+        assert_eq!(sentences[1..3], [GpsSentence::NoConnection, GpsSentence::InvalidSentence]);
+        match &sentences[3] {
+            GpsSentence::GSA(gsa) => assert_eq!((gsa.sat6, gsa.sat7, gsa.vdop), (Some(14), None, Some(0.8))),
+            other => panic!("Expected GSA, got {:?}", other),
+        }
+        match &sentences[4] {
+            GpsSentence::GSV(view) => assert_eq!((view.in_view, view.satellites[3].snr), (4, Some(22.0))),
+            other => panic!("Expected GSV, got {:?}", other),
+        }
+        match &sentences[5] {
+            GpsSentence::RMC(rmc) => assert_eq!(rmc.datetime().map(|datetime| datetime.unix_millis()),
+                                                Some(1_590_412_573_000)),
+            other => panic!("Expected RMC, got {:?}", other),
+        }
+        assert!(matches!(&sentences[6], GpsSentence::VTG(vtg) if vtg.speed_kph == Some(0.2)));
+        assert!(matches!(&sentences[7], GpsSentence::GLL(gll) if gll.utc == UtcTime::parse("131613.000")));
+        assert_eq!(sentences[8], GpsSentence::InvalidBytes);
+
+        // Junk is skipped to the next \n.
+        let bytes = [LEGACY, b"junk\n", LEGACY].concat();
+        let read = read_legacy(&bytes);
+        assert_eq!(read.records.len(), 18);
+        assert_eq!(read.errors, vec![RecordError::Corrupt { offset: LEGACY.len() as u64, skipped: 5 }]);
+
+        write("legacy_test", LEGACY).unwrap();
+        assert!(matches!(LogWriter::open("legacy_test"), Err(LogError::NotALog)));
+        let read_from = GpsSentence::read_from("legacy_test");
+        let migrated = migrate_legacy("legacy_test", "migrated_test");
+        let read = read_log("migrated_test");
+        let _ = remove_file("legacy_test");
+        let _ = remove_file("migrated_test");
+        assert_eq!(read_from.unwrap(), sentences);
+        assert_eq!(migrated.unwrap(), contents);
+        assert_eq!(read.unwrap().records, contents.records);
+    }
+
+    #[test]
+    fn unreadable_file() {
+        write("unreadable_test", b"not a log\nor a 0.4.1 file\n").unwrap();
+        let read = GpsSentence::read_from("unreadable_test");
+        let _ = remove_file("unreadable_test");
+        match read {
+            Err(LogError::Unreadable(errors)) => assert_eq!(errors.len(), 2),
+            other => panic!("Expected Unreadable, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn append_to_file() {
+        let _ = remove_file("append_test");
+        let write = SENTENCE.append_to("append_test").and_then(|_| SENTENCE.append_to("append_test"));
+        let read = read_log("append_test");
+        let _ = remove_file("append_test");
+        write.unwrap();
+        let read = read.unwrap();
+        assert_eq!(read.errors, vec![]);
+        assert_eq!(read.records.len(), 2);
+        assert!(read.records.iter().all(|record| record.sentence == SENTENCE && record.received.is_some()));
+    }
+}
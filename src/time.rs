@@ -0,0 +1,477 @@
+//! # UTC time
+//! Typed UTC time and date given by the gps, replacing the raw hhmmss.sss floats and ddmmyy strings.
+//!
+//! - UtcTime -> hh:mm:ss.sss, given by GGA, RMC and GLL.
+//! - UtcDate -> yyyy-mm-dd, given by RMC only.
+//! - UtcDateTime -> Both, with conversion to Unix time and GPS week and time of week.
+//! - DateTracker -> Pairs a time with the last RMC date, moving on a day at midnight.
+//!
+//! With the `chrono` feature each type converts into its chrono equivalent.
+
+pub mod utc {
+    //! UtcTime, UtcDate, UtcDateTime and DateTracker.
+    use std::fmt;
+    use std::str::FromStr;
+
+    use serde::{Deserialize, Serialize};
+
+    use crate::nmea::parse_nmea::ParseErrorReason;
+
+    const SECONDS_PER_DAY: i64 = 86_400;
+    const MILLIS_PER_DAY: i64 = SECONDS_PER_DAY * 1_000;
+    const SECONDS_PER_WEEK: i64 = 604_800;
+    /// Unix time of the GPS epoch: 1980-01-06 00:00:00 UTC.
+    const GPS_EPOCH_UNIX: i64 = 315_964_800;
+
+    /// Dates (year, month, day) from which GPS time is one more second ahead of UTC.
+    /// GPS time does not have leap seconds, UTC does.
+    const LEAP_SECONDS: [(u16, u8, u8); 18] = [
+        (1981, 7, 1), (1982, 7, 1), (1983, 7, 1), (1985, 7, 1), (1988, 1, 1), (1990, 1, 1),
+        (1991, 1, 1), (1992, 7, 1), (1993, 7, 1), (1994, 7, 1), (1996, 1, 1), (1997, 7, 1),
+        (1999, 1, 1), (2006, 1, 1), (2009, 1, 1), (2012, 7, 1), (2015, 7, 1), (2017, 1, 1),
+    ];
+
+    /// # UTC time of day
+    /// - hour -> 0-23
+    /// - minute -> 0-59
+    /// - second -> 0-60, 60 only for a leap second.
+    /// - millisecond -> 0-999
+    #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy, Default, Serialize, Deserialize)]
+    pub struct UtcTime {
+        pub hour: u8,
+        pub minute: u8,
+        pub second: u8,
+        pub millisecond: u16,
+    }
+
+    impl UtcTime {
+        /// Parses hhmmss or hhmmss.sss, the format given by the gps.
+        pub fn parse(time: &str) -> Option<UtcTime> {
+            if !time.is_ascii() || time.len() < 6 || !time[..6].bytes().all(|b| b.is_ascii_digit()) {
+                return None;
+            }
+            let millisecond = match &time[6..] {
+                "" => 0,
+                fraction => {
+                    let digits = fraction.strip_prefix('.')?;
+                    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+                        return None;
+                    }
+                    // Only the first 3 digits: .5 -> 500, .123456 -> 123
+                    let digits = format!("{:0<3}", &digits[..digits.len().min(3)]);
+                    digits.parse().ok()?
+                }
+            };
+            let time = UtcTime {
+                hour: time[0..2].parse().ok()?,
+                minute: time[2..4].parse().ok()?,
+                second: time[4..6].parse().ok()?,
+                millisecond,
+            };
+            if time.hour > 23 || time.minute > 59 || time.second > 60 {
+                return None;
+            }
+            Some(time)
+        }
+
+        /// Milliseconds since midnight.
+        pub fn millis_of_day(&self) -> u32 {
+            ((self.hour as u32 * 60 + self.minute as u32) * 60 + self.second as u32) * 1_000
+                + self.millisecond as u32
+        }
+
+        /// Seconds since midnight.
+        pub fn seconds_of_day(&self) -> f64 {
+            self.millis_of_day() as f64 / 1_000.0
+        }
+
+        /// Time from milliseconds since midnight. Whole days are ignored.
+        pub fn from_millis_of_day(millis: i64) -> UtcTime {
+            let millis = millis.rem_euclid(MILLIS_PER_DAY);
+            UtcTime {
+                hour: (millis / 3_600_000) as u8,
+                minute: (millis / 60_000 % 60) as u8,
+                second: (millis / 1_000 % 60) as u8,
+                millisecond: (millis % 1_000) as u16,
+            }
+        }
+
+        /// The time as given by the gps: hhmmss.sss
+        pub fn to_nmea(&self) -> String {
+            format!("{:02}{:02}{:02}.{:03}", self.hour, self.minute, self.second, self.millisecond)
+        }
+
+        /// Seconds from an earlier time to this time. Assumes less than a day between them, so a
+        /// time after midnight is the next day: 00:00:01 is 2 seconds since 23:59:59.
+        pub fn seconds_since(&self, earlier: &UtcTime) -> f64 {
+            let millis = (self.millis_of_day() as i64 - earlier.millis_of_day() as i64).rem_euclid(MILLIS_PER_DAY);
+            millis as f64 / 1_000.0
+        }
+    }
+
+    impl FromStr for UtcTime {
+        type Err = ParseErrorReason;
+
+        fn from_str(s: &str) -> Result<UtcTime, ParseErrorReason> {
+            UtcTime::parse(s).ok_or_else(|| ParseErrorReason::Invalid(s.to_string()))
+        }
+    }
+
+    impl fmt::Display for UtcTime {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "{:02}:{:02}:{:02}.{:03}", self.hour, self.minute, self.second, self.millisecond)
+        }
+    }
+
+    /// # UTC date
+    /// - year -> Full year, such as 2020.
+    /// - month -> 1-12
+    /// - day -> 1-31
+    #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy, Serialize, Deserialize)]
+    pub struct UtcDate {
+        pub year: u16,
+        pub month: u8,
+        pub day: u8,
+    }
+
+    impl Default for UtcDate {
+        /// The GPS epoch, 1980-01-06.
+        fn default() -> UtcDate {
+            UtcDate { year: 1980, month: 1, day: 6 }
+        }
+    }
+
+    impl UtcDate {
+        /// Parses ddmmyy, the format given by RMC. yy of 80-99 is 1980-1999, otherwise 2000-2079.
+        pub fn parse(date: &str) -> Option<UtcDate> {
+            if date.len() != 6 || !date.bytes().all(|b| b.is_ascii_digit()) {
+                return None;
+            }
+            let year: u16 = date[4..6].parse().ok()?;
+            UtcDate::from_ymd(
+                if year >= 80 { 1900 + year } else { 2000 + year },
+                date[2..4].parse().ok()?,
+                date[0..2].parse().ok()?,
+            )
+        }
+
+        /// The date, if it is a real date. None for a month of 13 or the 30th of February.
+        pub fn from_ymd(year: u16, month: u8, day: u8) -> Option<UtcDate> {
+            if month == 0 || month > 12 || day == 0 || day > days_in_month(year, month) {
+                return None;
+            }
+            Some(UtcDate { year, month, day })
+        }
+
+        /// The date as given by RMC: ddmmyy
+        pub fn to_nmea(&self) -> String {
+            format!("{:02}{:02}{:02}", self.day, self.month, self.year % 100)
+        }
+
+        /// Days since 1970-01-01.
+        pub fn days_since_unix_epoch(&self) -> i64 {
+            // Howard Hinnant's days_from_civil.
+            let year = self.year as i64 - if self.month <= 2 { 1 } else { 0 };
+            let era = year.div_euclid(400);
+            let year_of_era = year - era * 400;
+            let month = self.month as i64;
+            let day_of_year = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + self.day as i64 - 1;
+            let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+            era * 146_097 + day_of_era - 719_468
+        }
+
+        /// Date from days since 1970-01-01.
+        pub fn from_days_since_unix_epoch(days: i64) -> UtcDate {
+            // Howard Hinnant's civil_from_days.
+            let days = days + 719_468;
+            let era = days.div_euclid(146_097);
+            let day_of_era = days - era * 146_097;
+            let year_of_era = (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+            let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+            let mp = (5 * day_of_year + 2) / 153;
+            let day = day_of_year - (153 * mp + 2) / 5 + 1;
+            let month = if mp < 10 { mp + 3 } else { mp - 9 };
+            let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+            UtcDate { year: year as u16, month: month as u8, day: day as u8 }
+        }
+
+        pub fn next_day(&self) -> UtcDate {
+            UtcDate::from_days_since_unix_epoch(self.days_since_unix_epoch() + 1)
+        }
+
+        pub fn previous_day(&self) -> UtcDate {
+            UtcDate::from_days_since_unix_epoch(self.days_since_unix_epoch() - 1)
+        }
+    }
+
+    // is_multiple_of needs Rust 1.87.
+    #[allow(clippy::manual_is_multiple_of)]
+    fn days_in_month(year: u16, month: u8) -> u8 {
+        match month {
+            4 | 6 | 9 | 11 => 30,
+            2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+            2 => 28,
+            _ => 31,
+        }
+    }
+
+    impl FromStr for UtcDate {
+        type Err = ParseErrorReason;
+
+        fn from_str(s: &str) -> Result<UtcDate, ParseErrorReason> {
+            UtcDate::parse(s).ok_or_else(|| ParseErrorReason::Invalid(s.to_string()))
+        }
+    }
+
+    impl fmt::Display for UtcDate {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+        }
+    }
+
+    /// # GPS time
+    /// - week -> Weeks since the GPS epoch, 1980-01-06. Not rolled over at 1024.
+    /// - tow -> Time of week, seconds since the start of Sunday, in GPS time (UTC + leap seconds).
+    #[derive(Debug, PartialEq, Clone, Copy, Default, Serialize, Deserialize)]
+    pub struct GpsTime {
+        pub week: u32,
+        pub tow: f64,
+    }
+
+    /// # UTC date and time
+    #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy, Default, Serialize, Deserialize)]
+    pub struct UtcDateTime {
+        pub date: UtcDate,
+        pub time: UtcTime,
+    }
+
+    impl UtcDateTime {
+        pub fn new(date: UtcDate, time: UtcTime) -> UtcDateTime {
+            UtcDateTime { date, time }
+        }
+
+        /// Milliseconds since 1970-01-01 00:00:00 UTC, ignoring leap seconds like Unix time.
+        pub fn unix_millis(&self) -> i64 {
+            self.date.days_since_unix_epoch() * MILLIS_PER_DAY + self.time.millis_of_day() as i64
+        }
+
+        /// Seconds since 1970-01-01 00:00:00 UTC, ignoring leap seconds like Unix time.
+        pub fn unix_timestamp(&self) -> f64 {
+            self.unix_millis() as f64 / 1_000.0
+        }
+
+        pub fn from_unix_millis(millis: i64) -> UtcDateTime {
+            UtcDateTime {
+                date: UtcDate::from_days_since_unix_epoch(millis.div_euclid(MILLIS_PER_DAY)),
+                time: UtcTime::from_millis_of_day(millis),
+            }
+        }
+
+        /// GPS week and time of week. GPS time is ahead of UTC by the leap seconds since 1980.
+        pub fn gps_time(&self) -> GpsTime {
+            let gps_millis = self.unix_millis() + leap_seconds(&self.date) * 1_000 - GPS_EPOCH_UNIX * 1_000;
+            GpsTime {
+                week: gps_millis.div_euclid(SECONDS_PER_WEEK * 1_000) as u32,
+                tow: gps_millis.rem_euclid(SECONDS_PER_WEEK * 1_000) as f64 / 1_000.0,
+            }
+        }
+
+        /// UTC from GPS week and time of week.
+        pub fn from_gps_time(gps_time: GpsTime) -> UtcDateTime {
+            let gps_millis = gps_time.week as i64 * SECONDS_PER_WEEK * 1_000 + (gps_time.tow * 1_000.0).round() as i64;
+            let unix_millis = gps_millis + GPS_EPOCH_UNIX * 1_000;
+            // The leap seconds are those at the UTC date, which is at most a few seconds earlier.
+            let estimate = UtcDateTime::from_unix_millis(unix_millis);
+            let leap = leap_seconds(&UtcDateTime::from_unix_millis(unix_millis - leap_seconds(&estimate.date) * 1_000).date);
+            UtcDateTime::from_unix_millis(unix_millis - leap * 1_000)
+        }
+
+        /// Seconds from an earlier date time to this one.
+        pub fn seconds_since(&self, earlier: &UtcDateTime) -> f64 {
+            (self.unix_millis() - earlier.unix_millis()) as f64 / 1_000.0
+        }
+    }
+
+    impl fmt::Display for UtcDateTime {
+        /// ISO 8601: 2020-05-25T13:16:13.000Z
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "{}T{}Z", self.date, self.time)
+        }
+    }
+
+    /// Seconds GPS time is ahead of UTC on the date.
+    pub fn leap_seconds(date: &UtcDate) -> i64 {
+        LEAP_SECONDS.iter()
+            .filter(|(year, month, day)| *date >= UtcDate { year: *year, month: *month, day: *day })
+            .count() as i64
+    }
+
+    /// # DateTracker
+    /// Only RMC gives the date. DateTracker keeps the last RMC date so times from GGA and GLL can be
+    /// given a date, moving on a day when the time goes past midnight.
+    #[derive(Debug, Default, Clone)]
+    pub struct DateTracker {
+        last: Option<UtcDateTime>,
+    }
+
+    impl DateTracker {
+        pub fn new() -> DateTracker {
+            DateTracker::default()
+        }
+
+        /// Sets the date and time from an RMC sentence.
+        pub fn set(&mut self, date: UtcDate, time: UtcTime) {
+            self.last = Some(UtcDateTime::new(date, time));
+        }
+
+        /// Gives the date time of a time given after the last RMC. None if no date has been given.
+        ///
+        /// A time more than 12 hours before the last time is taken as the next day, and more than 12
+        /// hours after as the day before, such as a sentence from before midnight given late.
+        pub fn datetime(&mut self, time: UtcTime) -> Option<UtcDateTime> {
+            let last = self.last?;
+            let difference = time.millis_of_day() as i64 - last.time.millis_of_day() as i64;
+            let date = if difference < -MILLIS_PER_DAY / 2 {
+                last.date.next_day()
+            } else if difference > MILLIS_PER_DAY / 2 {
+                last.date.previous_day()
+            } else {
+                last.date
+            };
+            let datetime = UtcDateTime::new(date, time);
+            if datetime > last {
+                self.last = Some(datetime);
+            }
+            Some(datetime)
+        }
+    }
+
+    #[cfg(feature = "chrono")]
+    mod to_chrono {
+        use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+
+        use super::{UtcDate, UtcDateTime, UtcTime};
+
+        impl From<UtcTime> for NaiveTime {
+            /// A leap second, 60, is given as chrono's leap second: 59 with 1000+ milliseconds.
+            fn from(time: UtcTime) -> NaiveTime {
+                let (second, millisecond) = if time.second == 60 {
+                    (59, time.millisecond as u32 + 1_000)
+                } else {
+                    (time.second as u32, time.millisecond as u32)
+                };
+                NaiveTime::from_hms_milli_opt(time.hour as u32, time.minute as u32, second, millisecond)
+                    .unwrap_or_default()
+            }
+        }
+
+        impl From<UtcDate> for NaiveDate {
+            fn from(date: UtcDate) -> NaiveDate {
+                NaiveDate::from_ymd_opt(date.year as i32, date.month as u32, date.day as u32).unwrap_or_default()
+            }
+        }
+
+        impl From<UtcDateTime> for DateTime<Utc> {
+            fn from(datetime: UtcDateTime) -> DateTime<Utc> {
+                NaiveDateTime::new(datetime.date.into(), datetime.time.into()).and_utc()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod utc_test {
+    use super::utc::{DateTracker, GpsTime, UtcDate, UtcDateTime, UtcTime};
+
+    fn time(hour: u8, minute: u8, second: u8, millisecond: u16) -> UtcTime {
+        UtcTime { hour, minute, second, millisecond }
+    }
+
+    fn date(year: u16, month: u8, day: u8) -> UtcDate {
+        UtcDate { year, month, day }
+    }
+
+    #[test]
+    fn parse_time() {
+        assert_eq!(UtcTime::parse("131613.000"), Some(time(13, 16, 13, 0)));
+        assert_eq!(UtcTime::parse("235959.5"), Some(time(23, 59, 59, 500)));
+        assert_eq!(UtcTime::parse("000000"), Some(time(0, 0, 0, 0)));
+        assert_eq!(UtcTime::parse("120000.123456"), Some(time(12, 0, 0, 123)));
+        assert_eq!(UtcTime::parse("235960.000"), Some(time(23, 59, 60, 0)));
+        assert_eq!(UtcTime::parse("246000.000"), None);
+        assert_eq!(UtcTime::parse("1316"), None);
+        assert_eq!(UtcTime::parse("131613."), None);
+        assert_eq!(UtcTime::parse("13161é"), None);
+        assert_eq!(time(13, 16, 13, 50).to_string(), "13:16:13.050");
+    }
+
+    #[test]
+    fn parse_date() {
+        assert_eq!(UtcDate::parse("250520"), Some(date(2020, 5, 25)));
+        assert_eq!(UtcDate::parse("060180"), Some(date(1980, 1, 6)));
+        assert_eq!(UtcDate::parse("290220"), Some(date(2020, 2, 29)));
+        assert_eq!(UtcDate::parse("290221"), None);
+        assert_eq!(UtcDate::parse("321220"), None);
+        assert_eq!(UtcDate::parse("2505"), None);
+        assert_eq!(date(2020, 5, 25).to_string(), "2020-05-25");
+    }
+
+    #[test]
+    fn time_since_over_midnight() {
+        assert_eq!(time(0, 0, 1, 0).seconds_since(&time(23, 59, 59, 0)), 2.0);
+        assert_eq!(time(13, 17, 0, 0).seconds_since(&time(13, 16, 59, 500)), 0.5);
+        assert_eq!(time(14, 0, 0, 0).seconds_since(&time(13, 59, 0, 0)), 60.0);
+    }
+
+    #[test]
+    fn days() {
+        assert_eq!(date(1970, 1, 1).days_since_unix_epoch(), 0);
+        assert_eq!(date(2000, 3, 1).days_since_unix_epoch(), 11_017);
+        assert_eq!(UtcDate::from_days_since_unix_epoch(18_407), date(2020, 5, 25));
+        assert_eq!(date(2020, 2, 28).next_day(), date(2020, 2, 29));
+        assert_eq!(date(2020, 12, 31).next_day(), date(2021, 1, 1));
+        assert_eq!(date(2021, 3, 1).previous_day(), date(2021, 2, 28));
+    }
+
+    #[test]
+    fn unix_timestamp() {
+        let datetime = UtcDateTime::new(date(2020, 5, 25), time(13, 16, 13, 250));
+        assert_eq!(datetime.unix_timestamp(), 1_590_412_573.25);
+        assert_eq!(UtcDateTime::from_unix_millis(1_590_412_573_250), datetime);
+        assert_eq!(datetime.to_string(), "2020-05-25T13:16:13.250Z");
+    }
+
+    #[test]
+    fn gps_week_and_time_of_week() {
+        // Start of the GPS epoch.
+        let epoch = UtcDateTime::new(date(1980, 1, 6), time(0, 0, 0, 0));
+        assert_eq!(epoch.gps_time(), GpsTime { week: 0, tow: 0.0 });
+        // Monday 2020-05-25 13:16:13 UTC, 18 leap seconds.
+        let datetime = UtcDateTime::new(date(2020, 5, 25), time(13, 16, 13, 0));
+        let gps_time = datetime.gps_time();
+        assert_eq!(gps_time, GpsTime { week: 2107, tow: 86_400.0 + 47_773.0 + 18.0 });
+        assert_eq!(UtcDateTime::from_gps_time(gps_time), datetime);
+    }
+
+    #[test]
+    fn midnight_rollover() {
+        let mut tracker = DateTracker::new();
+        assert_eq!(tracker.datetime(time(23, 59, 59, 0)), None);
+        tracker.set(date(2020, 12, 31), time(23, 59, 59, 0));
+        assert_eq!(tracker.datetime(time(23, 59, 59, 500)).map(|d| d.date), Some(date(2020, 12, 31)));
+        assert_eq!(tracker.datetime(time(0, 0, 0, 0)).map(|d| d.date), Some(date(2021, 1, 1)));
+        // A late sentence from before midnight.
+        assert_eq!(tracker.datetime(time(23, 59, 59, 900)).map(|d| d.date), Some(date(2020, 12, 31)));
+        assert_eq!(tracker.datetime(time(0, 0, 1, 0)).map(|d| d.date), Some(date(2021, 1, 1)));
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn to_chrono() {
+        use chrono::{DateTime, Utc};
+
+        let datetime = UtcDateTime::new(date(2020, 5, 25), time(13, 16, 13, 250));
+        let chrono: DateTime<Utc> = datetime.into();
+        assert_eq!(chrono.timestamp_millis(), datetime.unix_millis());
+    }
+}